@@ -0,0 +1,2007 @@
+// App state, actions and the update function
+//
+// Everything that can happen in the TUI (a key press or click, a network
+// result, a timer tick) is turned into an `Action`. `App::update` applies an
+// action to the state and returns any `Task`s (network calls, quitting,
+// ...) for the main loop to run. Nothing in here touches the terminal or the network, so
+// the whole app can be driven from tests by feeding it actions.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{layout::Position, prelude::*, widgets::*};
+
+use crate::colors::ComindColors;
+use crate::components::{
+    compose::ComposeView,
+    conflicts::ConflictView,
+    drafts::DraftsView,
+    following::FollowingView,
+    help::render_help,
+    palette::{Entry, PaletteView, Target},
+    pings::PingsView,
+    profile::ProfileView,
+    search::SearchView,
+    spinner,
+    status::{render_status, Connection, Status},
+    stream::StreamView,
+    thoughts::ThoughtsView,
+    whoami::render_whoami,
+    Component, Panes,
+};
+use crate::config::{Config, LIST_WIDTHS};
+use crate::keys::{Command, Context, Key, Keymap, Lookup};
+use crate::ui::{mode_strings, modes, next_mode, prev_mode, UIMode};
+use crate::worker::{JobId, Request};
+use chrono::{DateTime, Utc};
+use comode::drafts::Draft;
+use comode::editor::Document;
+use comode::errors::{ApiError, ApiResult};
+use comode::filter::{ThoughtFilter, Toggle};
+use comode::follow::{Feed, Following, PushOutcome};
+use comode::outbox::OutboxItem;
+use comode::profile::{Profile, Who};
+use comode::store::Cached;
+use comode::sync::{apply_edits, Edit, Resolution, SyncOutcome, SyncState};
+use comode::types::{Ping, Thought};
+use comode::utils::datetime_to_relative;
+use comode::vault::ExportReport;
+
+///
+/// Everything that can change the app state.
+///
+pub enum Action {
+    // App-wide
+    Quit,
+    Suspend,
+    Logout,
+    Tick,
+    NextTab,
+    PrevTab,
+    // A tab was clicked
+    ShowTab(UIMode),
+    // The preview next to lists, and how wide the list is
+    TogglePreview,
+    WiderList,
+    NarrowerList,
+    ToggleWhoami,
+    ToggleHelp,
+    OpenPalette,
+    Refresh,
+    // Restart comode with another profile from the config
+    SwitchProfile(String),
+
+    // Navigation inside a view
+    Up,
+    Down,
+    Top,
+    Bottom,
+    Select,
+    Back,
+    Search,
+
+    // Mouse: the wheel over a list or a thought, clicking a row to open
+    // it, and clicking a link
+    ScrollUp,
+    ScrollDown,
+    ScrollDetailUp,
+    ScrollDetailDown,
+    Click(usize),
+    OpenLink(String),
+
+    // Compose
+    Compose {
+        public: bool,
+    },
+    Submit,
+    // Write in $EDITOR. With `review`, the result goes back to the composer
+    // instead of being sent.
+    OpenEditor {
+        review: bool,
+    },
+    EditorClosed {
+        result: std::io::Result<Option<Document>>,
+        review: bool,
+    },
+
+    // The row under the thoughts cursor: edit a thought, or edit or
+    // discard one still in the outbox
+    Edit,
+    Discard,
+
+    // Filter and sort the thoughts tab
+    Filter(Toggle),
+
+    // Drafts
+    ShowDrafts,
+
+    // Someone's profile: whoever wrote the row under the cursor, or anyone
+    ShowProfile,
+    OpenProfile(Who),
+    // Follow or unfollow whoever wrote the row under the cursor
+    ToggleFollow,
+    MarkPingsRead,
+    // Write every page of thoughts to a folder as Markdown
+    Export(String),
+
+    // Sync conflicts
+    ShowConflicts,
+    Resolve(Resolution),
+    EditMerge,
+
+    // Text entry for whichever view has an input focused
+    Input(KeyEvent),
+
+    // Network results
+    ThoughtsLoaded {
+        job: JobId,
+        thoughts: ApiResult<Vec<Thought>>,
+    },
+    Synced {
+        job: JobId,
+        outcome: ApiResult<SyncOutcome>,
+    },
+    PingsLoaded {
+        job: JobId,
+        pings: ApiResult<Vec<Ping>>,
+    },
+    ProfileLoaded {
+        job: JobId,
+        profile: ApiResult<Profile>,
+    },
+    FeedLoaded {
+        job: JobId,
+        feed: Feed,
+    },
+    FollowsPushed {
+        job: JobId,
+        outcome: PushOutcome,
+    },
+    OutboxSent {
+        job: JobId,
+        local_id: Uuid,
+        result: ApiResult<()>,
+    },
+    Exported {
+        job: JobId,
+        dir: String,
+        result: Result<(usize, ExportReport), String>,
+    },
+}
+
+///
+/// Side effects asked for by views and actions.
+///
+/// Views ask for network calls with `Request`; `App::update` gives each one
+/// a job id and turns it into `Task::Spawn` for the worker. New thoughts go
+/// through the outbox with `Enqueue`, and `SaveOutbox` writes it to disk.
+/// Edits to sent thoughts go through sync with `SaveEdit` and `SaveSync`.
+///
+#[derive(Debug, PartialEq)]
+pub enum Effect {
+    Quit,
+    Suspend,
+    Logout,
+    SwitchProfile(String),
+    Enqueue(OutboxItem),
+    SaveOutbox(Vec<OutboxItem>),
+    SaveEdit(Edit),
+    SaveSync(SyncState),
+    SaveDrafts(Vec<Draft>),
+    SaveFilter(ThoughtFilter),
+    SaveFollowing(Following),
+    SavePingsRead(DateTime<Utc>),
+    OpenEditor { document: Document, review: bool },
+    OpenLink(String),
+    Request(Request),
+    Cancel(JobId),
+}
+
+///
+/// What `update` leaves for the main loop to run: the effects, once
+/// requests, queued thoughts and edits have been scheduled.
+///
+#[derive(Debug, PartialEq)]
+pub enum Task {
+    Quit,
+    Suspend,
+    Logout,
+    SwitchProfile(String),
+    SaveOutbox(Vec<OutboxItem>),
+    SaveSync(SyncState),
+    SaveDrafts(Vec<Draft>),
+    SaveFilter(ThoughtFilter),
+    SaveFollowing(Following),
+    SavePingsRead(DateTime<Utc>),
+    OpenEditor { document: Document, review: bool },
+    OpenLink(String),
+    Spawn(JobId, Request),
+    Cancel(JobId),
+}
+
+///
+/// Kinds of background work, one of each can be in flight at a time.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Load {
+    Thoughts,
+    Pings,
+    Profile,
+    Feed,
+    Follow,
+    Send,
+    Export,
+}
+
+impl Load {
+    fn of(request: &Request) -> Load {
+        match request {
+            Request::FetchThoughts | Request::Sync(_) => Load::Thoughts,
+            Request::FetchPings => Load::Pings,
+            Request::FetchProfile(_) => Load::Profile,
+            Request::FetchFeed(_) => Load::Feed,
+            Request::PushFollows(_) => Load::Follow,
+            Request::Send(_) => Load::Send,
+            Request::Export(_) => Load::Export,
+        }
+    }
+}
+
+// The data a view shows, so its loads can be cancelled when you leave it
+fn view_loads(mode: UIMode) -> &'static [Load] {
+    match mode {
+        UIMode::Thoughts | UIMode::Stream | UIMode::Search => &[Load::Thoughts],
+        UIMode::Pings => &[Load::Pings],
+        UIMode::Profile => &[Load::Profile],
+        UIMode::Following => &[Load::Feed],
+        UIMode::ThinkPublic | UIMode::ThinkPrivate | UIMode::Conflicts | UIMode::Drafts => &[],
+    }
+}
+
+///
+/// Data shared between views.
+///
+pub struct Data {
+    pub username: String,
+    pub session: Vec<(&'static str, String)>,
+    pub thoughts: Vec<Thought>,
+    // The thoughts as the server sent them, before local edits
+    pub server_thoughts: Vec<Thought>,
+    pub pings: Vec<Ping>,
+
+    // Thoughts waiting to be sent, shown above the thoughts list
+    pub outbox: Vec<OutboxItem>,
+    // The outbox item being sent right now
+    pub sending: Option<Uuid>,
+    // Edits not on the server yet, and conflicts to resolve
+    pub sync: SyncState,
+    // Autosaved compose buffers
+    pub drafts: Vec<Draft>,
+
+    // Work in flight, for spinners
+    pub loading: HashMap<Load, JobId>,
+    pub ticks: usize,
+
+    // Data shown from the local cache that hasn't been refreshed yet, with
+    // when it was fetched
+    pub cached_at: HashMap<Load, DateTime<Utc>>,
+    // The last request couldn't reach the server
+    pub offline: bool,
+
+    pub theme: ComindColors,
+    pub panes: Panes,
+
+    // What the thoughts tab shows, and in what order
+    pub filter: ThoughtFilter,
+
+    // The profile being looked at
+    pub profile: Option<Profile>,
+
+    // Who you follow, and what they've written lately
+    pub following: Following,
+    pub feed: Vec<Thought>,
+
+    // Pings up to this time were marked read here
+    pub pings_read: Option<DateTime<Utc>>,
+}
+
+impl Data {
+    ///
+    /// Sent thoughts as the thoughts tab shows them, filtered and sorted.
+    ///
+    pub fn filtered_thoughts(&self) -> Vec<&Thought> {
+        self.filter.apply(&self.thoughts)
+    }
+
+    // Show pings marked read here as read
+    fn apply_pings_read(&mut self) {
+        if let Some(read) = self.pings_read {
+            for ping in self.pings.iter_mut().filter(|ping| ping.created_at <= read) {
+                ping.read_status = true;
+            }
+        }
+    }
+}
+
+///
+/// The TUI state.
+///
+pub struct App {
+    pub mode: UIMode,
+    pub data: Data,
+    pub show_whoami: bool,
+    pub show_help: bool,
+
+    // Transient message, cleared after a few ticks
+    pub message: Option<String>,
+    message_ticks: u32,
+
+    // Mode to return to when leaving compose
+    previous_mode: UIMode,
+    // And when leaving a profile
+    profile_from: UIMode,
+
+    // Background jobs
+    next_job: JobId,
+    refreshed: HashMap<Load, Instant>,
+    pub refresh_interval: Duration,
+
+    // Settings from the config file
+    keymap: Keymap,
+    // Keys pressed so far of a chord like `g g`
+    chord: Vec<Key>,
+    default_public: bool,
+    profile: String,
+    profiles: Vec<String>,
+    message_limit: u32,
+    autosave_ticks: usize,
+
+    // Views
+    pub thoughts: ThoughtsView,
+    pub pings: PingsView,
+    pub search: SearchView,
+    pub stream: StreamView,
+    pub following: FollowingView,
+    pub compose: ComposeView,
+    pub conflicts: ConflictView,
+    pub drafts: DraftsView,
+    pub user_profile: ProfileView,
+    pub palette: PaletteView,
+}
+
+// How long a message stays on screen
+const MESSAGE_TIME: Duration = Duration::from_secs(3);
+
+impl App {
+    pub fn new(username: &str, session: Vec<(&'static str, String)>, config: &Config) -> App {
+        // Timers count ticks, one per poll
+        let ticks =
+            |duration: Duration| (duration.as_millis() / config.poll().as_millis().max(1)).max(1);
+
+        App {
+            mode: UIMode::Thoughts,
+            data: Data {
+                username: username.to_string(),
+                session,
+                thoughts: Vec::new(),
+                server_thoughts: Vec::new(),
+                pings: Vec::new(),
+                outbox: Vec::new(),
+                sending: None,
+                sync: SyncState::default(),
+                drafts: Vec::new(),
+                loading: HashMap::new(),
+                ticks: 0,
+                cached_at: HashMap::new(),
+                offline: false,
+                // The theme was checked when the config was loaded
+                theme: config.theme().unwrap_or_default(),
+                panes: Panes::new(&config.layout),
+                filter: ThoughtFilter::default(),
+                profile: None,
+                following: Following::default(),
+                feed: Vec::new(),
+                pings_read: None,
+            },
+            show_whoami: false,
+            show_help: false,
+            message: None,
+            message_ticks: 0,
+            previous_mode: UIMode::Thoughts,
+            profile_from: UIMode::Thoughts,
+            next_job: 0,
+            refreshed: HashMap::new(),
+            refresh_interval: config.refresh_interval(),
+            // The keymap was checked when the config was loaded
+            keymap: config.keymap().unwrap_or_default(),
+            chord: Vec::new(),
+            default_public: config.default_public(),
+            profile: config.profile.clone(),
+            profiles: config.profile_names(),
+            message_limit: ticks(MESSAGE_TIME) as u32,
+            autosave_ticks: ticks(config.autosave()) as usize,
+            thoughts: ThoughtsView::default(),
+            pings: PingsView::default(),
+            search: SearchView::default(),
+            stream: StreamView::default(),
+            following: FollowingView::default(),
+            compose: ComposeView::default(),
+            conflicts: ConflictView::default(),
+            drafts: DraftsView::default(),
+            user_profile: ProfileView::default(),
+            palette: PaletteView::default(),
+        }
+    }
+
+    ///
+    /// Show cached data until the first refresh comes back.
+    ///
+    pub fn load_cache(
+        &mut self,
+        thoughts: Option<Cached<Vec<Thought>>>,
+        pings: Option<Cached<Vec<Ping>>>,
+        feed: Option<Cached<Vec<Thought>>>,
+    ) {
+        if let Some(cached) = thoughts {
+            self.set_thoughts(cached.items);
+            self.data
+                .cached_at
+                .insert(Load::Thoughts, cached.fetched_at);
+        }
+        if let Some(cached) = pings {
+            self.data.pings = cached.items;
+            self.data.apply_pings_read();
+            self.data.cached_at.insert(Load::Pings, cached.fetched_at);
+        }
+        if let Some(cached) = feed {
+            self.data.feed = cached.items;
+            self.data.cached_at.insert(Load::Feed, cached.fetched_at);
+        }
+    }
+
+    ///
+    /// Pick up thoughts that weren't sent last time.
+    ///
+    pub fn load_outbox(&mut self, outbox: std::io::Result<Vec<OutboxItem>>) {
+        match outbox {
+            Ok(outbox) => self.data.outbox = outbox,
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    ///
+    /// Pick up edits and conflicts from last time.
+    ///
+    pub fn load_sync(&mut self, state: std::io::Result<SyncState>) {
+        match state {
+            Ok(state) => {
+                self.data.sync = state;
+                apply_edits(&mut self.data.thoughts, &self.data.sync.edits);
+            }
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    ///
+    /// Pick up the thoughts filter from last time.
+    ///
+    pub fn load_filter(&mut self, filter: Option<ThoughtFilter>) {
+        self.data.filter = filter.unwrap_or_default();
+    }
+
+    ///
+    /// Pick up who you follow.
+    ///
+    pub fn load_following(&mut self, following: std::io::Result<Following>) {
+        match following {
+            Ok(following) => self.data.following = following,
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    ///
+    /// Pick up when pings were last marked read.
+    ///
+    pub fn load_pings_read(&mut self, read: Option<DateTime<Utc>>) {
+        self.data.pings_read = read;
+        self.data.apply_pings_read();
+    }
+
+    ///
+    /// Pick up drafts from last time, and offer them if there are any.
+    ///
+    pub fn load_drafts(&mut self, drafts: std::io::Result<Vec<Draft>>) {
+        match drafts {
+            Ok(drafts) => self.data.drafts = drafts,
+            Err(e) => self.set_message(&e.to_string()),
+        }
+
+        if !self.data.drafts.is_empty() {
+            self.drafts.recovered = true;
+            self.mode = UIMode::Drafts;
+        }
+    }
+
+    // The view for the current mode
+    fn view(&self) -> &dyn Component {
+        match self.mode {
+            UIMode::Thoughts => &self.thoughts,
+            UIMode::Pings => &self.pings,
+            UIMode::Search => &self.search,
+            UIMode::Stream => &self.stream,
+            UIMode::Following => &self.following,
+            UIMode::ThinkPublic | UIMode::ThinkPrivate => &self.compose,
+            UIMode::Conflicts => &self.conflicts,
+            UIMode::Drafts => &self.drafts,
+            UIMode::Profile => &self.user_profile,
+        }
+    }
+
+    ///
+    /// Turn a key press into an action.
+    ///
+    /// Bindings are looked up for the current view's context, waiting for
+    /// the rest of a chord if need be. Keys that aren't bound go to the view,
+    /// so it can take typing.
+    ///
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        // Always available
+        match key.code {
+            KeyCode::Char('c') if ctrl => return Some(Action::Quit),
+            KeyCode::Char('z') if ctrl => return Some(Action::Suspend),
+            _ => {}
+        }
+
+        // The whoami overlay swallows everything but its own keys
+        if self.show_whoami {
+            return match self.keymap.get(Context::Global, key) {
+                Some(Command::Whoami | Command::Back) => Some(Action::ToggleWhoami),
+                Some(Command::Logout) => Some(Action::Logout),
+                Some(Command::Quit) => Some(Action::Quit),
+                _ => None,
+            };
+        }
+
+        // So do the key help and the palette
+        if self.show_help {
+            return match self.keymap.get(self.view().context(), key) {
+                Some(Command::Help | Command::EditorHelp | Command::Back | Command::Cancel) => {
+                    Some(Action::ToggleHelp)
+                }
+                Some(Command::Quit) => Some(Action::Quit),
+                _ => None,
+            };
+        }
+        if self.palette.open {
+            return self.palette.handle_key(key);
+        }
+
+        // Bindings from the config, which may take more than one key
+        self.chord.push(Key::from_event(key));
+        match self.keymap.lookup(self.view().context(), &self.chord) {
+            Lookup::Command(command) => {
+                self.chord.clear();
+                return Some(command.action(self.default_public));
+            }
+            Lookup::Pending => return None,
+            Lookup::None => {}
+        }
+
+        // A key that breaks off a chord counts on its own
+        if self.chord.len() > 1 {
+            self.chord.clear();
+            return self.handle_key(key);
+        }
+        self.chord.clear();
+
+        // Keys the view handles itself, like typing
+        self.view().handle_key(key)
+    }
+
+    ///
+    /// Apply an action, returning the tasks to run.
+    ///
+    pub fn update(&mut self, action: Action) -> Vec<Task> {
+        let mode = self.mode;
+        let mut effects = self.reduce(action);
+
+        // Leaving a view cancels loads only it was waiting on
+        if self.mode != mode {
+            effects.extend(self.cancel_hidden(mode));
+        }
+
+        self.schedule(effects)
+    }
+
+    fn reduce(&mut self, action: Action) -> Vec<Effect> {
+        match action {
+            // The palette has the keys while it's open
+            Action::Select if self.palette.open => {
+                self.palette.hide();
+                if let Some(target) = self.palette.selected() {
+                    return self.reduce(target.action(self.default_public));
+                }
+            }
+            Action::Back if self.palette.open => {
+                self.palette.hide();
+            }
+            Action::Up | Action::Down | Action::Input(_) if self.palette.open => {
+                self.palette.update(&action, &self.data);
+            }
+
+            // Whatever is being written is saved on the way out
+            Action::Quit => return self.autosave().into_iter().chain([Effect::Quit]).collect(),
+            Action::Suspend => {
+                return self
+                    .autosave()
+                    .into_iter()
+                    .chain([Effect::Suspend])
+                    .collect()
+            }
+            Action::Logout => {
+                return self
+                    .autosave()
+                    .into_iter()
+                    .chain([Effect::Logout])
+                    .collect()
+            }
+            Action::SwitchProfile(profile) => {
+                return self
+                    .autosave()
+                    .into_iter()
+                    .chain([Effect::SwitchProfile(profile)])
+                    .collect()
+            }
+            Action::Refresh => {
+                self.set_message("refreshing...");
+                let mut effects = vec![
+                    Effect::Request(self.thoughts_request()),
+                    Effect::Request(Request::FetchPings),
+                ];
+                if let (UIMode::Profile, Some(who)) = (self.mode, &self.user_profile.who) {
+                    effects.push(Effect::Request(Request::FetchProfile(who.clone())));
+                }
+                effects.extend(self.feed_request());
+                effects.extend(self.push_follows());
+                return effects;
+            }
+            Action::Tick => {
+                self.data.ticks = self.data.ticks.wrapping_add(1);
+                if self.message.is_some() {
+                    self.message_ticks += 1;
+                    if self.message_ticks > self.message_limit {
+                        self.message = None;
+                    }
+                }
+
+                // Background refresh, outbox retries and draft autosave
+                let mut effects = self.stale_loads();
+                effects.extend(self.due_send());
+                if self.data.ticks.is_multiple_of(self.autosave_ticks) {
+                    effects.extend(self.autosave());
+                }
+                return effects;
+            }
+            Action::NextTab => {
+                self.mode = next_mode(self.mode);
+                return self.stale_loads();
+            }
+            Action::PrevTab => {
+                self.mode = prev_mode(self.mode);
+                return self.stale_loads();
+            }
+            Action::ShowTab(mode) => {
+                self.mode = mode;
+                return self.stale_loads();
+            }
+            Action::TogglePreview => {
+                self.data.panes.preview = !self.data.panes.preview;
+                match self.data.panes.preview {
+                    true => self.set_message("preview on, when the window is wide enough"),
+                    false => self.set_message("preview off"),
+                }
+            }
+            Action::WiderList | Action::NarrowerList => {
+                let width = match action {
+                    Action::WiderList => self.data.panes.list_width + 5,
+                    _ => self.data.panes.list_width.saturating_sub(5),
+                };
+                self.data.panes.list_width = width.clamp(*LIST_WIDTHS.start(), *LIST_WIDTHS.end());
+                self.set_message(&format!(
+                    "list is {}% of the width",
+                    self.data.panes.list_width
+                ));
+            }
+            Action::Click(row) => {
+                // The view moves its cursor to the row, then it opens like enter
+                let mut effects = self.update_view(&Action::Click(row));
+                effects.extend(self.reduce(Action::Select));
+                return effects;
+            }
+            Action::OpenLink(url) => {
+                self.set_message(&format!("opening {}", url));
+                return vec![Effect::OpenLink(url)];
+            }
+            Action::ToggleWhoami => {
+                self.show_whoami = !self.show_whoami;
+            }
+            Action::ToggleHelp => {
+                self.show_help = !self.show_help;
+            }
+            Action::OpenPalette => {
+                let entries = self.palette_entries();
+                self.palette.show(entries);
+            }
+            Action::Search => {
+                self.mode = UIMode::Search;
+                self.search.editing = true;
+                self.search.open = false;
+            }
+            Action::Compose { public } => {
+                if !self.mode.is_compose() {
+                    self.previous_mode = self.mode;
+                }
+                self.mode = if public {
+                    UIMode::ThinkPublic
+                } else {
+                    UIMode::ThinkPrivate
+                };
+                self.compose.public = public;
+            }
+            Action::Back if self.mode.is_compose() => {
+                self.leave_compose();
+            }
+            Action::Back if self.mode == UIMode::Conflicts || self.mode == UIMode::Drafts => {
+                self.drafts.recovered = false;
+                self.mode = UIMode::Thoughts;
+            }
+            // Only the composer has text to continue in the editor
+            Action::OpenEditor { review: true } if !self.mode.is_compose() => {}
+            Action::OpenEditor { review } => {
+                // The composer's text, when reviewing it there afterwards
+                let document = if review {
+                    Document::new(
+                        &self.compose.title_text(),
+                        &self.compose.raw_body(),
+                        self.compose.public,
+                    )
+                } else {
+                    Document::new("", "", self.default_public)
+                };
+                return vec![Effect::OpenEditor { document, review }];
+            }
+            Action::EditorClosed { result, review } => {
+                let document = match result {
+                    Ok(Some(document)) => document,
+                    Ok(None) => {
+                        self.set_message("nothing written, cancelled");
+                        return Vec::new();
+                    }
+                    Err(e) => {
+                        self.set_message(&format!("editor: {}", e));
+                        return Vec::new();
+                    }
+                };
+
+                if !review {
+                    self.set_message("sending...");
+                    return self.enqueue(OutboxItem::new(
+                        &document.title,
+                        &document.body,
+                        document.public,
+                    ));
+                }
+
+                // Back in the composer, still editing the same thought if it was
+                let editing = self.compose.editing.take();
+                let draft_id = self.compose.draft_id;
+                self.compose
+                    .edit(&document.title, &document.body, document.public);
+                self.compose.editing = editing;
+                self.compose.draft_id = draft_id;
+                self.mode = if document.public {
+                    UIMode::ThinkPublic
+                } else {
+                    UIMode::ThinkPrivate
+                };
+                return self.autosave().into_iter().collect();
+            }
+            Action::Filter(_) if self.mode != UIMode::Thoughts => {
+                self.set_message("filters are for the thoughts tab");
+            }
+            Action::Filter(toggle) => {
+                self.data.filter.toggle(toggle);
+                self.thoughts.cursor = 0;
+                self.thoughts.scroll = 0;
+                return vec![Effect::SaveFilter(self.data.filter.clone())];
+            }
+            Action::ShowDrafts => {
+                self.drafts.cursor = 0;
+                self.drafts.recovered = false;
+                self.mode = UIMode::Drafts;
+            }
+            Action::ShowProfile => match self.view().author(&self.data) {
+                Some(who) => return self.reduce(Action::OpenProfile(who)),
+                None => self.set_message("no one to show here"),
+            },
+            Action::OpenProfile(who) => {
+                if self.mode != UIMode::Profile {
+                    self.profile_from = self.mode;
+                }
+                self.mode = UIMode::Profile;
+                self.user_profile = ProfileView {
+                    who: Some(who.clone()),
+                    ..ProfileView::default()
+                };
+                self.data.profile = None;
+
+                // Whoever was being looked up before isn't wanted now
+                let mut effects = Vec::new();
+                if let Some(job) = self.data.loading.remove(&Load::Profile) {
+                    effects.push(Effect::Cancel(job));
+                }
+                effects.push(Effect::Request(Request::FetchProfile(who)));
+                return effects;
+            }
+            Action::ToggleFollow => {
+                let username = match self.view().author(&self.data) {
+                    Some(Who::Name(username)) => username,
+                    Some(Who::Id(_)) => {
+                        self.set_message("open their profile with u to follow them");
+                        return Vec::new();
+                    }
+                    None => {
+                        self.set_message("no one to follow here");
+                        return Vec::new();
+                    }
+                };
+                if username == self.data.username {
+                    self.set_message("that's you");
+                    return Vec::new();
+                }
+
+                if self.data.following.toggle(&username) {
+                    self.set_message(&format!("following {}", username));
+                } else {
+                    self.set_message(&format!("stopped following {}", username));
+                    self.data
+                        .feed
+                        .retain(|thought| thought.username != username);
+                    self.following.cursor = self
+                        .following
+                        .cursor
+                        .min(self.data.feed.len().saturating_sub(1));
+                }
+                let mut effects = vec![Effect::SaveFollowing(self.data.following.clone())];
+                effects.extend(self.push_follows());
+                effects.extend(self.feed_request());
+                return effects;
+            }
+            Action::MarkPingsRead => {
+                let unread = self
+                    .data
+                    .pings
+                    .iter()
+                    .filter(|ping| !ping.read_status)
+                    .count();
+                if unread == 0 {
+                    self.set_message("no unread pings");
+                    return Vec::new();
+                }
+
+                let read = Utc::now();
+                self.data.pings_read = Some(read);
+                self.data.apply_pings_read();
+                self.set_message(&format!("marked {} read", plural(unread, "ping")));
+                return vec![Effect::SavePingsRead(read)];
+            }
+            Action::Export(dir) => {
+                self.set_message("exporting...");
+                return vec![Effect::Request(Request::Export(dir))];
+            }
+            Action::Back if self.mode == UIMode::Profile && !self.user_profile.open => {
+                self.mode = self.profile_from;
+            }
+            Action::Select if self.mode == UIMode::Drafts => {
+                let draft = match self.data.drafts.get(self.drafts.cursor) {
+                    Some(draft) => draft.clone(),
+                    None => return Vec::new(),
+                };
+
+                // Keep what's in the composer before replacing it
+                let effects = self.autosave().into_iter().collect();
+                self.compose.resume(&draft);
+                self.drafts.recovered = false;
+                self.previous_mode = UIMode::Thoughts;
+                self.mode = if draft.public {
+                    UIMode::ThinkPublic
+                } else {
+                    UIMode::ThinkPrivate
+                };
+                return effects;
+            }
+            Action::Discard if self.mode == UIMode::Drafts => {
+                if self.drafts.cursor >= self.data.drafts.len() {
+                    return Vec::new();
+                }
+                let draft = self.data.drafts.remove(self.drafts.cursor);
+                if self.compose.draft_id == Some(draft.id) {
+                    self.compose.draft_id = None;
+                }
+                self.drafts.cursor = self
+                    .drafts
+                    .cursor
+                    .min(self.data.drafts.len().saturating_sub(1));
+                self.set_message("draft deleted");
+                return vec![Effect::SaveDrafts(self.data.drafts.clone())];
+            }
+            Action::Edit => {
+                if let Some(thought) = self.thought_under_cursor() {
+                    let title = thought.title.clone().unwrap_or_default();
+                    self.compose.edit_thought(&thought, &title, &thought.body);
+                    self.previous_mode = self.mode;
+                    self.mode = if thought.public {
+                        UIMode::ThinkPublic
+                    } else {
+                        UIMode::ThinkPrivate
+                    };
+                    return Vec::new();
+                }
+
+                let item = match self.pending_under_cursor() {
+                    Some(item) => item,
+                    None => return Vec::new(),
+                };
+                self.compose.edit(&item.title, &item.body, item.public);
+                self.previous_mode = self.mode;
+                self.mode = if item.public {
+                    UIMode::ThinkPublic
+                } else {
+                    UIMode::ThinkPrivate
+                };
+                return self.remove_pending(item.local_id);
+            }
+            Action::Discard => {
+                let item = match self.pending_under_cursor() {
+                    Some(item) => item,
+                    None => return Vec::new(),
+                };
+                self.set_message("discarded");
+                return self.remove_pending(item.local_id);
+            }
+            Action::ShowConflicts => {
+                if self.data.sync.conflicts.is_empty() {
+                    self.set_message("no conflicts");
+                } else {
+                    self.conflicts.cursor = 0;
+                    self.mode = UIMode::Conflicts;
+                }
+            }
+            Action::Resolve(_) | Action::EditMerge if self.mode != UIMode::Conflicts => {}
+            Action::Resolve(resolution) => {
+                if self.conflicts.cursor >= self.data.sync.conflicts.len() {
+                    return Vec::new();
+                }
+                let conflict = self.data.sync.conflicts.remove(self.conflicts.cursor);
+                if let Some(thought) = self
+                    .data
+                    .server_thoughts
+                    .iter_mut()
+                    .find(|thought| thought.id == conflict.remote.id)
+                {
+                    *thought = conflict.remote.clone();
+                }
+                if let Some(edit) = conflict.resolve(resolution) {
+                    self.data.sync.edits.push(edit);
+                }
+                self.show_edits();
+
+                if self.data.sync.conflicts.is_empty() {
+                    self.mode = UIMode::Thoughts;
+                }
+                self.conflicts.cursor = self
+                    .conflicts
+                    .cursor
+                    .min(self.data.sync.conflicts.len().saturating_sub(1));
+                return vec![
+                    Effect::SaveSync(self.data.sync.clone()),
+                    Effect::Request(self.thoughts_request()),
+                ];
+            }
+            Action::EditMerge => {
+                let conflict = match self.data.sync.conflicts.get(self.conflicts.cursor) {
+                    Some(conflict) => conflict.clone(),
+                    None => return Vec::new(),
+                };
+
+                // Saving the merge as an edit of their version settles it
+                let (title, merge) = conflict.merge();
+                self.compose.edit_thought(
+                    &conflict.remote,
+                    &title.unwrap_or_default(),
+                    &merge.text,
+                );
+                self.previous_mode = self.mode;
+                self.mode = if conflict.remote.public {
+                    UIMode::ThinkPublic
+                } else {
+                    UIMode::ThinkPrivate
+                };
+                if merge.conflicted {
+                    self.set_message("fix the lines between the conflict markers, then ctrl-s");
+                }
+            }
+            Action::ThoughtsLoaded { job, thoughts } => {
+                if self.finish(Load::Thoughts, job) {
+                    match thoughts {
+                        Ok(thoughts) => {
+                            self.set_thoughts(thoughts);
+                            self.loaded(Load::Thoughts);
+
+                            // Edits made while this was in flight
+                            if !self.data.sync.edits.is_empty() {
+                                return vec![Effect::Request(self.thoughts_request())];
+                            }
+                        }
+                        Err(e) => self.load_failed("thoughts", e),
+                    }
+                }
+            }
+            Action::Synced { job, outcome } => {
+                if !self.finish(Load::Thoughts, job) {
+                    return Vec::new();
+                }
+                let outcome = match outcome {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        self.load_failed("thoughts", e);
+                        return Vec::new();
+                    }
+                };
+
+                // Thoughts whose revision moved on since they were shown,
+                // other than by the edits just pushed
+                let changed = outcome
+                    .thoughts
+                    .iter()
+                    .filter(|thought| !outcome.settled.iter().any(|edit| edit.id == thought.id))
+                    .filter(|thought| {
+                        self.data.server_thoughts.iter().any(|shown| {
+                            shown.id == thought.id && shown.revision != thought.revision
+                        })
+                    })
+                    .count();
+
+                outcome.apply_to(&mut self.data.sync);
+                self.set_thoughts(outcome.thoughts.clone());
+                self.loaded(Load::Thoughts);
+
+                if !self.data.sync.conflicts.is_empty() {
+                    self.set_message(&format!(
+                        "{} changed on the server too, press c to resolve",
+                        plural(self.data.sync.conflicts.len(), "thought")
+                    ));
+                } else if let Some((_, e)) = outcome.errors.first() {
+                    self.set_message(&format!("couldn't sync an edit: {}", e));
+                } else if !outcome.settled.is_empty() {
+                    self.set_message("edits synced");
+                } else if changed > 0 {
+                    self.set_message(&format!(
+                        "{} changed on the server since they were loaded",
+                        plural(changed, "thought")
+                    ));
+                }
+                return vec![Effect::SaveSync(self.data.sync.clone())];
+            }
+            Action::PingsLoaded { job, pings } => {
+                if self.finish(Load::Pings, job) {
+                    match pings {
+                        Ok(pings) => {
+                            self.data.pings = pings;
+                            self.data.apply_pings_read();
+                            self.loaded(Load::Pings);
+                        }
+                        Err(e) => self.load_failed("pings", e),
+                    }
+                }
+            }
+            Action::ProfileLoaded { job, profile } => {
+                if self.finish(Load::Profile, job) {
+                    match profile {
+                        Ok(profile) => {
+                            self.data.profile = Some(profile);
+                            self.loaded(Load::Profile);
+                        }
+                        Err(e) if e.status() == Some(404) => self.set_message("no such user"),
+                        Err(e) => {
+                            self.data.offline = matches!(e, ApiError::Request(_));
+                            self.set_message(&format!("couldn't load the profile: {}", e));
+                        }
+                    }
+                }
+            }
+            Action::FeedLoaded { job, feed } => {
+                if !self.finish(Load::Feed, job) {
+                    return Vec::new();
+                }
+
+                // Nobody could be reached: keep what we had
+                if feed.thoughts.is_empty() && feed.errors.len() == feed.users.len() {
+                    if let Some((_, e)) = feed.errors.into_iter().next() {
+                        self.load_failed("feed", e);
+                    }
+                    return Vec::new();
+                }
+                self.data.feed = feed.thoughts;
+                self.loaded(Load::Feed);
+                if let Some((username, e)) = feed.errors.first() {
+                    self.set_message(&format!("couldn't load {}'s thoughts: {}", username, e));
+                }
+
+                // Follows changed while this was in flight
+                if feed.users != self.data.following.users {
+                    return self.feed_request().into_iter().collect();
+                }
+            }
+            Action::FollowsPushed { job, outcome } => {
+                if !self.finish(Load::Follow, job) {
+                    return Vec::new();
+                }
+                self.data.following.settle(&outcome);
+
+                if let Some(change) = outcome.missing.first() {
+                    self.set_message(&format!("no such user: {}", change.username));
+                } else if !outcome.supported {
+                    self.set_message(
+                        "this server doesn't keep follows, they're kept on this device",
+                    );
+                } else if let Some((change, e)) = outcome.errors.first() {
+                    self.data.offline = matches!(e, ApiError::Request(_));
+                    self.set_message(&format!(
+                        "couldn't tell the server about {}: {}",
+                        change.username, e
+                    ));
+                }
+                let mut effects = vec![Effect::SaveFollowing(self.data.following.clone())];
+
+                // Changes made while this was in flight
+                let newer = self
+                    .data
+                    .following
+                    .pending
+                    .iter()
+                    .any(|change| !outcome.pushed.contains(change));
+                if newer {
+                    effects.extend(self.push_follows());
+                }
+                return effects;
+            }
+            Action::OutboxSent {
+                job,
+                local_id,
+                result,
+            } => {
+                if !self.finish(Load::Send, job) {
+                    return Vec::new();
+                }
+                self.data.sending = None;
+
+                match result {
+                    Ok(()) => {
+                        self.data.offline = false;
+                        self.set_message("thought sent");
+                        let mut effects = self.remove_pending(local_id);
+                        effects.push(Effect::Request(self.thoughts_request()));
+                        return effects;
+                    }
+                    Err(e) => {
+                        self.data.offline = matches!(e, ApiError::Request(_));
+                        let item =
+                            match self.data.outbox.iter_mut().find(|i| i.local_id == local_id) {
+                                Some(item) => item,
+                                // Edited or discarded while it was being sent
+                                None => return Vec::new(),
+                            };
+                        item.record_failure(&e);
+                        if item.failed.is_some() {
+                            self.set_message(&format!(
+                                "couldn't send your thought: {} (e to edit, d to discard)",
+                                e
+                            ));
+                        } else {
+                            self.set_message("couldn't send your thought, will retry");
+                        }
+                        return vec![Effect::SaveOutbox(self.data.outbox.clone())];
+                    }
+                }
+            }
+            Action::Exported { job, dir, result } => {
+                if self.finish(Load::Export, job) {
+                    match result {
+                        Ok((count, report)) => self.set_message(&format!(
+                            "exported {} to {} · {} written",
+                            plural(count, "thought"),
+                            dir,
+                            report.written
+                        )),
+                        Err(e) => self.set_message(&e),
+                    }
+                }
+            }
+            // Everything else belongs to the current view
+            action => return self.update_view(&action),
+        }
+
+        Vec::new()
+    }
+
+    // Pass an action to the current view
+    fn update_view(&mut self, action: &Action) -> Vec<Effect> {
+        let data = &self.data;
+        let view: &mut dyn Component = match self.mode {
+            UIMode::Thoughts => &mut self.thoughts,
+            UIMode::Pings => &mut self.pings,
+            UIMode::Search => &mut self.search,
+            UIMode::Stream => &mut self.stream,
+            UIMode::Following => &mut self.following,
+            UIMode::ThinkPublic | UIMode::ThinkPrivate => &mut self.compose,
+            UIMode::Conflicts => &mut self.conflicts,
+            UIMode::Drafts => &mut self.drafts,
+            UIMode::Profile => &mut self.user_profile,
+        };
+        view.update(action, data)
+    }
+
+    // Fresh data from the server
+    fn loaded(&mut self, load: Load) {
+        self.data.cached_at.remove(&load);
+
+        // Back online: don't wait out the backoff
+        if self.data.offline {
+            for item in self.data.outbox.iter_mut() {
+                item.retry_now();
+            }
+        }
+        self.data.offline = false;
+    }
+
+    ///
+    /// Save the composer as a draft if it changed since the last save.
+    ///
+    pub fn autosave(&mut self) -> Option<Effect> {
+        if self.compose.is_empty() {
+            return None;
+        }
+
+        let title = self.compose.title_text();
+        let body = self.compose.raw_body();
+        let public = self.compose.public;
+        let id = *self.compose.draft_id.get_or_insert_with(Uuid::new_v4);
+
+        match self.data.drafts.iter_mut().find(|draft| draft.id == id) {
+            Some(draft) if draft.is_same(&title, &body, public) => return None,
+            Some(draft) => draft.update(&title, &body, public),
+            None => self.data.drafts.push(Draft::new(
+                id,
+                &title,
+                &body,
+                public,
+                self.compose.editing.clone(),
+            )),
+        }
+
+        Some(Effect::SaveDrafts(self.data.drafts.clone()))
+    }
+
+    // Add a new thought to the outbox. It's saved before it's sent, so it
+    // survives a crash or exit.
+    fn enqueue(&mut self, item: OutboxItem) -> Vec<Effect> {
+        self.data.outbox.push(item);
+        let mut effects = vec![Effect::SaveOutbox(self.data.outbox.clone())];
+        effects.extend(self.due_send());
+        effects
+    }
+
+    // A thought or edit was saved from the composer: its draft isn't needed
+    // any more, and we go back to where compose was opened from
+    fn close_compose(&mut self) -> Option<Effect> {
+        let draft_id = self.compose.draft_id;
+        self.compose.clear();
+        if self.mode.is_compose() {
+            self.leave_compose();
+        }
+
+        let draft_id = draft_id?;
+        self.data.drafts.retain(|draft| draft.id != draft_id);
+        Some(Effect::SaveDrafts(self.data.drafts.clone()))
+    }
+
+    // Server thoughts, shown with local edits on top
+    fn set_thoughts(&mut self, thoughts: Vec<Thought>) {
+        self.data.server_thoughts = thoughts;
+        self.show_edits();
+    }
+
+    // Show the edits as they are now. They go on the server's copy, so an
+    // edit that was dropped or replaced doesn't linger.
+    fn show_edits(&mut self) {
+        self.data.thoughts = self.data.server_thoughts.clone();
+        apply_edits(&mut self.data.thoughts, &self.data.sync.edits);
+    }
+
+    // Fetching thoughts syncs them if there are local edits to push
+    fn thoughts_request(&self) -> Request {
+        if self.data.sync.edits.is_empty() {
+            Request::FetchThoughts
+        } else {
+            Request::Sync(self.data.sync.clone())
+        }
+    }
+
+    // The feed, if you follow anyone
+    fn feed_request(&self) -> Option<Effect> {
+        if self.data.following.users.is_empty() {
+            return None;
+        }
+        Some(Effect::Request(Request::FetchFeed(
+            self.data.following.users.clone(),
+        )))
+    }
+
+    // Follows and unfollows the server hasn't heard about
+    fn push_follows(&self) -> Option<Effect> {
+        let following = &self.data.following;
+        if following.local_only || following.pending.is_empty() {
+            return None;
+        }
+        Some(Effect::Request(Request::PushFollows(
+            following.pending.clone(),
+        )))
+    }
+
+    // Back to where compose was opened from
+    fn leave_compose(&mut self) {
+        self.mode = self.previous_mode;
+        if self.mode == UIMode::Conflicts && self.data.sync.conflicts.is_empty() {
+            self.mode = UIMode::Thoughts;
+        }
+    }
+
+    // The next outbox item to send, if nothing is being sent
+    fn due_send(&self) -> Option<Effect> {
+        if self.data.loading.contains_key(&Load::Send) {
+            return None;
+        }
+
+        let now = Utc::now();
+        self.data
+            .outbox
+            .iter()
+            .find(|item| item.is_due(now))
+            .map(|item| Effect::Request(Request::Send(item.clone())))
+    }
+
+    // The outbox row under the thoughts cursor. The one being sent can't be
+    // edited or discarded.
+    fn pending_under_cursor(&mut self) -> Option<OutboxItem> {
+        if !matches!(self.mode, UIMode::Thoughts) {
+            return None;
+        }
+        let item = self.data.outbox.get(self.thoughts.cursor)?;
+        if self.data.sending == Some(item.local_id) {
+            self.set_message("that thought is being sent");
+            return None;
+        }
+        Some(item.clone())
+    }
+
+    // The sent thought under the thoughts cursor, below any outbox rows
+    fn thought_under_cursor(&self) -> Option<Thought> {
+        if !matches!(self.mode, UIMode::Thoughts) {
+            return None;
+        }
+        let i = self.thoughts.cursor.checked_sub(self.data.outbox.len())?;
+        self.data
+            .filtered_thoughts()
+            .get(i)
+            .map(|thought| (*thought).clone())
+    }
+
+    fn remove_pending(&mut self, local_id: Uuid) -> Vec<Effect> {
+        self.data.outbox.retain(|item| item.local_id != local_id);
+        vec![Effect::SaveOutbox(self.data.outbox.clone())]
+    }
+
+    // A fetch failed. Whatever we had (possibly from the cache) stays.
+    fn load_failed(&mut self, what: &str, e: ApiError) {
+        if let ApiError::Request(_) = e {
+            self.data.offline = true;
+            self.set_message(&format!("offline, showing the {} comode has seen", what));
+        } else {
+            self.set_message(&format!("couldn't load {}: {}", what, e));
+        }
+    }
+
+    ///
+    /// A note for data shown from the cache, e.g. "cached 2 hours ago".
+    ///
+    pub fn stale_note(&self) -> Option<String> {
+        let cached_at = view_loads(self.mode)
+            .iter()
+            .filter_map(|load| self.data.cached_at.get(load))
+            .min()?;
+
+        let offline = if self.data.offline { "offline · " } else { "" };
+        Some(format!(
+            "{}cached {}",
+            offline,
+            datetime_to_relative(cached_at)
+        ))
+    }
+
+    ///
+    /// A note while there are edits to push or conflicts to resolve.
+    ///
+    pub fn sync_note(&self) -> Option<String> {
+        let sync = &self.data.sync;
+        if !sync.conflicts.is_empty() {
+            return Some(format!(
+                "{} to resolve, press c",
+                plural(sync.conflicts.len(), "conflict")
+            ));
+        }
+        if !sync.edits.is_empty() {
+            return Some(format!(
+                "{} not synced yet",
+                plural(sync.edits.len(), "edit")
+            ));
+        }
+        None
+    }
+
+    // Give requests job ids and track them. A fetch that's already in
+    // flight isn't started twice.
+    fn schedule(&mut self, effects: Vec<Effect>) -> Vec<Task> {
+        let mut scheduled = Vec::new();
+
+        for effect in effects {
+            match effect {
+                Effect::Request(request) => {
+                    let load = Load::of(&request);
+                    if self.data.loading.contains_key(&load) {
+                        continue;
+                    }
+
+                    let job = self.next_job;
+                    self.next_job += 1;
+                    self.data.loading.insert(load, job);
+                    if let Request::Send(item) = &request {
+                        self.data.sending = Some(item.local_id);
+                    }
+                    scheduled.push(Task::Spawn(job, request));
+                }
+                Effect::Enqueue(item) => {
+                    self.set_message("sending...");
+                    let closed = self.close_compose().into_iter().collect();
+                    scheduled.extend(self.schedule(closed));
+                    let effects = self.enqueue(item);
+                    scheduled.extend(self.schedule(effects));
+                }
+                Effect::SaveEdit(mut edit) => {
+                    // A second edit before the first is pushed keeps the
+                    // first one's base, so conflicts are still caught
+                    if let Some(earlier) = self.data.sync.edits.iter().find(|e| e.id == edit.id) {
+                        edit.base = earlier.base.clone();
+                    }
+                    self.data.sync.edits.retain(|e| e.id != edit.id);
+                    // Editing their version settles a conflict
+                    self.data.sync.conflicts.retain(|c| c.edit.id != edit.id);
+                    self.data.sync.edits.push(edit);
+                    self.show_edits();
+
+                    self.set_message("saved, syncing...");
+                    let closed = self.close_compose().into_iter().collect();
+                    scheduled.extend(self.schedule(closed));
+                    scheduled.push(Task::SaveSync(self.data.sync.clone()));
+                    scheduled.extend(self.schedule(vec![Effect::Request(self.thoughts_request())]));
+                }
+                Effect::Quit => scheduled.push(Task::Quit),
+                Effect::Suspend => scheduled.push(Task::Suspend),
+                Effect::Logout => scheduled.push(Task::Logout),
+                Effect::SwitchProfile(profile) => scheduled.push(Task::SwitchProfile(profile)),
+                Effect::SaveOutbox(outbox) => scheduled.push(Task::SaveOutbox(outbox)),
+                Effect::SaveSync(state) => scheduled.push(Task::SaveSync(state)),
+                Effect::SaveDrafts(drafts) => scheduled.push(Task::SaveDrafts(drafts)),
+                Effect::SaveFilter(filter) => scheduled.push(Task::SaveFilter(filter)),
+                Effect::SaveFollowing(following) => scheduled.push(Task::SaveFollowing(following)),
+                Effect::SavePingsRead(read) => scheduled.push(Task::SavePingsRead(read)),
+                Effect::OpenEditor { document, review } => {
+                    scheduled.push(Task::OpenEditor { document, review })
+                }
+                Effect::OpenLink(url) => scheduled.push(Task::OpenLink(url)),
+                Effect::Cancel(job) => scheduled.push(Task::Cancel(job)),
+            }
+        }
+
+        scheduled
+    }
+
+    // Mark a job done. Returns false for results we no longer want.
+    fn finish(&mut self, load: Load, job: JobId) -> bool {
+        if self.data.loading.get(&load) != Some(&job) {
+            return false;
+        }
+
+        self.data.loading.remove(&load);
+        self.refreshed.insert(load, Instant::now());
+        true
+    }
+
+    // Fetches that are due, either for the periodic refresh or because the
+    // current view has never been loaded
+    fn stale_loads(&self) -> Vec<Effect> {
+        let mut effects = Vec::new();
+
+        for load in [Load::Thoughts, Load::Pings, Load::Feed] {
+            let request = match load {
+                Load::Thoughts => self.thoughts_request(),
+                Load::Feed if self.data.following.users.is_empty() => continue,
+                Load::Feed => Request::FetchFeed(self.data.following.users.clone()),
+                _ => Request::FetchPings,
+            };
+            let stale = match self.refreshed.get(&load) {
+                Some(refreshed) => refreshed.elapsed() >= self.refresh_interval,
+                None => view_loads(self.mode).contains(&load),
+            };
+            if stale && !self.data.loading.contains_key(&load) {
+                effects.push(Effect::Request(request));
+            }
+        }
+
+        effects
+    }
+
+    // Cancel fetches for the view we just left that the new view doesn't
+    // show. Sends are never cancelled, so a thought isn't lost by switching
+    // tabs.
+    fn cancel_hidden(&mut self, left: UIMode) -> Vec<Effect> {
+        let left = view_loads(left);
+        let wanted = view_loads(self.mode);
+        let hidden = self
+            .data
+            .loading
+            .iter()
+            .filter(|(load, _)| left.contains(load) && !wanted.contains(load))
+            .map(|(load, job)| (*load, *job))
+            .collect::<Vec<(Load, JobId)>>();
+
+        hidden
+            .into_iter()
+            .map(|(load, job)| {
+                self.data.loading.remove(&load);
+                Effect::Cancel(job)
+            })
+            .collect()
+    }
+
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some(message.to_string());
+        self.message_ticks = 0;
+    }
+
+    ///
+    /// Everything the palette can run from here: the commands that apply to
+    /// the current view, composing, and switching profiles.
+    ///
+    fn palette_entries(&self) -> Vec<Entry> {
+        let keys = |command: Command| {
+            let chords = self.keymap.keys_for(command);
+            chords
+                .iter()
+                .map(|chord| chord.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        let mut entries = vec![
+            Entry {
+                label: "new public thought".to_string(),
+                keys: keys(if self.default_public {
+                    Command::Compose
+                } else {
+                    Command::ComposeAlt
+                }),
+                target: Target::Compose { public: true },
+            },
+            Entry {
+                label: "new private thought".to_string(),
+                keys: keys(if self.default_public {
+                    Command::ComposeAlt
+                } else {
+                    Command::Compose
+                }),
+                target: Target::Compose { public: false },
+            },
+        ];
+
+        // Moving around and composing are covered already
+        let context = self.view().context();
+        for command in Command::all() {
+            let skip = matches!(
+                command,
+                Command::Up
+                    | Command::Down
+                    | Command::Top
+                    | Command::Bottom
+                    | Command::Select
+                    | Command::Back
+                    | Command::Compose
+                    | Command::ComposeAlt
+                    | Command::Palette
+            );
+            if skip || !context.chain().contains(&command.context()) {
+                continue;
+            }
+            entries.push(Entry {
+                label: command.help().to_string(),
+                keys: keys(*command),
+                target: Target::Command(*command),
+            });
+        }
+
+        for profile in &self.profiles {
+            if *profile != self.profile {
+                entries.push(Entry {
+                    label: format!("switch profile to {}", profile),
+                    keys: String::new(),
+                    target: Target::SwitchProfile(profile.clone()),
+                });
+            }
+        }
+
+        entries
+    }
+
+    ///
+    /// Draw the whole app.
+    ///
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.size();
+        frame.render_widget(Block::default().style(self.data.theme.base()), area);
+
+        // Session info, toggled with `w`
+        if self.show_whoami {
+            render_whoami(frame, area, &self.data);
+            return;
+        }
+
+        // Tabs, the view, and the status bar
+        let (tabs, view, status) = self.layout(area);
+        if let Some(tabs) = tabs {
+            self.render_tabs(frame, tabs);
+        }
+        self.view().render(frame, view, &self.data);
+        render_status(frame, status, &self.status(), &self.data.theme);
+
+        // Overlays on top of everything
+        if self.show_help {
+            render_help(
+                frame,
+                area,
+                &self.keymap,
+                self.view().context(),
+                self.view().hints(),
+                &self.data.theme,
+            );
+        }
+        if self.palette.open {
+            self.palette.render(frame, area, &self.data);
+        }
+    }
+
+    // Where the tabs (unless the view is fullscreen), the view and the
+    // status bar go on screen
+    fn layout(&self, area: Rect) -> (Option<Rect>, Rect, Rect) {
+        let rows = Layout::new(
+            Direction::Vertical,
+            [Constraint::Fill(1), Constraint::Length(1)],
+        )
+        .split(area);
+        if self.view().fullscreen() {
+            return (None, rows[0], rows[1]);
+        }
+
+        let view = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(2), Constraint::Fill(1)],
+        )
+        .split(rows[0]);
+        (Some(view[0]), view[1], rows[1])
+    }
+
+    // Tab titles, with a spinner on tabs that are loading
+    fn tab_titles(&self) -> Vec<String> {
+        modes()
+            .into_iter()
+            .zip(mode_strings())
+            .map(|(mode, title)| {
+                let loading = view_loads(mode)
+                    .iter()
+                    .any(|load| self.data.loading.contains_key(load));
+                if loading {
+                    format!("{} {}", title, spinner(&self.data))
+                } else {
+                    title
+                }
+            })
+            .collect()
+    }
+
+    fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        let tab = modes()
+            .iter()
+            .position(|mode| *mode == self.mode)
+            .unwrap_or(0);
+        let tabs = Tabs::new(self.tab_titles())
+            .block(Block::default().borders(Borders::TOP).title("comind"))
+            .style(self.data.theme.base())
+            .highlight_style(self.data.theme.heading())
+            .select(tab)
+            .divider(symbols::DOT);
+        frame.render_widget(tabs, area);
+    }
+
+    // The tab at column `x`, laid out the way `Tabs` draws them: a space,
+    // the title and a space, then the divider
+    fn tab_at(&self, area: Rect, x: u16) -> Option<UIMode> {
+        let mut left = area.x;
+        for (mode, title) in modes().into_iter().zip(self.tab_titles()) {
+            let right = left + Span::raw(title).width() as u16 + 2;
+            if (left..right).contains(&x) {
+                return Some(mode);
+            }
+            left = right + 1;
+        }
+        None
+    }
+
+    ///
+    /// Turn a click or a turn of the wheel into an action. `area` is the
+    /// whole screen.
+    ///
+    pub fn handle_mouse(&mut self, mouse: MouseEvent, area: Rect) -> Option<Action> {
+        // Overlays are closed with the keyboard
+        if self.show_whoami || self.show_help || self.palette.open {
+            return None;
+        }
+
+        let (tabs, view, _) = self.layout(area);
+        let position = Position::new(mouse.column, mouse.row);
+        if let Some(tabs) = tabs {
+            if tabs.contains(position) {
+                return match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        self.tab_at(tabs, mouse.column).map(Action::ShowTab)
+                    }
+                    _ => None,
+                };
+            }
+        }
+        if view.contains(position) {
+            return self.view().handle_mouse(mouse, view, &self.data);
+        }
+
+        None
+    }
+
+    ///
+    /// What the status bar shows.
+    ///
+    pub fn status(&self) -> Status {
+        let connection = if self.data.offline {
+            Connection::Offline
+        } else if self.refreshed.is_empty() {
+            Connection::Connecting
+        } else {
+            Connection::Online
+        };
+
+        // Conflicts matter more than being a bit out of date
+        let note = match self.sync_note() {
+            Some(note) => Some((note, true)),
+            None => self.stale_note().map(|note| (note, false)),
+        };
+
+        Status {
+            mode: self.mode.label(),
+            username: self.data.username.clone(),
+            profile: self.profile.clone(),
+            connection,
+            unread: self
+                .data
+                .pings
+                .iter()
+                .filter(|ping| !ping.read_status)
+                .count(),
+            unsent: self.data.outbox.len(),
+            refreshed: self
+                .refreshed
+                .values()
+                .max()
+                .map(|refreshed| refreshed.elapsed()),
+            note,
+            message: self.message.clone(),
+        }
+    }
+}
+
+// "1 edit", "2 edits"
+fn plural(n: usize, what: &str) -> String {
+    if n == 1 {
+        format!("1 {}", what)
+    } else {
+        format!("{} {}s", n, what)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_thought;
+    use comode::sync::Conflict;
+
+    fn app() -> App {
+        App::new("me", Vec::new(), &Config::default())
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn refresh_spawns_each_load_once() {
+        let mut app = app();
+        assert_eq!(
+            app.update(Action::Refresh),
+            [
+                Task::Spawn(0, Request::FetchThoughts),
+                Task::Spawn(1, Request::FetchPings),
+            ]
+        );
+
+        // Still in flight, so nothing new
+        assert_eq!(app.update(Action::Refresh), []);
+    }
+
+    #[test]
+    fn only_the_latest_job_counts() {
+        let mut app = app();
+        app.update(Action::Refresh);
+
+        app.update(Action::ThoughtsLoaded {
+            job: 7,
+            thoughts: Ok(vec![test_thought(1, "", "stale")]),
+        });
+        assert!(app.data.thoughts.is_empty());
+
+        app.update(Action::ThoughtsLoaded {
+            job: 0,
+            thoughts: Ok(vec![test_thought(1, "", "fresh")]),
+        });
+        assert_eq!(app.data.thoughts[0].body, "fresh");
+        assert!(!app.data.loading.contains_key(&Load::Thoughts));
+    }
+
+    #[test]
+    fn leaving_a_view_cancels_its_loads() {
+        let mut app = app();
+        app.update(Action::Refresh);
+
+        let tasks = app.update(Action::NextTab);
+        assert_eq!(app.mode, UIMode::Pings);
+        assert!(tasks.contains(&Task::Cancel(0)));
+        assert!(!tasks.contains(&Task::Cancel(1)));
+    }
+
+    #[test]
+    fn tabs_cycle_both_ways() {
+        let mut app = app();
+        app.update(Action::NextTab);
+        assert_eq!(app.mode, UIMode::Pings);
+        app.update(Action::PrevTab);
+        app.update(Action::PrevTab);
+        assert_eq!(app.mode, UIMode::Following);
+    }
+
+    #[test]
+    fn sent_thoughts_leave_the_outbox() {
+        let mut app = app();
+        app.update(Action::Refresh);
+        app.update(Action::Compose { public: false });
+        assert_eq!(app.mode, UIMode::ThinkPrivate);
+
+        // What the compose view asks for on submit
+        let item = OutboxItem::new("", "hi", false);
+        assert_eq!(
+            app.schedule(vec![Effect::Enqueue(item.clone())]),
+            [
+                Task::SaveOutbox(vec![item.clone()]),
+                Task::Spawn(2, Request::Send(item.clone())),
+            ]
+        );
+        assert_eq!(app.mode, UIMode::Thoughts);
+
+        app.update(Action::ThoughtsLoaded {
+            job: 0,
+            thoughts: Ok(Vec::new()),
+        });
+        assert_eq!(
+            app.update(Action::OutboxSent {
+                job: 2,
+                local_id: item.local_id,
+                result: Ok(()),
+            }),
+            [
+                Task::SaveOutbox(Vec::new()),
+                Task::Spawn(3, Request::FetchThoughts),
+            ]
+        );
+        assert_eq!(app.mode, UIMode::Thoughts);
+    }
+
+    #[test]
+    fn compose_keeps_letters_for_itself() {
+        let mut app = app();
+        assert!(matches!(
+            app.handle_key(key(KeyCode::Char('q'))),
+            Some(Action::Quit)
+        ));
+
+        app.update(Action::Compose { public: true });
+        assert!(matches!(
+            app.handle_key(key(KeyCode::Char('q'))),
+            Some(Action::Input(_)) | None
+        ));
+        assert_eq!(app.update(Action::Quit), [Task::Quit]);
+    }
+
+    #[test]
+    fn keeping_theirs_shows_the_server_copy() {
+        let mut app = app();
+        let base = test_thought(1, "", "base");
+        let edit = Edit::new(&base, None, "mine", true);
+        app.data.sync.edits.push(edit.clone());
+        app.set_thoughts(vec![base.clone()]);
+        assert_eq!(app.data.thoughts[0].body, "mine");
+
+        // The next sync finds the thought changed on the server too
+        let remote = Thought {
+            body: "theirs".to_string(),
+            revision: 1,
+            ..base
+        };
+        app.data.sync.edits.clear();
+        app.data.sync.conflicts.push(Conflict { edit, remote });
+        app.mode = UIMode::Conflicts;
+
+        app.update(Action::Resolve(Resolution::KeepTheirs));
+        assert_eq!(app.data.thoughts[0].body, "theirs");
+        assert!(app.data.sync.edits.is_empty());
+    }
+
+    #[test]
+    fn exports_go_to_the_worker() {
+        let mut app = app();
+        app.set_thoughts(vec![test_thought(1, "Only", "the first page")]);
+
+        // The worker fetches every page, not just the ones on screen
+        assert_eq!(
+            app.update(Action::Export("notes".to_string())),
+            [Task::Spawn(0, Request::Export("notes".to_string()))]
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! A whole account in one file, for `comode backup` and `comode restore`.
+//!
+//! The archive is JSON: a manifest saying who and where it's from, then the
+//! thoughts, pings and links. The manifest has the size and a SHA-256 of
+//! each of those sections, so a damaged or edited archive is caught before
+//! anything is restored from it.
+//!
+//! ```text
+//! {
+//!   "manifest": {
+//!     "format": "comode-backup",
+//!     "version": 1,
+//!     "created_at": "2024-03-14T16:36:44.764Z",
+//!     "server": "https://nimbus.pfiffer.org",
+//!     "username": "cameron",
+//!     "user_id": "...",
+//!     "sections": {
+//!       "thoughts": { "count": 412, "sha256": "..." },
+//!       ...
+//!     }
+//!   },
+//!   "thoughts": [...],
+//!   "pings": [...],
+//!   "links": [...]
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::errors::{ArchiveError, ArchiveResult};
+use crate::store::write_json;
+use crate::types::{Ping, Thought, ThoughtId, User, UserId};
+
+/// What the manifest's `format` says.
+pub const FORMAT: &str = "comode-backup";
+
+/// The archive format this comode writes, and the newest it reads.
+pub const VERSION: u32 = 1;
+
+const THOUGHTS: &str = "thoughts";
+const PINGS: &str = "pings";
+const LINKS: &str = "links";
+
+///
+/// An account's thoughts, pings and links, with a manifest.
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Archive {
+    pub manifest: Manifest,
+    pub thoughts: Vec<Thought>,
+    pub pings: Vec<Ping>,
+    pub links: Vec<Link>,
+}
+
+///
+/// Who and where an archive is from, and what's in it.
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub server: String,
+    pub username: String,
+    pub user_id: UserId,
+    /// Each section's size and checksum, by name
+    pub sections: BTreeMap<String, Section>,
+}
+
+///
+/// How many items a section has, and the SHA-256 (in hex) of its JSON.
+///
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Section {
+    pub count: usize,
+    pub sha256: String,
+}
+
+///
+/// One thought linking to another. Links from the user's thoughts come from
+/// their `to_id`; links to them from other people's thoughts come from pings.
+///
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Link {
+    pub from: ThoughtId,
+    pub to: ThoughtId,
+}
+
+impl Archive {
+    ///
+    /// An archive of `user`'s `thoughts` and `pings` on `server`, with the
+    /// links between them.
+    ///
+    pub fn new(
+        server: &str,
+        user: &User,
+        thoughts: Vec<Thought>,
+        pings: Vec<Ping>,
+    ) -> ArchiveResult<Archive> {
+        let mut links = thoughts
+            .iter()
+            .filter_map(|thought| {
+                thought.to_id.map(|to| Link {
+                    from: thought.id,
+                    to,
+                })
+            })
+            .chain(pings.iter().map(|ping| Link {
+                from: ping.linking_thought_id,
+                to: ping.user_thought_id,
+            }))
+            .collect::<Vec<Link>>();
+        links.sort();
+        links.dedup();
+
+        let mut sections = BTreeMap::new();
+        sections.insert(
+            THOUGHTS.to_string(),
+            section(&serde_json::to_value(&thoughts)?),
+        );
+        sections.insert(PINGS.to_string(), section(&serde_json::to_value(&pings)?));
+        sections.insert(LINKS.to_string(), section(&serde_json::to_value(&links)?));
+
+        Ok(Archive {
+            manifest: Manifest {
+                format: FORMAT.to_string(),
+                version: VERSION,
+                created_at: Utc::now(),
+                server: server.to_string(),
+                username: user.username.clone(),
+                user_id: user.user_id,
+                sections,
+            },
+            thoughts,
+            pings,
+            links,
+        })
+    }
+
+    /// Write the archive to `path`, replacing it only once it's all written.
+    pub fn write(&self, path: &Path) -> ArchiveResult<()> {
+        write_json(path, self)?;
+        Ok(())
+    }
+
+    ///
+    /// Read an archive, checking its format, version, and every section
+    /// against the manifest.
+    ///
+    pub fn read(path: &Path) -> ArchiveResult<Archive> {
+        let mut value: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        let manifest: Manifest = match value.get_mut("manifest").map(Value::take) {
+            Some(manifest) => serde_json::from_value(manifest)?,
+            None => return Err(ArchiveError::NotAnArchive),
+        };
+        if manifest.format != FORMAT {
+            return Err(ArchiveError::NotAnArchive);
+        }
+        if manifest.version > VERSION {
+            return Err(ArchiveError::Version(manifest.version));
+        }
+
+        Ok(Archive {
+            thoughts: checked(&mut value, &manifest, THOUGHTS)?,
+            pings: checked(&mut value, &manifest, PINGS)?,
+            links: checked(&mut value, &manifest, LINKS)?,
+            manifest,
+        })
+    }
+
+    /// Where `id` links to, according to the archive.
+    pub fn link_from(&self, id: &ThoughtId) -> Option<ThoughtId> {
+        self.links
+            .iter()
+            .find(|link| link.from == *id)
+            .map(|link| link.to)
+    }
+}
+
+// A section's entry in the manifest
+fn section(items: &Value) -> Section {
+    Section {
+        count: items.as_array().map(|items| items.len()).unwrap_or(0),
+        sha256: sha256(items),
+    }
+}
+
+// The SHA-256 of a value's compact JSON, in hex
+fn sha256(value: &Value) -> String {
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// A section, once it's matched the manifest
+fn checked<T: DeserializeOwned>(
+    value: &mut Value,
+    manifest: &Manifest,
+    name: &str,
+) -> ArchiveResult<Vec<T>> {
+    let items = value.get_mut(name).map(Value::take).unwrap_or(Value::Null);
+    let expected = match manifest.sections.get(name) {
+        Some(expected) => expected,
+        None => return Err(ArchiveError::NotAnArchive),
+    };
+
+    let found = section(&items);
+    if found.count != expected.count {
+        return Err(ArchiveError::Count {
+            section: name.to_string(),
+            expected: expected.count,
+            found: found.count,
+        });
+    }
+    if found.sha256 != expected.sha256 {
+        return Err(ArchiveError::Checksum(name.to_string()));
+    }
+
+    Ok(serde_json::from_value(items)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_thought, PingType};
+    use uuid::Uuid;
+
+    fn user() -> User {
+        User {
+            token: String::new(),
+            user_id: UserId(Uuid::from_u128(0)),
+            username: "me".to_string(),
+            issued_at: None,
+            expires_at: Utc::now(),
+        }
+    }
+
+    // An archive with a link each way, written to a file of its own
+    fn written() -> (Archive, std::path::PathBuf) {
+        let mut a = test_thought(1, "A", "a");
+        let b = test_thought(2, "B", "b");
+        a.to_id = Some(b.id);
+        let ping = Ping {
+            id: 1,
+            r#type: PingType::Link,
+            message: "linked".to_string(),
+            created_at: b.date_created,
+            read_status: false,
+            user_thought_id: b.id,
+            linking_thought_id: ThoughtId(Uuid::from_u128(9)),
+            linking_user_id: UserId(Uuid::from_u128(8)),
+            user_id: b.user_id,
+        };
+
+        let archive = Archive::new("https://example.org", &user(), vec![a, b], vec![ping]).unwrap();
+        let path = std::env::temp_dir().join(format!("comode-archive-{}.json", Uuid::new_v4()));
+        archive.write(&path).unwrap();
+        (archive, path)
+    }
+
+    // Edit the archive's JSON on disk
+    fn tamper(path: &Path, edit: impl FnOnce(&mut Value)) {
+        let mut value: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        edit(&mut value);
+        fs::write(path, value.to_string()).unwrap();
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let (archive, path) = written();
+        let read = Archive::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.thoughts, archive.thoughts);
+        assert_eq!(read.pings, archive.pings);
+        assert_eq!(read.links, archive.links);
+        assert_eq!(read.manifest.sections, archive.manifest.sections);
+        assert_eq!(read.manifest.sections[THOUGHTS].count, 2);
+        assert_eq!(read.links.len(), 2);
+        assert_eq!(
+            read.link_from(&ThoughtId(Uuid::from_u128(1))),
+            Some(ThoughtId(Uuid::from_u128(2)))
+        );
+    }
+
+    #[test]
+    fn catches_edits() {
+        let (_, path) = written();
+        tamper(&path, |value| {
+            value["thoughts"][0]["body"] = Value::from("changed");
+        });
+        let edited = Archive::read(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(edited, Err(ArchiveError::Checksum(section)) if section == THOUGHTS));
+
+        let (_, path) = written();
+        tamper(&path, |value| {
+            value["pings"].as_array_mut().unwrap().clear();
+        });
+        let cut = Archive::read(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            cut,
+            Err(ArchiveError::Count {
+                expected: 1,
+                found: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn refuses_other_files() {
+        let (_, path) = written();
+        tamper(&path, |value| {
+            value["manifest"]["version"] = Value::from(VERSION + 1)
+        });
+        let newer = Archive::read(&path);
+
+        fs::write(&path, "[]").unwrap();
+        let other = Archive::read(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(newer, Err(ArchiveError::Version(version)) if version == VERSION + 1));
+        assert!(matches!(other, Err(ArchiveError::NotAnArchive)));
+    }
+}
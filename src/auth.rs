@@ -0,0 +1,70 @@
+//! Authentication: keeping the server's token in the system keyring, and
+//! turning it back into a `User`.
+use keyring::Entry;
+
+use crate::errors::{AuthResult, AuthenticationError};
+use crate::types::User;
+
+// Where the token lives in the keyring
+const KEYRING_SERVICE: &str = "comind";
+const KEYRING_USER: &str = "token";
+
+///
+/// The profile used unless another is picked. Each profile (e.g. one per
+/// server) has its own token.
+///
+pub const DEFAULT_PROFILE: &str = "default";
+
+///
+/// The keyring entry holding `profile`'s login response.
+///
+/// The default profile uses the entry comode has always used, so existing
+/// logins keep working.
+///
+pub fn token_entry(profile: &str) -> AuthResult<Entry> {
+    let user = match profile {
+        DEFAULT_PROFILE => KEYRING_USER.to_string(),
+        profile => format!("{}:{}", KEYRING_USER, profile),
+    };
+    Ok(Entry::new(KEYRING_SERVICE, &user)?)
+}
+
+///
+/// The logged-in user for `profile`, from the token in the keyring.
+///
+pub fn load_user(profile: &str) -> AuthResult<User> {
+    User::create_from_entry(&token_entry(profile)?)
+}
+
+///
+/// Save the server's login response to the keyring, and decode it.
+///
+pub fn store_token(profile: &str, login_response: &str) -> AuthResult<User> {
+    // Make sure it's a usable token before replacing the old one
+    let user = User::from_login_response(login_response)?;
+    token_entry(profile)?.set_password(login_response)?;
+    Ok(user)
+}
+
+///
+/// Forget the stored token. Succeeds if there was none.
+///
+pub fn clear_token(profile: &str) -> AuthResult<()> {
+    match token_entry(profile)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AuthenticationError::Keyring(e)),
+    }
+}
+
+/// Name of the platform credential store the keyring crate uses.
+pub fn credential_backend() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS Keychain"
+    } else if cfg!(target_os = "windows") {
+        "Windows Credential Manager"
+    } else if cfg!(target_os = "ios") {
+        "iOS Keychain"
+    } else {
+        "Secret Service (keyring)"
+    }
+}
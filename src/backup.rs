@@ -0,0 +1,217 @@
+// `comode backup` and `comode restore`: a whole account in one file
+//
+//   comode backup                          to comode-<you>-<date>.json, here
+//   comode backup ~/backups/comind.json
+//   comode restore backup.json             into the account you're logged in as
+//   comode restore --dry-run backup.json   say what would happen, send nothing
+//   comode restore --rate 1 backup.json    at most one request a second
+//
+// A backup has every thought (paged with ComindLimit and ComindPageNo), the
+// pings and the links between thoughts, with a manifest and checksums.
+// Restoring makes the thoughts again, skipping ones that are there already,
+// then links them up with their new ids. Use `--profile` to restore into
+// another server or account. Dates, origins and pings can't be made again;
+// they stay in the backup.
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::Local;
+use comode::archive::Archive;
+use comode::auth;
+use comode::client::blocking::Client;
+use comode::types::{ThoughtId, User};
+use comode::vault::Note;
+
+use crate::colors::ComindColors;
+use crate::config::Account;
+use crate::display::co_say;
+use crate::import::{self, parse_rate, DEFAULT_RATE};
+
+const BACKUP_USAGE: &str = "usage: comode backup [file]";
+const RESTORE_USAGE: &str = "usage: comode restore [--dry-run] [--rate N] <file>";
+
+pub fn backup(account: &Account, page_size: u32, args: &[String], colors: &ComindColors) {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", BACKUP_USAGE);
+        return;
+    }
+    if args.len() > 1 || args.iter().any(|arg| arg.starts_with('-')) {
+        co_say("just the file to write, if you want to name it", colors);
+        println!("{}", BACKUP_USAGE);
+        return;
+    }
+
+    let user = match logged_in(account, colors) {
+        Some(user) => user,
+        None => return,
+    };
+    let path = match args.first() {
+        Some(path) => path.clone(),
+        None => format!(
+            "comode-{}-{}.json",
+            user.username,
+            Local::now().format("%Y%m%d-%H%M%S")
+        ),
+    };
+
+    // Everything, or nothing: a backup from the cache could be missing things
+    let client = Client::for_user(&account.server, &user).with_page_size(page_size);
+    let thoughts = match client.get_all_user_thoughts(&user.username) {
+        Ok(thoughts) => thoughts,
+        Err(e) => {
+            co_say(
+                &format!("couldn't fetch your thoughts, nothing written: {}", e),
+                colors,
+            );
+            return;
+        }
+    };
+    let pings = match client.get_pings() {
+        Ok(pings) => pings,
+        Err(e) => {
+            co_say(
+                &format!("couldn't fetch your pings, nothing written: {}", e),
+                colors,
+            );
+            return;
+        }
+    };
+
+    let archive = match Archive::new(&account.server, &user, thoughts, pings) {
+        Ok(archive) => archive,
+        Err(e) => {
+            co_say(&format!("couldn't make the backup: {}", e), colors);
+            return;
+        }
+    };
+    match archive.write(Path::new(&path)) {
+        Ok(()) => co_say(
+            &format!(
+                "backed up {} thoughts, {} pings and {} links to {}",
+                archive.thoughts.len(),
+                archive.pings.len(),
+                archive.links.len(),
+                path
+            ),
+            colors,
+        ),
+        Err(e) => co_say(&format!("couldn't write {}: {}", path, e), colors),
+    }
+}
+
+pub fn restore(account: &Account, page_size: u32, args: &[String], colors: &ComindColors) {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", RESTORE_USAGE);
+        return;
+    }
+    let (path, dry_run, rate) = match parse_restore_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            co_say(&e, colors);
+            println!("{}", RESTORE_USAGE);
+            return;
+        }
+    };
+
+    // Check it all before sending anything
+    let archive = match Archive::read(Path::new(&path)) {
+        Ok(archive) => archive,
+        Err(e) => {
+            co_say(&format!("can't restore {}: {}", path, e), colors);
+            return;
+        }
+    };
+    let user = match logged_in(account, colors) {
+        Some(user) => user,
+        None => return,
+    };
+
+    let manifest = &archive.manifest;
+    co_say(
+        &format!(
+            "restoring {} thoughts by {} on {}, backed up {}, into {} on {}",
+            archive.thoughts.len(),
+            manifest.username,
+            manifest.server,
+            manifest
+                .created_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            user.username,
+            account.server
+        ),
+        colors,
+    );
+
+    // Links go by the archive, not by what bodies happen to say
+    let notes = archive
+        .thoughts
+        .iter()
+        .map(|thought| {
+            let mut note = Note::from_thought(thought, &thought.id.to_string());
+            note.links_to = archive.link_from(&thought.id);
+            note.wikilinks.clear();
+            note
+        })
+        .collect::<Vec<Note>>();
+
+    // Links to other people's thoughts only mean something on the same server
+    let ours = archive
+        .thoughts
+        .iter()
+        .map(|thought| thought.id)
+        .collect::<HashSet<ThoughtId>>();
+    let keep = match manifest.server == account.server {
+        true => archive
+            .links
+            .iter()
+            .map(|link| link.to)
+            .filter(|to| !ours.contains(to))
+            .collect::<Vec<ThoughtId>>(),
+        false => Vec::new(),
+    };
+
+    let client = Client::for_user(&account.server, &user).with_page_size(page_size);
+    import::run(&client, &user, notes, &keep, dry_run, rate, colors);
+}
+
+fn logged_in(account: &Account, colors: &ComindColors) -> Option<User> {
+    match auth::load_user(&account.profile) {
+        Ok(user) => Some(user),
+        Err(_) => {
+            co_say("you're not logged in, run `comode login` first", colors);
+            None
+        }
+    }
+}
+
+// The file, whether it's a dry run, and the rate
+fn parse_restore_args(args: &[String]) -> Result<(String, bool, f64), String> {
+    let mut path = None;
+    let mut dry_run = false;
+    let mut rate = DEFAULT_RATE;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        match flag {
+            "--dry-run" | "-n" => dry_run = true,
+            "--rate" => match inline.or_else(|| args.next().cloned()) {
+                Some(value) => rate = parse_rate(&value)?,
+                None => return Err("--rate needs a value".to_string()),
+            },
+            _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_some() => return Err(format!("only one backup, not `{}` too", arg)),
+            _ => path = Some(arg.clone()),
+        }
+    }
+
+    match path {
+        Some(path) => Ok((path, dry_run, rate)),
+        None => Err("restore which backup? e.g. `comode restore comode-backup.json`".to_string()),
+    }
+}
@@ -0,0 +1,322 @@
+//! Blocking Comind API client.
+use crate::client::{
+    bearer, check_status, follows_supported, parse, supported, Call, Pages, DEFAULT_LIMIT,
+};
+use crate::errors::ApiResult;
+use crate::profile::{Profile, UserInfo, Who};
+use crate::types::{Ping, Thought, ThoughtId, User, UserId};
+
+///
+/// Blocking Comind API client, with the same methods as the async
+/// `client::Client`.
+///
+#[derive(Clone)]
+pub struct Client {
+    server: String,
+    token: Option<String>,
+    page_size: u32,
+    http: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// A client without a token, for logging in and registering.
+    pub fn new(server: &str) -> Client {
+        Client {
+            server: server.trim_end_matches('/').to_string(),
+            token: None,
+            page_size: DEFAULT_LIMIT,
+            http: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// A client that acts as `user`.
+    pub fn for_user(server: &str, user: &User) -> Client {
+        Client::new(server).with_token(&user.token)
+    }
+
+    /// Use `token` for authenticated requests. An empty token (an offline
+    /// user) means requests fail with `ApiError::NotLoggedIn`.
+    pub fn with_token(mut self, token: &str) -> Client {
+        self.token = match token {
+            "" => None,
+            token => Some(token.to_string()),
+        };
+        self
+    }
+
+    /// Page size for listings when the caller doesn't give one. The
+    /// default is 100.
+    pub fn with_page_size(mut self, page_size: u32) -> Client {
+        self.page_size = page_size;
+        self
+    }
+
+    /// The server this client talks to.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Get user thoughts
+    /// get /api/user-thoughts/{username}
+    ///
+    /// This endpoint returns the thoughts of a user.
+    ///
+    /// # Parameters
+    /// - username: The user's name
+    /// - limit: Page size, the client's page size if not given (ComindLimit header)
+    /// - pageno: Page number, from 0 (ComindPageNo header)
+    ///
+    /// # Returns
+    /// - 200: The user's thoughts
+    /// - 401: The user is not authenticated
+    /// - 404: The user does not exist
+    /// - 500: An internal server error occurred
+    ///
+    /// # Example
+    ///
+    /// ```json
+    ///  [{
+    ///           "title": "Thought ID refresh issue in dual submission problem",
+    ///            "body": "i've narrowed the dual submission problem to thought ids not being refreshed when a thought is submitted",
+    ///    "date_created": "2024-01-05T13:18:21.26",
+    ///    "date_updated": "2024-03-14T16:36:44.764",
+    ///        "revision": 0,
+    ///              "id": "139f78be-c4e8-5f17-8d60-06162fbed802",
+    ///          "public": true,
+    ///       "synthetic": false,
+    ///          "origin": "app",
+    ///         "user_id": "6b129b9f-b958-4cf0-a6ad-3108d221177a",
+    ///        "username": "cameron",
+    ///           "to_id": "139f78be-c4e8-5f17-8d60-06162fbed802",
+    ///         "n_links": 1,
+    ///        "numlinks": 1
+    /// }]
+    /// ```
+    ///
+    pub fn get_user_thoughts(
+        &self,
+        username: &str,
+        limit: Option<u32>,
+        pageno: Option<u32>,
+    ) -> ApiResult<Vec<Thought>> {
+        let limit = limit.unwrap_or(self.page_size);
+        let call = Call::user_thoughts(username, limit, pageno.unwrap_or(0));
+        parse(&self.send(call)?)
+    }
+
+    ///
+    /// Get all of a user's thoughts, a page at a time
+    ///
+    /// Pages of the client's page size are fetched with `ComindPageNo` until
+    /// one comes back short, or repeats what we have (a server that ignores
+    /// the page number).
+    ///
+    pub fn get_all_user_thoughts(&self, username: &str) -> ApiResult<Vec<Thought>> {
+        let mut pages = Pages::default();
+        for pageno in 0.. {
+            let page = self.get_user_thoughts(username, None, Some(pageno))?;
+            if !pages.add(page, self.page_size) {
+                break;
+            }
+        }
+
+        Ok(pages.thoughts)
+    }
+
+    ///
+    /// Look up a user by name
+    ///
+    /// get /api/users/{username}
+    ///
+    /// # Returns
+    /// - 200: The user's id and name
+    /// - 401: The user is not authenticated
+    /// - 404: No user has that name
+    ///
+    pub fn get_user(&self, username: &str) -> ApiResult<UserInfo> {
+        parse(&self.send(Call::user(username))?)
+    }
+
+    ///
+    /// Look up a user by id
+    ///
+    /// get /api/users/id/{user_id}
+    ///
+    /// Pings name the user who linked to you by id only.
+    ///
+    pub fn get_user_by_id(&self, user_id: &UserId) -> ApiResult<UserInfo> {
+        parse(&self.send(Call::user_by_id(user_id))?)
+    }
+
+    ///
+    /// Get a user's profile: who they are and their public thoughts, one
+    /// page of them.
+    ///
+    pub fn get_profile(&self, who: &Who) -> ApiResult<Profile> {
+        let user = match who {
+            Who::Name(username) => self.get_user(username)?,
+            Who::Id(user_id) => self.get_user_by_id(user_id)?,
+        };
+        let thoughts = self.get_user_thoughts(&user.username, None, None)?;
+
+        Ok(Profile::new(user, thoughts))
+    }
+
+    ///
+    /// Make a new thought
+    ///
+    /// post /api/thoughts
+    ///
+    pub fn make_new_thought(
+        &self,
+        user_id: &UserId,
+        title: &str,
+        body: &str,
+        public: bool,
+    ) -> ApiResult<()> {
+        self.send(Call::new_thought(user_id, title, body, public))?;
+        Ok(())
+    }
+
+    ///
+    /// Edit a thought
+    ///
+    /// put /api/thoughts/{id}
+    ///
+    /// `revision` is the revision the edit was made against. The server
+    /// refuses the edit with 409 if the thought has changed since, so
+    /// concurrent edits aren't silently overwritten.
+    ///
+    /// # Returns
+    /// - 200: The updated thought, with its new revision
+    /// - 404: The thought does not exist
+    /// - 409: The thought has a newer revision
+    ///
+    pub fn update_thought(
+        &self,
+        id: &ThoughtId,
+        title: Option<&str>,
+        body: &str,
+        public: bool,
+        revision: i32,
+    ) -> ApiResult<Thought> {
+        let call = Call::update_thought(id, title, body, public, revision);
+        parse(&self.send(call)?)
+    }
+
+    ///
+    /// Link a thought to another
+    ///
+    /// put /api/thoughts/{id}
+    ///
+    /// The same edit as `update_thought`, keeping the thought as it is but
+    /// setting its `to_id`. A thought links to at most one other.
+    ///
+    /// # Returns
+    /// - 200: The linked thought, with its new revision
+    /// - 404: The thought does not exist
+    /// - 409: The thought has a newer revision
+    ///
+    pub fn link_thought(&self, thought: &Thought, to: &ThoughtId) -> ApiResult<Thought> {
+        parse(&self.send(Call::link_thought(thought, to))?)
+    }
+
+    ///
+    /// Get pings
+    ///
+    /// get /api/notifications/
+    ///
+    pub fn get_pings(&self) -> ApiResult<Vec<Ping>> {
+        parse(&self.send(Call::pings())?)
+    }
+
+    ///
+    /// Follow a user
+    ///
+    /// post /api/follow/
+    ///
+    /// Servers without follows answer 405, and `Ok(false)` is returned so
+    /// the caller can keep the follow locally instead. A 404 is an error:
+    /// either there's no such user, or no follow route at all, and
+    /// `get_user` tells which.
+    ///
+    pub fn follow_user(&self, username: &str) -> ApiResult<bool> {
+        follows_supported(self.send(Call::follow(username, true)))
+    }
+
+    ///
+    /// Stop following a user
+    ///
+    /// post /api/unfollow/
+    ///
+    /// Like `follow_user`, `Ok(false)` means the server has no follows.
+    ///
+    pub fn unfollow_user(&self, username: &str) -> ApiResult<bool> {
+        follows_supported(self.send(Call::follow(username, false)))
+    }
+
+    ///
+    /// Log in
+    ///
+    /// post /api/login/
+    ///
+    /// The server requires a username or email with a password, and answers
+    /// with a JWT. Returns the response body, which `auth::store_token`
+    /// saves to the keyring.
+    ///
+    pub fn login(&self, username: &str, password: &str) -> ApiResult<String> {
+        self.send(Call::login(username, password))
+    }
+
+    ///
+    /// Register a new account
+    ///
+    /// post /api/register/
+    ///
+    /// Sends the username, email and password for a new account. The caller
+    /// logs in separately afterwards to get a token.
+    ///
+    /// # Returns
+    /// - 200/201: The account was created
+    /// - 409: The username or email is already taken
+    /// - 422: The server rejected one of the fields
+    ///
+    pub fn register_user(&self, username: &str, email: &str, password: &str) -> ApiResult<()> {
+        self.send(Call::register(username, email, password))?;
+        Ok(())
+    }
+
+    ///
+    /// Revoke the current token on the server
+    ///
+    /// post /api/logout/
+    ///
+    /// Servers without a logout endpoint answer 404 or 405. In that case there
+    /// is nothing to revoke, and `Ok(false)` is returned so the caller can still
+    /// clear the local credentials.
+    ///
+    pub fn revoke_token(&self) -> ApiResult<bool> {
+        supported(self.send(Call::logout()))
+    }
+
+    // Send a call and return the body of a successful response
+    fn send(&self, call: Call) -> ApiResult<String> {
+        let url = format!("{}{}", self.server, call.path);
+        let mut request = self.http.request(call.method, url);
+        if call.auth {
+            request = request.bearer_auth(bearer(&self.token)?);
+        }
+        for (name, value) in call.headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &call.body {
+            request = request.json(body);
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+        let body = response.text()?;
+
+        check_status(status, body)
+    }
+}
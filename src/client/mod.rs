@@ -0,0 +1,567 @@
+//! Clients for the Comind API.
+//!
+//! `Client` is async and needs a tokio runtime, like `reqwest::Client`.
+//! `blocking::Client` has the same methods and works anywhere.
+//!
+//! ```no_run
+//! # async fn example() -> comode::errors::ApiResult<()> {
+//! use comode::{auth, client::Client, DEFAULT_SERVER};
+//!
+//! let user = auth::load_user(auth::DEFAULT_PROFILE)?;
+//! let client = Client::for_user(DEFAULT_SERVER, &user);
+//! for thought in client.get_user_thoughts(&user.username, None, None).await? {
+//!     println!("{}", thought.body);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashSet;
+
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::errors::{ApiError, ApiResult};
+use crate::profile::{Profile, UserInfo, Who};
+use crate::types::{Ping, Thought, ThoughtId, User, UserId};
+
+pub mod blocking;
+
+// Default page size for thought listings
+pub(crate) const DEFAULT_LIMIT: u32 = 100;
+
+///
+/// Async Comind API client.
+///
+#[derive(Clone)]
+pub struct Client {
+    server: String,
+    token: Option<String>,
+    page_size: u32,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// A client without a token, for logging in and registering.
+    pub fn new(server: &str) -> Client {
+        Client {
+            server: server.trim_end_matches('/').to_string(),
+            token: None,
+            page_size: DEFAULT_LIMIT,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// A client that acts as `user`.
+    pub fn for_user(server: &str, user: &User) -> Client {
+        Client::new(server).with_token(&user.token)
+    }
+
+    /// Use `token` for authenticated requests. An empty token (an offline
+    /// user) means requests fail with `ApiError::NotLoggedIn`.
+    pub fn with_token(mut self, token: &str) -> Client {
+        self.token = match token {
+            "" => None,
+            token => Some(token.to_string()),
+        };
+        self
+    }
+
+    /// Page size for listings when the caller doesn't give one. The
+    /// default is 100.
+    pub fn with_page_size(mut self, page_size: u32) -> Client {
+        self.page_size = page_size;
+        self
+    }
+
+    /// The server this client talks to.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    ///
+    /// Get user thoughts
+    /// get /api/user-thoughts/{username}
+    ///
+    /// See `blocking::Client::get_user_thoughts`.
+    ///
+    pub async fn get_user_thoughts(
+        &self,
+        username: &str,
+        limit: Option<u32>,
+        pageno: Option<u32>,
+    ) -> ApiResult<Vec<Thought>> {
+        let limit = limit.unwrap_or(self.page_size);
+        let call = Call::user_thoughts(username, limit, pageno.unwrap_or(0));
+        parse(&self.send(call).await?)
+    }
+
+    ///
+    /// Get all of a user's thoughts, a page at a time
+    ///
+    /// See `blocking::Client::get_all_user_thoughts`.
+    ///
+    pub async fn get_all_user_thoughts(&self, username: &str) -> ApiResult<Vec<Thought>> {
+        let mut pages = Pages::default();
+        for pageno in 0.. {
+            let page = self.get_user_thoughts(username, None, Some(pageno)).await?;
+            if !pages.add(page, self.page_size) {
+                break;
+            }
+        }
+
+        Ok(pages.thoughts)
+    }
+
+    ///
+    /// Look up a user by name
+    /// get /api/users/{username}
+    ///
+    pub async fn get_user(&self, username: &str) -> ApiResult<UserInfo> {
+        parse(&self.send(Call::user(username)).await?)
+    }
+
+    ///
+    /// Look up a user by id
+    /// get /api/users/id/{user_id}
+    ///
+    pub async fn get_user_by_id(&self, user_id: &UserId) -> ApiResult<UserInfo> {
+        parse(&self.send(Call::user_by_id(user_id)).await?)
+    }
+
+    ///
+    /// Get a user's profile
+    ///
+    /// See `blocking::Client::get_profile`.
+    ///
+    pub async fn get_profile(&self, who: &Who) -> ApiResult<Profile> {
+        let user = match who {
+            Who::Name(username) => self.get_user(username).await?,
+            Who::Id(user_id) => self.get_user_by_id(user_id).await?,
+        };
+        let thoughts = self.get_user_thoughts(&user.username, None, None).await?;
+
+        Ok(Profile::new(user, thoughts))
+    }
+
+    ///
+    /// Make a new thought
+    /// post /api/thoughts
+    ///
+    pub async fn make_new_thought(
+        &self,
+        user_id: &UserId,
+        title: &str,
+        body: &str,
+        public: bool,
+    ) -> ApiResult<()> {
+        let call = Call::new_thought(user_id, title, body, public);
+        self.send(call).await?;
+        Ok(())
+    }
+
+    ///
+    /// Edit a thought
+    ///
+    /// put /api/thoughts/{id}
+    ///
+    /// `revision` is the revision the edit was made against. The server
+    /// refuses the edit with 409 if the thought has changed since, so
+    /// concurrent edits aren't silently overwritten.
+    ///
+    /// # Returns
+    /// - 200: The updated thought, with its new revision
+    /// - 404: The thought does not exist
+    /// - 409: The thought has a newer revision
+    ///
+    pub async fn update_thought(
+        &self,
+        id: &ThoughtId,
+        title: Option<&str>,
+        body: &str,
+        public: bool,
+        revision: i32,
+    ) -> ApiResult<Thought> {
+        let call = Call::update_thought(id, title, body, public, revision);
+        parse(&self.send(call).await?)
+    }
+
+    ///
+    /// Link a thought to another
+    ///
+    /// put /api/thoughts/{id}
+    ///
+    /// The same edit as `update_thought`, keeping the thought as it is but
+    /// setting its `to_id`. A thought links to at most one other.
+    ///
+    /// # Returns
+    /// - 200: The linked thought, with its new revision
+    /// - 404: The thought does not exist
+    /// - 409: The thought has a newer revision
+    ///
+    pub async fn link_thought(&self, thought: &Thought, to: &ThoughtId) -> ApiResult<Thought> {
+        parse(&self.send(Call::link_thought(thought, to)).await?)
+    }
+
+    ///
+    /// Get pings
+    /// get /api/notifications
+    ///
+    pub async fn get_pings(&self) -> ApiResult<Vec<Ping>> {
+        parse(&self.send(Call::pings()).await?)
+    }
+
+    ///
+    /// Follow a user
+    /// post /api/follow/
+    ///
+    /// Returns `Ok(false)` if the server has no follows (405). A 404 is an
+    /// error: either there's no such user, or no follow route at all, and
+    /// `get_user` tells which.
+    ///
+    pub async fn follow_user(&self, username: &str) -> ApiResult<bool> {
+        follows_supported(self.send(Call::follow(username, true)).await)
+    }
+
+    ///
+    /// Stop following a user
+    /// post /api/unfollow/
+    ///
+    /// Like `follow_user`, `Ok(false)` means the server has no follows.
+    ///
+    pub async fn unfollow_user(&self, username: &str) -> ApiResult<bool> {
+        follows_supported(self.send(Call::follow(username, false)).await)
+    }
+
+    ///
+    /// Log in
+    /// post /api/login/
+    ///
+    /// Returns the server's response, which holds the token. Store it with
+    /// `auth::store_token`.
+    ///
+    pub async fn login(&self, username: &str, password: &str) -> ApiResult<String> {
+        self.send(Call::login(username, password)).await
+    }
+
+    ///
+    /// Register a new account
+    /// post /api/register/
+    ///
+    pub async fn register_user(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+    ) -> ApiResult<()> {
+        let call = Call::register(username, email, password);
+        self.send(call).await?;
+        Ok(())
+    }
+
+    ///
+    /// Revoke the current token on the server
+    /// post /api/logout/
+    ///
+    /// Returns `Ok(false)` if the server has no logout endpoint.
+    ///
+    pub async fn revoke_token(&self) -> ApiResult<bool> {
+        supported(self.send(Call::logout()).await)
+    }
+
+    // Send a call and return the body of a successful response
+    async fn send(&self, call: Call) -> ApiResult<String> {
+        let url = format!("{}{}", self.server, call.path);
+        let mut request = self.http.request(call.method, url);
+        if call.auth {
+            request = request.bearer_auth(bearer(&self.token)?);
+        }
+        for (name, value) in call.headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &call.body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        check_status(status, body)
+    }
+}
+
+// The token, or NotLoggedIn
+pub(crate) fn bearer(token: &Option<String>) -> ApiResult<&str> {
+    match token {
+        Some(token) => Ok(token),
+        None => Err(ApiError::NotLoggedIn),
+    }
+}
+
+// Turn anything but a 2xx into an error, keeping the server's explanation
+pub(crate) fn check_status(status: StatusCode, body: String) -> ApiResult<String> {
+    if status.is_success() {
+        return Ok(body);
+    }
+
+    let message = match status {
+        StatusCode::UNAUTHORIZED => "not authenticated, try logging in again".to_string(),
+        StatusCode::CONFLICT if body.is_empty() => "already exists".to_string(),
+        _ if body.is_empty() => status.canonical_reason().unwrap_or("error").to_string(),
+        _ => body,
+    };
+
+    Err(ApiError::Status {
+        status: status.as_u16(),
+        message,
+    })
+}
+
+// Parse a JSON body
+pub(crate) fn parse<T: DeserializeOwned>(body: &str) -> ApiResult<T> {
+    Ok(serde_json::from_str(body)?)
+}
+
+///
+/// A request to the API, described once for both clients. Each client's
+/// `send` turns it into a reqwest request.
+///
+pub(crate) struct Call {
+    method: Method,
+    // From the server's root, e.g. `/api/thoughts`
+    path: String,
+    // Whether it needs the token
+    auth: bool,
+    headers: Vec<(&'static str, String)>,
+    body: Option<serde_json::Value>,
+}
+
+impl Call {
+    fn new(method: Method, path: String) -> Call {
+        Call {
+            method,
+            path,
+            auth: true,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    // For logging in and registering, before there is a token
+    fn without_token(mut self) -> Call {
+        self.auth = false;
+        self
+    }
+
+    fn header(mut self, name: &'static str, value: String) -> Call {
+        self.headers.push((name, value));
+        self
+    }
+
+    fn json(mut self, body: serde_json::Value) -> Call {
+        self.body = Some(body);
+        self
+    }
+
+    pub(crate) fn user_thoughts(username: &str, limit: u32, pageno: u32) -> Call {
+        let path = format!("/api/user-thoughts/{}", segment(username));
+        Call::new(Method::GET, path)
+            .header("ComindLimit", limit.to_string())
+            .header("ComindPageNo", pageno.to_string())
+    }
+
+    pub(crate) fn user(username: &str) -> Call {
+        Call::new(Method::GET, format!("/api/users/{}", segment(username)))
+    }
+
+    pub(crate) fn user_by_id(user_id: &UserId) -> Call {
+        Call::new(Method::GET, format!("/api/users/id/{}", user_id))
+    }
+
+    // All we need for a new thought is title, body, and user_id
+    pub(crate) fn new_thought(user_id: &UserId, title: &str, body: &str, public: bool) -> Call {
+        Call::new(Method::POST, "/api/thoughts".to_string()).json(json!({
+            "title": title,
+            "body": body,
+            "user_id": user_id,
+            "public": public,
+        }))
+    }
+
+    pub(crate) fn update_thought(
+        id: &ThoughtId,
+        title: Option<&str>,
+        body: &str,
+        public: bool,
+        revision: i32,
+    ) -> Call {
+        Call::new(Method::PUT, format!("/api/thoughts/{}", id)).json(json!({
+            "title": title,
+            "body": body,
+            "public": public,
+            "revision": revision,
+        }))
+    }
+
+    // The thought unchanged, with a link
+    pub(crate) fn link_thought(thought: &Thought, to: &ThoughtId) -> Call {
+        let mut call = Call::update_thought(
+            &thought.id,
+            thought.title.as_deref(),
+            &thought.body,
+            thought.public,
+            thought.revision,
+        );
+        if let Some(body) = call.body.as_mut() {
+            body["to_id"] = json!(to);
+        }
+        call
+    }
+
+    pub(crate) fn pings() -> Call {
+        Call::new(Method::GET, "/api/notifications".to_string())
+    }
+
+    pub(crate) fn follow(username: &str, follow: bool) -> Call {
+        let path = match follow {
+            true => "/api/follow/",
+            false => "/api/unfollow/",
+        };
+        Call::new(Method::POST, path.to_string()).json(json!({ "username": username }))
+    }
+
+    pub(crate) fn login(username: &str, password: &str) -> Call {
+        Call::new(Method::POST, "/api/login/".to_string())
+            .without_token()
+            .json(json!({ "username": username, "password": password }))
+    }
+
+    pub(crate) fn register(username: &str, email: &str, password: &str) -> Call {
+        Call::new(Method::POST, "/api/register/".to_string())
+            .without_token()
+            .json(json!({ "username": username, "email": email, "password": password }))
+    }
+
+    pub(crate) fn logout() -> Call {
+        Call::new(Method::POST, "/api/logout/".to_string())
+    }
+}
+
+// A name as one path segment: anything but letters, digits and `-._~` is
+// percent-encoded, so a name with a `/`, `?` or `#` can't reach another route
+pub(crate) fn segment(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+///
+/// The pages `get_all_user_thoughts` has fetched so far.
+///
+#[derive(Default)]
+pub(crate) struct Pages {
+    thoughts: Vec<Thought>,
+    seen: HashSet<ThoughtId>,
+}
+
+impl Pages {
+    // Add a page. Returns false when there are no more: the page came back
+    // short, or repeats what we have (a server that ignores the page number).
+    pub(crate) fn add(&mut self, page: Vec<Thought>, page_size: u32) -> bool {
+        let last = page.len() < page_size as usize;
+        match page.first() {
+            Some(first) if !self.seen.contains(&first.id) => {}
+            _ => return false,
+        }
+        self.seen.extend(page.iter().map(|thought| thought.id));
+        self.thoughts.extend(page);
+        !last
+    }
+}
+
+// Servers without a logout endpoint answer 404 or 405. That isn't an error,
+// just Ok(false): there is nothing to revoke.
+pub(crate) fn supported(result: ApiResult<String>) -> ApiResult<bool> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(ApiError::Status { status: 404, .. }) | Err(ApiError::Status { status: 405, .. }) => {
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Servers without follows answer 405, and follows can only be kept locally.
+// A 404 stays an error, since it may only mean there's no such user.
+pub(crate) fn follows_supported(result: ApiResult<String>) -> ApiResult<bool> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(ApiError::Status { status: 405, .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_thought;
+
+    #[test]
+    fn names_stay_one_segment() {
+        assert_eq!(segment("cameron_p-1.x~"), "cameron_p-1.x~");
+        assert_eq!(segment("../admin?x=1#y"), "..%2Fadmin%3Fx%3D1%23y");
+        assert_eq!(segment("zoë"), "zo%C3%AB");
+        assert_eq!(Call::user("a/b").path, "/api/users/a%2Fb");
+        assert_eq!(
+            Call::user_thoughts("a b", 10, 2).path,
+            "/api/user-thoughts/a%20b"
+        );
+    }
+
+    #[test]
+    fn links_keep_the_thought_as_it_is() {
+        let thought = test_thought(1, "Title", "body");
+        let to = ThoughtId(uuid::Uuid::from_u128(2));
+        let call = Call::link_thought(&thought, &to);
+
+        assert_eq!(call.method, Method::PUT);
+        assert_eq!(call.path, format!("/api/thoughts/{}", thought.id));
+        let body = call.body.unwrap();
+        assert_eq!(body["title"], "Title");
+        assert_eq!(body["body"], "body");
+        assert_eq!(body["to_id"], to.to_string());
+    }
+
+    #[test]
+    fn logging_in_needs_no_token() {
+        assert!(!Call::login("me", "secret").auth);
+        assert!(!Call::register("me", "me@example.org", "secret").auth);
+        assert!(Call::logout().auth);
+    }
+
+    #[test]
+    fn pages_stop_when_short_or_repeated() {
+        let page = |ids: &[u128]| {
+            ids.iter()
+                .map(|id| test_thought(*id, "", ""))
+                .collect::<Vec<Thought>>()
+        };
+
+        let mut pages = Pages::default();
+        assert!(pages.add(page(&[1, 2]), 2));
+        assert!(!pages.add(page(&[3]), 2));
+        assert_eq!(pages.thoughts.len(), 3);
+
+        // A server that ignores the page number sends the first page again
+        let mut pages = Pages::default();
+        assert!(pages.add(page(&[1, 2]), 2));
+        assert!(!pages.add(page(&[1, 2]), 2));
+        assert_eq!(pages.thoughts.len(), 2);
+    }
+}
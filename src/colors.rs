@@ -1,10 +1,49 @@
 // Color pack contains current colors
-use colored::{Colorize, CustomColor};
+//
+// The brand colors (primary, secondary, tertiary) color command line output
+// through `colored`. The rest is the TUI theme. Themes start from a preset
+// (dark, light or high-contrast) or from a theme defined in the config, with
+// any colors set in `[colors]` on top. With `NO_COLOR` set there are no
+// colors at all, and the TUI gets by with bold, dim and reversed text.
+use std::collections::BTreeMap;
+use std::env;
+use std::str::FromStr;
 
+use colored::CustomColor;
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config::{parse_color, ThemeConfig};
+
+// Names of the built-in themes
+pub const THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+pub const DEFAULT_THEME: &str = "dark";
+
+#[derive(Clone, Debug)]
 pub struct ComindColors {
     primary: CustomColor,
     secondary: CustomColor,
     tertiary: CustomColor,
+
+    // The TUI theme
+    background: Color,
+    foreground: Color,
+    // Timestamps, hints and other secondary text
+    muted: Color,
+    // Background of every other row in a list
+    stripe: Color,
+    // Focused borders, headings and the current tab
+    accent: Color,
+    // Background of the row under the cursor
+    selection: Color,
+    border: Color,
+    unread: Color,
+    private: Color,
+    error: Color,
+    warning: Color,
+    success: Color,
+
+    // No colors, only text attributes
+    plain: bool,
 }
 
 // Extractor methods
@@ -22,6 +61,273 @@ impl ComindColors {
     }
 }
 
+// Styles for the TUI
+impl ComindColors {
+    /// Normal text on the background.
+    pub fn base(&self) -> Style {
+        Style::default().fg(self.foreground).bg(self.background)
+    }
+
+    /// Row `i` of a list, striped.
+    pub fn row(&self, i: usize) -> Style {
+        match i % 2 {
+            0 => self.base(),
+            _ => self.base().bg(self.stripe),
+        }
+    }
+
+    /// The row under the cursor.
+    pub fn selected(&self) -> Style {
+        let style = Style::default()
+            .bg(self.selection)
+            .add_modifier(Modifier::BOLD);
+        match self.plain {
+            true => style.add_modifier(Modifier::REVERSED),
+            false => style,
+        }
+    }
+
+    pub fn muted(&self) -> Style {
+        let style = Style::default().fg(self.muted);
+        match self.plain {
+            true => style.add_modifier(Modifier::DIM),
+            false => style,
+        }
+    }
+
+    pub fn accent(&self) -> Style {
+        Style::default().fg(self.accent)
+    }
+
+    pub fn heading(&self) -> Style {
+        self.accent().add_modifier(Modifier::BOLD)
+    }
+
+    pub fn border(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    /// The border of the focused input.
+    pub fn focused(&self) -> Style {
+        let style = Style::default().fg(self.accent);
+        match self.plain {
+            true => style.add_modifier(Modifier::BOLD),
+            false => style,
+        }
+    }
+
+    pub fn unread(&self) -> Style {
+        Style::default()
+            .fg(self.unread)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    pub fn private(&self) -> Style {
+        Style::default().fg(self.private)
+    }
+
+    pub fn error(&self) -> Style {
+        Style::default().fg(self.error).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn warning(&self) -> Style {
+        Style::default().fg(self.warning)
+    }
+
+    pub fn success(&self) -> Style {
+        Style::default().fg(self.success)
+    }
+}
+
+impl ComindColors {
+    ///
+    /// A built-in theme by name.
+    ///
+    pub fn preset(name: &str) -> Option<ComindColors> {
+        let dark = ComindColors::default();
+        match name {
+            "dark" => Some(dark),
+            "light" => Some(ComindColors {
+                background: Color::White,
+                foreground: Color::Black,
+                muted: Color::Gray,
+                stripe: Color::Rgb(240, 240, 240),
+                accent: Color::Rgb(0, 110, 170),
+                selection: Color::Rgb(210, 228, 242),
+                border: Color::DarkGray,
+                unread: Color::Rgb(0, 110, 170),
+                private: Color::Rgb(170, 60, 40),
+                error: Color::Rgb(180, 0, 0),
+                warning: Color::Rgb(150, 100, 0),
+                success: Color::Rgb(0, 120, 80),
+                ..dark
+            }),
+            "high-contrast" => Some(ComindColors {
+                background: Color::Black,
+                foreground: Color::White,
+                muted: Color::Gray,
+                stripe: Color::Black,
+                accent: Color::LightYellow,
+                selection: Color::Blue,
+                border: Color::White,
+                unread: Color::LightCyan,
+                private: Color::LightMagenta,
+                error: Color::LightRed,
+                warning: Color::LightYellow,
+                success: Color::LightGreen,
+                ..dark
+            }),
+            _ => None,
+        }
+    }
+
+    ///
+    /// No colors, for `NO_COLOR`. Everything is the terminal's own default.
+    ///
+    pub fn plain() -> ComindColors {
+        ComindColors {
+            background: Color::Reset,
+            foreground: Color::Reset,
+            muted: Color::Reset,
+            stripe: Color::Reset,
+            accent: Color::Reset,
+            selection: Color::Reset,
+            border: Color::Reset,
+            unread: Color::Reset,
+            private: Color::Reset,
+            error: Color::Reset,
+            warning: Color::Reset,
+            success: Color::Reset,
+            plain: true,
+            ..ComindColors::default()
+        }
+    }
+
+    ///
+    /// The theme from the config: `[colors]` on top of the theme it names,
+    /// which may be one of `themes` or a preset. Returns every problem found.
+    ///
+    pub fn from_config(
+        colors: &ThemeConfig,
+        themes: &BTreeMap<String, ThemeConfig>,
+    ) -> Result<ComindColors, Vec<String>> {
+        let mut errors = Vec::new();
+        for name in themes.keys() {
+            if THEMES.contains(&name.as_str()) {
+                errors.push(format!(
+                    "themes.{}: that's a built-in theme, pick another name",
+                    name
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let name = colors.theme.as_deref().unwrap_or(DEFAULT_THEME);
+        let mut theme = resolve(name, themes, 0)?;
+        theme.apply(colors, "colors")?;
+        Ok(theme)
+    }
+
+    // Set the colors `overrides` has, reporting the ones that don't parse
+    fn apply(&mut self, overrides: &ThemeConfig, section: &str) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        let brand = [
+            ("primary", &overrides.primary, &mut self.primary),
+            ("secondary", &overrides.secondary, &mut self.secondary),
+            ("tertiary", &overrides.tertiary, &mut self.tertiary),
+        ];
+        for (name, value, color) in brand {
+            if let Some(value) = value {
+                match parse_color(value) {
+                    Ok(parsed) => *color = parsed,
+                    Err(e) => errors.push(format!("{}.{}: {}", section, name, e)),
+                }
+            }
+        }
+
+        let ui = [
+            ("background", &overrides.background, &mut self.background),
+            ("foreground", &overrides.foreground, &mut self.foreground),
+            ("muted", &overrides.muted, &mut self.muted),
+            ("stripe", &overrides.stripe, &mut self.stripe),
+            ("accent", &overrides.accent, &mut self.accent),
+            ("selection", &overrides.selection, &mut self.selection),
+            ("border", &overrides.border, &mut self.border),
+            ("unread", &overrides.unread, &mut self.unread),
+            ("private", &overrides.private, &mut self.private),
+            ("error", &overrides.error, &mut self.error),
+            ("warning", &overrides.warning, &mut self.warning),
+            ("success", &overrides.success, &mut self.success),
+        ];
+        for (name, value, color) in ui {
+            if let Some(value) = value {
+                match Color::from_str(value) {
+                    Ok(parsed) => *color = parsed,
+                    Err(_) => errors.push(format!(
+                        "{}.{}: expected a color name like \"blue\" or a hex color like \"#0089c8\", got `{}`",
+                        section, name, value
+                    )),
+                }
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+// A theme by name, following user themes down to the preset they start from
+fn resolve(
+    name: &str,
+    themes: &BTreeMap<String, ThemeConfig>,
+    depth: usize,
+) -> Result<ComindColors, Vec<String>> {
+    if let Some(preset) = ComindColors::preset(name) {
+        return Ok(preset);
+    }
+
+    let overrides = match themes.get(name) {
+        Some(overrides) => overrides,
+        None => {
+            let mut known = THEMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<String>>();
+            known.extend(themes.keys().cloned());
+            return Err(vec![format!(
+                "unknown theme `{}`, expected one of: {}",
+                name,
+                known.join(", ")
+            )]);
+        }
+    };
+    if depth > themes.len() {
+        return Err(vec![format!(
+            "themes.{}: themes build on each other in a loop",
+            name
+        )]);
+    }
+
+    let base = overrides.theme.as_deref().unwrap_or(DEFAULT_THEME);
+    let mut theme = resolve(base, themes, depth + 1)?;
+    theme.apply(overrides, &format!("themes.{}", name))?;
+    Ok(theme)
+}
+
+///
+/// True if `NO_COLOR` is set, see https://no-color.org.
+///
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR")
+        .map(|value| !value.is_empty())
+        .unwrap_or(false)
+}
+
 // Default color pack
 impl Default for ComindColors {
     fn default() -> Self {
@@ -30,6 +336,22 @@ impl Default for ComindColors {
             primary: CustomColor::new(0, 137, 200),
             secondary: CustomColor::new(207, 94, 74),
             tertiary: CustomColor::new(0, 152, 119),
+
+            // The dark theme
+            background: Color::Black,
+            foreground: Color::White,
+            muted: Color::DarkGray,
+            stripe: Color::Rgb(18, 18, 18),
+            accent: Color::Yellow,
+            selection: Color::Rgb(40, 40, 40),
+            border: Color::Gray,
+            unread: Color::Rgb(0, 137, 200),
+            private: Color::Rgb(207, 94, 74),
+            error: Color::Red,
+            warning: Color::Yellow,
+            success: Color::Green,
+
+            plain: false,
         }
     }
 }
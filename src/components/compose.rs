@@ -0,0 +1,223 @@
+// Composer for new thoughts, and for editing existing ones
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{block::Title, *},
+};
+use tui_textarea::TextArea;
+use uuid::Uuid;
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::components::{spinner, Component};
+use crate::keys::Context;
+use comode::drafts::Draft;
+use comode::outbox::OutboxItem;
+use comode::sync::Edit;
+use comode::types::Thought;
+
+pub struct ComposeView {
+    pub title: TextArea<'static>,
+    pub body: TextArea<'static>,
+    pub public: bool,
+    // Typing in the title (true) or the body (false)
+    pub title_focused: bool,
+    // The thought being edited, if this isn't a new one
+    pub editing: Option<Thought>,
+    // The draft this is autosaved to, once it has been
+    pub draft_id: Option<Uuid>,
+}
+
+impl Default for ComposeView {
+    fn default() -> Self {
+        let mut view = ComposeView {
+            title: TextArea::default(),
+            body: TextArea::default(),
+            public: true,
+            title_focused: false,
+            editing: None,
+            draft_id: None,
+        };
+        view.clear();
+        view
+    }
+}
+
+impl ComposeView {
+    /// Start from an existing thought, e.g. one that failed to send.
+    pub fn edit(&mut self, title: &str, body: &str, public: bool) {
+        self.clear();
+        self.title.insert_str(title);
+        self.body.insert_str(body);
+        self.public = public;
+    }
+
+    /// Edit a sent thought. `title` and `body` may differ from `base`, e.g.
+    /// when resolving a conflict.
+    pub fn edit_thought(&mut self, base: &Thought, title: &str, body: &str) {
+        self.edit(title, body, base.public);
+        self.editing = Some(base.clone());
+    }
+
+    /// Empty both inputs, after a thought is sent.
+    pub fn clear(&mut self) {
+        self.title = TextArea::default();
+        self.title.set_cursor_line_style(Style::default());
+        self.title.set_placeholder_text("title (optional)");
+
+        self.body = TextArea::default();
+        self.body.set_cursor_line_style(Style::default());
+        self.body.set_placeholder_text("what are you thinking?");
+
+        self.title_focused = false;
+        self.editing = None;
+        self.draft_id = None;
+    }
+
+    /// Pick up a saved draft where it was left.
+    pub fn resume(&mut self, draft: &Draft) {
+        self.edit(&draft.title, &draft.body, draft.public);
+        self.editing = draft.editing.clone();
+        self.draft_id = Some(draft.id);
+    }
+
+    /// Nothing typed yet.
+    pub fn is_empty(&self) -> bool {
+        self.title_text().is_empty() && self.body_text().is_empty()
+    }
+
+    /// The body exactly as typed, for drafts.
+    pub fn raw_body(&self) -> String {
+        self.body.lines().join("\n")
+    }
+
+    pub fn title_text(&self) -> String {
+        self.title.lines()[0].trim().to_string()
+    }
+
+    pub fn body_text(&self) -> String {
+        self.body.lines().join("\n").trim().to_string()
+    }
+}
+
+impl Component for ComposeView {
+    // Sending and cancelling are bound in the keymap, the rest is typing
+    fn handle_key(&self, key: KeyEvent) -> Option<Action> {
+        Some(Action::Input(key))
+    }
+
+    fn context(&self) -> Context {
+        Context::Editor
+    }
+
+    fn hints(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("tab", "switch between the title and the body"),
+            ("ctrl-t", "switch between public and private"),
+        ]
+    }
+
+    fn update(&mut self, action: &Action, _data: &Data) -> Vec<Effect> {
+        match action {
+            Action::Submit => {
+                let body = self.body_text();
+                if body.is_empty() {
+                    return Vec::new();
+                }
+                if let Some(base) = &self.editing {
+                    let title = self.title_text();
+                    return vec![Effect::SaveEdit(Edit::new(
+                        base,
+                        Some(title.as_str()),
+                        &body,
+                        self.public,
+                    ))];
+                }
+                return vec![Effect::Enqueue(OutboxItem::new(
+                    &self.title_text(),
+                    &body,
+                    self.public,
+                ))];
+            }
+            Action::Input(key) => {
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    // Switch between title and body
+                    KeyCode::Tab | KeyCode::BackTab => self.title_focused = !self.title_focused,
+                    // Toggle visibility
+                    KeyCode::Char('t') if ctrl => self.public = !self.public,
+                    // The title is a single line
+                    KeyCode::Enter if self.title_focused => self.title_focused = false,
+                    _ if self.title_focused => {
+                        self.title.input(*key);
+                    }
+                    _ => {
+                        self.body.input(*key);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let layout = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(3), Constraint::Fill(1)],
+        )
+        .split(area);
+
+        let visibility = match (self.editing.is_some(), self.public) {
+            (false, true) => "think",
+            (false, false) => "think 🔒",
+            (true, true) => "edit",
+            (true, false) => "edit 🔒",
+        };
+        let sending = if data.loading.contains_key(&Load::Send) {
+            format!(" {} sending... ", spinner(data))
+        } else {
+            String::new()
+        };
+        let focused = data.theme.focused();
+        let unfocused = data.theme.border();
+
+        let mut title = self.title.clone();
+        title.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(if self.title_focused {
+                    focused
+                } else {
+                    unfocused
+                })
+                .title(Span::styled(
+                    format!(" [{}] ", visibility),
+                    if self.public {
+                        Style::default()
+                    } else {
+                        data.theme.private()
+                    },
+                ))
+                .title(Title::from(sending).alignment(Alignment::Right)),
+        );
+
+        let mut body = self.body.clone();
+        body.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(if self.title_focused { unfocused } else { focused })
+                .title_bottom(" ctrl-s: send · tab: title/body · ctrl-t: public/private · ctrl-e: $EDITOR · esc: back "),
+        );
+
+        // Only the focused input shows a cursor
+        if self.title_focused {
+            body.set_cursor_style(Style::default());
+        } else {
+            title.set_cursor_style(Style::default());
+        }
+
+        frame.render_widget(title.widget(), layout[0]);
+        frame.render_widget(body.widget(), layout[1]);
+    }
+}
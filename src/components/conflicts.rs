@@ -0,0 +1,189 @@
+// Thoughts changed both here and on the server, side by side
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect};
+use crate::colors::ComindColors;
+use crate::components::{move_cursor, Component};
+use crate::keys::Context;
+use comode::sync::{diff_lines, Conflict, DiffLine};
+use comode::utils::datetime_to_relative;
+
+#[derive(Default)]
+pub struct ConflictView {
+    // Which conflict is shown
+    pub cursor: usize,
+}
+
+impl Component for ConflictView {
+    // Left and right step between conflicts
+    fn handle_key(&self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Left => Some(Action::Up),
+            KeyCode::Right => Some(Action::Down),
+            _ => None,
+        }
+    }
+
+    fn context(&self) -> Context {
+        Context::Detail
+    }
+
+    fn hints(&self) -> &'static [(&'static str, &'static str)] {
+        &[("left, right", "previous or next conflict")]
+    }
+
+    fn fullscreen(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        if let Action::Up | Action::Down | Action::Top | Action::Bottom = action {
+            self.cursor = move_cursor(self.cursor, data.sync.conflicts.len(), action);
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let theme = &data.theme;
+        let conflicts = &data.sync.conflicts;
+        let conflict = match conflicts.get(self.cursor) {
+            Some(conflict) => conflict,
+            None => {
+                frame.render_widget(Paragraph::new("no conflicts, esc to go back"), area);
+                return;
+            }
+        };
+
+        // Heading, the three versions, the merge, keys
+        let layout = Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Length(1),
+                Constraint::Fill(3),
+                Constraint::Fill(2),
+                Constraint::Length(1),
+            ],
+        )
+        .split(area);
+        let columns =
+            Layout::new(Direction::Horizontal, [Constraint::Ratio(1, 3); 3]).split(layout[1]);
+
+        let title = conflict.remote.title.as_deref().unwrap_or("∘");
+        frame.render_widget(
+            Paragraph::new(format!(
+                " conflict {} of {} · {}",
+                self.cursor + 1,
+                conflicts.len(),
+                title
+            ))
+            .style(theme.heading()),
+            layout[0],
+        );
+
+        let base = conflict.base();
+        frame.render_widget(
+            version(
+                format!(" base · rev {} ", base.revision),
+                base.body.lines().map(Line::from).collect(),
+                theme,
+            ),
+            columns[0],
+        );
+        frame.render_widget(
+            version(
+                format!(
+                    " mine · {} ",
+                    datetime_to_relative(&conflict.edit.edited_at)
+                ),
+                diff(&base.body, &conflict.edit.body, theme),
+                theme,
+            ),
+            columns[1],
+        );
+        frame.render_widget(
+            version(
+                format!(
+                    " theirs · rev {} · {} ",
+                    conflict.remote.revision,
+                    datetime_to_relative(&conflict.remote.date_updated)
+                ),
+                diff(&base.body, &conflict.remote.body, theme),
+                theme,
+            ),
+            columns[2],
+        );
+
+        render_merge(frame, layout[2], conflict, theme);
+
+        frame.render_widget(
+            Paragraph::new(" m: keep mine · t: keep theirs · e: edit the merge · ←→: other conflicts · esc: back ")
+                .style(theme.muted()),
+            layout[3],
+        );
+    }
+}
+
+// One version of the thought, in a box
+fn version<'a>(title: String, lines: Vec<Line<'a>>, theme: &ComindColors) -> Paragraph<'a> {
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border())
+                .title(title),
+        )
+        .wrap(Wrap { trim: false })
+}
+
+// `new` with what changed from `old` marked
+fn diff<'a>(old: &'a str, new: &'a str, theme: &ComindColors) -> Vec<Line<'a>> {
+    diff_lines(old, new)
+        .into_iter()
+        .map(|line| match line {
+            DiffLine::Same(text) => Line::from(format!("  {}", text)),
+            DiffLine::Removed(text) => Line::styled(format!("- {}", text), theme.error()),
+            DiffLine::Added(text) => Line::styled(format!("+ {}", text), theme.success()),
+        })
+        .collect()
+}
+
+// The automatic merge, with conflict markers highlighted
+fn render_merge(frame: &mut Frame, area: Rect, conflict: &Conflict, theme: &ComindColors) {
+    let (_, merge) = conflict.merge();
+    let lines = merge
+        .text
+        .lines()
+        .map(|line| {
+            if line.starts_with("<<<<<<<")
+                || line.starts_with("=======")
+                || line.starts_with(">>>>>>>")
+            {
+                Line::styled(line.to_string(), theme.warning().bold())
+            } else {
+                Line::from(line.to_string())
+            }
+        })
+        .collect::<Vec<Line>>();
+
+    let (title, border) = if merge.conflicted {
+        (
+            " merged · both sides changed the same lines ",
+            theme.warning(),
+        )
+    } else {
+        (" merged ", theme.border())
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border)
+                    .title(title),
+            )
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
@@ -0,0 +1,90 @@
+// Unsent drafts, to resume or delete
+use crossterm::event::MouseEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect};
+use crate::components::{list_mouse, move_cursor, render_list, row_style, Component};
+use comode::utils::datetime_to_relative;
+
+#[derive(Default)]
+pub struct DraftsView {
+    pub cursor: usize,
+    // Opened at startup to offer drafts left over from last time
+    pub recovered: bool,
+}
+
+impl Component for DraftsView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        // The list is inside a border
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        list_mouse(mouse, inner, self.cursor, data.drafts.len())
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        if let Action::Up
+        | Action::Down
+        | Action::Top
+        | Action::Bottom
+        | Action::ScrollUp
+        | Action::ScrollDown
+        | Action::Click(_) = action
+        {
+            self.cursor = move_cursor(self.cursor, data.drafts.len(), action);
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let heading = if self.recovered {
+            " unsent drafts from last time "
+        } else {
+            " drafts "
+        };
+        let theme = &data.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border())
+            .title(heading)
+            .title_bottom(" enter: resume · d: delete · esc: back ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if data.drafts.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no drafts")
+                    .block(Block::default().padding(Padding::uniform(1)))
+                    .style(theme.muted()),
+                inner,
+            );
+            return;
+        }
+
+        let items = data
+            .drafts
+            .iter()
+            .enumerate()
+            .map(|(i, draft)| {
+                let cursor = if i == self.cursor { "👉 " } else { "   " };
+                let kind = match (&draft.editing, draft.public) {
+                    (Some(_), _) => "[edit] ",
+                    (None, true) => "",
+                    (None, false) => "🔒 ",
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::raw(cursor),
+                    Span::styled(kind, theme.accent()),
+                    Span::raw(draft.summary()),
+                    Span::styled(
+                        format!("  {}", datetime_to_relative(&draft.updated_at)),
+                        theme.muted(),
+                    ),
+                ]))
+                .style(row_style(i, i == self.cursor, theme))
+            })
+            .collect::<Vec<ListItem>>();
+
+        render_list(frame, inner, items, Some(self.cursor), theme);
+    }
+}
@@ -0,0 +1,153 @@
+// Recent thoughts of the people you follow, newest first, under a line
+// saying who they are
+use crossterm::event::MouseEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::components::{
+    detail_mouse, move_cursor, preview_mouse, render_placeholder, render_preview,
+    render_thought_detail, render_thought_list, Component,
+};
+use comode::profile::Who;
+use comode::types::Thought;
+
+#[derive(Default)]
+pub struct FollowingView {
+    pub cursor: usize,
+    pub open: bool,
+    // Lines scrolled down in the open or previewed thought
+    pub scroll: usize,
+}
+
+impl FollowingView {
+    fn thought<'a>(&self, data: &'a Data) -> Option<&'a Thought> {
+        data.feed.get(self.cursor)
+    }
+}
+
+// The line of who's followed over the rest
+fn layout(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(1), Constraint::Fill(1)],
+    )
+    .split(area)
+}
+
+fn render_users(frame: &mut Frame, area: Rect, data: &Data) {
+    let theme = &data.theme;
+    let following = &data.following;
+    let mut spans = vec![
+        Span::raw("  following "),
+        Span::styled(following.users.join(", "), theme.accent()),
+    ];
+    if following.local_only {
+        spans.push(Span::styled(" · kept on this device", theme.muted()));
+    } else if !following.pending.is_empty() {
+        spans.push(Span::styled(" · not on the server yet", theme.warning()));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)).style(theme.base()), area);
+}
+
+impl Component for FollowingView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        let thought = self.thought(data);
+        if self.open {
+            return detail_mouse(mouse, area, thought?, self.scroll);
+        }
+        preview_mouse(
+            mouse,
+            layout(area)[1],
+            data,
+            self.cursor,
+            data.feed.len(),
+            thought,
+            self.scroll,
+        )
+    }
+
+    fn author(&self, data: &Data) -> Option<Who> {
+        self.thought(data)
+            .map(|thought| Who::Name(thought.username.clone()))
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        match action {
+            Action::ScrollDetailUp => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::ScrollDetailDown => {
+                self.scroll += 1;
+            }
+            Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::Click(_) => {
+                self.cursor = move_cursor(self.cursor, data.feed.len(), action);
+                self.scroll = 0;
+            }
+            Action::Select => {
+                self.open = !data.feed.is_empty();
+                self.scroll = 0;
+            }
+            Action::Back => {
+                self.open = false;
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        if self.open {
+            if let Some(thought) = self.thought(data) {
+                render_thought_detail(frame, area, thought, self.scroll, &data.theme);
+                return;
+            }
+        }
+
+        if data.following.users.is_empty() {
+            let empty = "you're not following anyone yet, press f on someone's thought or profile";
+            render_placeholder(frame, area, data, Load::Feed, empty);
+            return;
+        }
+
+        let layout = layout(area);
+        render_users(frame, layout[0], data);
+        if data.feed.is_empty() {
+            render_placeholder(
+                frame,
+                layout[1],
+                data,
+                Load::Feed,
+                "nothing from the people you follow yet",
+            );
+            return;
+        }
+
+        // The thought under the cursor next to the list, if there's room
+        let area = match data.panes.split(layout[1]) {
+            Some((list, preview)) => {
+                render_preview(frame, preview, self.thought(data), self.scroll, &data.theme);
+                list
+            }
+            None => layout[1],
+        };
+
+        render_thought_list(
+            frame,
+            area,
+            data.feed.iter(),
+            Some(self.cursor),
+            &data.theme,
+        );
+    }
+}
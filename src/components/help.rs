@@ -0,0 +1,77 @@
+// Key help overlay, built from the active keymap
+use ratatui::{prelude::*, widgets::*};
+
+use crate::colors::ComindColors;
+use crate::components::centered;
+use crate::keys::{Command, Context, Keymap};
+
+pub fn render_help(
+    frame: &mut Frame,
+    area: Rect,
+    keymap: &Keymap,
+    context: Context,
+    hints: &[(&str, &str)],
+    theme: &ComindColors,
+) {
+    let heading = theme.heading();
+    let mut lines = Vec::new();
+
+    // Keys the view handles itself come first, they're the most specific
+    if !hints.is_empty() {
+        lines.push(Line::styled("this view", heading));
+        for (keys, help) in hints {
+            lines.push(help_line(keys, help));
+        }
+    }
+
+    // Then the bindings that apply here, most specific first
+    for context in context.chain() {
+        let commands = Command::all()
+            .iter()
+            .filter(|command| command.context() == *context)
+            .filter_map(|command| {
+                let keys = keymap.keys_for(*command);
+                if keys.is_empty() {
+                    return None;
+                }
+                let keys = keys
+                    .iter()
+                    .map(|chord| chord.to_string())
+                    .collect::<Vec<String>>();
+                Some((keys.join(", "), command.help()))
+            })
+            .collect::<Vec<(String, &str)>>();
+        if commands.is_empty() {
+            continue;
+        }
+
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::styled(context.name(), heading));
+        for (keys, help) in &commands {
+            lines.push(help_line(keys, help));
+        }
+    }
+
+    let area = centered(area, 72, lines.len() as u16 + 2);
+    let help = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border())
+                .title(" keys ")
+                .title_bottom(" ?/esc: close · keys can be changed in the config file "),
+        )
+        .style(theme.base());
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(help, area);
+}
+
+fn help_line<'a>(keys: &str, help: &'a str) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{:>18}  ", keys), Style::default().bold()),
+        Span::raw(help),
+    ])
+}
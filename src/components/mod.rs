@@ -0,0 +1,539 @@
+// Per-view components for the TUI
+//
+// Each tab (and the composer) is a `Component`: it maps the keys and clicks
+// it cares about to actions, updates its own state, and renders itself. Data
+// shared between views lives in `app::Data` and is passed in.
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{layout::Position, prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::colors::ComindColors;
+use crate::config::LayoutConfig;
+use crate::keys::Context;
+use comode::outbox::OutboxItem;
+use comode::profile::Who;
+use comode::types::Thought;
+use comode::utils::datetime_to_relative;
+
+pub mod compose;
+pub mod conflicts;
+pub mod drafts;
+pub mod following;
+pub mod help;
+pub mod palette;
+pub mod pings;
+pub mod profile;
+pub mod search;
+pub mod status;
+pub mod stream;
+pub mod thoughts;
+pub mod whoami;
+
+pub trait Component {
+    /// Map a key that isn't bound in the keymap to an action.
+    fn handle_key(&self, _key: KeyEvent) -> Option<Action> {
+        None
+    }
+
+    /// Map a click or scroll inside the view's `area` to an action.
+    fn handle_mouse(&self, _mouse: MouseEvent, _area: Rect, _data: &Data) -> Option<Action> {
+        None
+    }
+
+    /// Which key bindings apply. A view with a text input focused is
+    /// `Context::Editor`, so bound letter keys are left alone.
+    fn context(&self) -> Context {
+        Context::List
+    }
+
+    /// Keys the view handles itself, for the help overlay.
+    fn hints(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Who wrote the thought or ping under the cursor, for opening their
+    /// profile.
+    fn author(&self, _data: &Data) -> Option<Who> {
+        None
+    }
+
+    /// True if the view wants the whole screen, without the tab bar.
+    fn fullscreen(&self) -> bool {
+        false
+    }
+
+    /// React to an action the app didn't handle itself.
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect>;
+
+    /// Draw the view.
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data);
+}
+
+///
+/// How a list shares the screen with a preview of the thought under the
+/// cursor.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Panes {
+    pub preview: bool,
+    // Percent of the width for the list
+    pub list_width: u16,
+    // Narrower than this, there's no preview
+    pub min_width: u16,
+}
+
+impl Panes {
+    pub fn new(config: &LayoutConfig) -> Panes {
+        Panes {
+            preview: config.preview,
+            list_width: config.list_width,
+            min_width: config.min_width,
+        }
+    }
+
+    ///
+    /// The list and preview areas, or None if the preview is off or `area`
+    /// is too narrow for it.
+    ///
+    pub fn split(&self, area: Rect) -> Option<(Rect, Rect)> {
+        if !self.preview || area.width < self.min_width {
+            return None;
+        }
+
+        let columns = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Percentage(self.list_width),
+                Constraint::Percentage(100 - self.list_width),
+            ],
+        )
+        .split(area);
+        Some((columns[0], columns[1]))
+    }
+}
+
+///
+/// Current frame of the loading spinner.
+///
+pub fn spinner(data: &Data) -> &'static str {
+    const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    FRAMES[(data.ticks / 2) % FRAMES.len()]
+}
+
+///
+/// A `width` x `height` rectangle in the middle of `area`.
+///
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+///
+/// Placeholder for an empty list: a spinner while loading, or `empty`.
+///
+pub fn render_placeholder(frame: &mut Frame, area: Rect, data: &Data, load: Load, empty: &str) {
+    let text = if data.loading.contains_key(&load) {
+        format!("{} loading...", spinner(data))
+    } else {
+        empty.to_string()
+    };
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .block(Block::default().padding(Padding::uniform(2)))
+            .style(data.theme.muted()),
+        area,
+    );
+}
+
+///
+/// Move a list cursor up or down, wrapping around at the ends, or to the
+/// first or last row.
+///
+pub fn move_cursor(cursor: usize, len: usize, action: &Action) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    match action {
+        Action::Up if cursor == 0 => len - 1,
+        Action::Up => (cursor - 1).min(len - 1),
+        Action::Down if cursor + 1 >= len => 0,
+        Action::Down => cursor + 1,
+        Action::Top => 0,
+        Action::Bottom => len - 1,
+        // The wheel stops at the ends
+        Action::ScrollUp => cursor.saturating_sub(1),
+        Action::ScrollDown => (cursor + 1).min(len - 1),
+        Action::Click(row) => (*row).min(len - 1),
+        _ => cursor.min(len - 1),
+    }
+}
+
+///
+/// One row in a list of thoughts.
+///
+/// Two forms of lines to display. If the thought has a newline in it, display
+/// only the title. Otherwise, display only the body. Include the username in
+/// both.
+///
+pub fn thought_item<'a>(
+    thought: &'a Thought,
+    i: usize,
+    selected: bool,
+    theme: &ComindColors,
+) -> ListItem<'a> {
+    let cursor = if selected { "👉 " } else { "   " };
+    let username_style = match thought.public {
+        true => Style::default().bold(),
+        false => theme.private().bold(),
+    };
+    let lock = if thought.public { "" } else { "🔒 " };
+    let username_span = Span::styled(
+        format!("{}[{}] {}", cursor, thought.username, lock),
+        username_style,
+    );
+    let body_or_title = if thought.body.len() < 80 && !thought.body.contains('\n') {
+        Span::raw(thought.body.as_str())
+    } else {
+        match thought.title {
+            Some(ref title) => Span::styled(title.as_str(), Style::default().underlined()),
+            None => Span::raw(thought.body.as_str()),
+        }
+    };
+
+    let body_line = Line::from(vec![username_span, body_or_title]);
+    ListItem::new(body_line).style(row_style(i, selected, theme))
+}
+
+///
+/// One row for a thought still in the outbox, with how it's getting on.
+///
+pub fn pending_item<'a>(
+    item: &'a OutboxItem,
+    i: usize,
+    selected: bool,
+    data: &Data,
+) -> ListItem<'a> {
+    let theme = &data.theme;
+    let (status, style) = if data.sending == Some(item.local_id) {
+        (format!("{} sending", spinner(data)), theme.warning())
+    } else if let Some(error) = &item.failed {
+        (format!("failed: {}", error), theme.error())
+    } else if item.attempts > 0 {
+        (
+            format!("retrying {}", datetime_to_relative(&item.next_attempt)),
+            theme.warning(),
+        )
+    } else {
+        ("pending".to_string(), theme.warning())
+    };
+
+    let cursor = if selected { "👉 " } else { "   " };
+    let text = if item.title.is_empty() {
+        item.body.lines().next().unwrap_or("")
+    } else {
+        item.title.as_str()
+    };
+
+    let line = Line::from(vec![
+        Span::styled(format!("{}[{}] ", cursor, status), style.bold()),
+        Span::styled(text, theme.muted()),
+    ]);
+    ListItem::new(line).style(row_style(i, selected, theme))
+}
+
+///
+/// The style for row `i` of a list: striped, or highlighted under the cursor.
+///
+pub fn row_style(i: usize, selected: bool, theme: &ComindColors) -> Style {
+    match selected {
+        true => theme.row(i).patch(theme.selected()),
+        false => theme.row(i),
+    }
+}
+
+///
+/// A list with a cursor, scrolled to keep the cursor visible.
+///
+pub fn render_list(
+    frame: &mut Frame,
+    area: Rect,
+    items: Vec<ListItem>,
+    cursor: Option<usize>,
+    theme: &ComindColors,
+) {
+    let offset = list_offset(area, cursor.unwrap_or(0));
+    let list = List::new(items)
+        .block(Block::default().padding(Padding::uniform(2)))
+        .style(theme.base());
+    let mut state = ListState::default()
+        .with_selected(cursor)
+        .with_offset(offset);
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+// Rows inside a list's padding
+fn list_inner(area: Rect) -> Rect {
+    Block::default().padding(Padding::uniform(2)).inner(area)
+}
+
+// The first row shown, scrolled just far enough to show the cursor
+fn list_offset(area: Rect, cursor: usize) -> usize {
+    let height = list_inner(area).height.max(1) as usize;
+    cursor.saturating_sub(height - 1)
+}
+
+///
+/// A click or scroll on a list drawn by `render_list` with `len` rows: the
+/// wheel moves the cursor and clicking a row opens it.
+///
+pub fn list_mouse(mouse: MouseEvent, area: Rect, cursor: usize, len: usize) -> Option<Action> {
+    match mouse.kind {
+        MouseEventKind::ScrollUp => Some(Action::ScrollUp),
+        MouseEventKind::ScrollDown => Some(Action::ScrollDown),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let inner = list_inner(area);
+            if !inner.contains(Position::new(mouse.column, mouse.row)) {
+                return None;
+            }
+            let row = list_offset(area, cursor) + (mouse.row - inner.y) as usize;
+            match row < len {
+                true => Some(Action::Click(row)),
+                false => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+///
+/// A list of thoughts with a cursor, scrolled to keep the cursor visible.
+///
+pub fn render_thought_list<'a, I>(
+    frame: &mut Frame,
+    area: Rect,
+    thoughts: I,
+    cursor: Option<usize>,
+    theme: &ComindColors,
+) where
+    I: Iterator<Item = &'a Thought>,
+{
+    let items = thoughts
+        .enumerate()
+        .map(|(i, thought)| thought_item(thought, i, Some(i) == cursor, theme))
+        .collect::<Vec<ListItem>>();
+
+    render_list(frame, area, items, cursor, theme);
+}
+
+///
+/// A single thought, full size, scrolled down `scroll` lines. Links are
+/// underlined so they look clickable.
+///
+pub fn render_thought_detail(
+    frame: &mut Frame,
+    area: Rect,
+    thought: &Thought,
+    scroll: usize,
+    theme: &ComindColors,
+) {
+    // Get Thought title
+    let title = match &thought.title {
+        Some(title) => title.as_str(),
+        None => " ∘ ",
+    };
+
+    let border = match thought.public {
+        true => theme.border(),
+        false => theme.private(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(format!(" {} ", title));
+
+    // Wrapped here rather than by the paragraph, so clicks can be matched
+    // up with the text
+    let width = block.inner(area).width as usize;
+    let lines = wrap(&thought.body, width)
+        .into_iter()
+        .skip(scroll)
+        .map(|line| {
+            let spans = words(&line)
+                .into_iter()
+                .map(|(_, word)| match link(word) {
+                    Some(_) => Span::styled(word.to_string(), theme.accent().underlined()),
+                    None => Span::raw(word.to_string()),
+                })
+                .collect::<Vec<Span>>();
+            Line::from(spans)
+        })
+        .collect::<Vec<Line>>();
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(theme.base())
+        .alignment(Alignment::Left);
+
+    frame.render_widget(paragraph, area);
+}
+
+// Inside the detail view's border
+fn detail_inner(area: Rect) -> Rect {
+    Block::default().borders(Borders::ALL).inner(area)
+}
+
+///
+/// A click or scroll on a thought drawn by `render_thought_detail`: the
+/// wheel scrolls it and clicking a link opens it.
+///
+pub fn detail_mouse(
+    mouse: MouseEvent,
+    area: Rect,
+    thought: &Thought,
+    scroll: usize,
+) -> Option<Action> {
+    let inner = detail_inner(area);
+    let lines = wrap(&thought.body, inner.width as usize);
+
+    match mouse.kind {
+        MouseEventKind::ScrollUp if scroll > 0 => Some(Action::ScrollDetailUp),
+        MouseEventKind::ScrollDown if scroll + (inner.height as usize) < lines.len() => {
+            Some(Action::ScrollDetailDown)
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if !inner.contains(Position::new(mouse.column, mouse.row)) {
+                return None;
+            }
+            let line = lines.get(scroll + (mouse.row - inner.y) as usize)?;
+            let column = (mouse.column - inner.x) as usize;
+            words(line)
+                .into_iter()
+                .find(|(start, word)| *start <= column && column < start + Span::raw(*word).width())
+                .and_then(|(_, word)| link(word))
+                .map(|url| Action::OpenLink(url.to_string()))
+        }
+        _ => None,
+    }
+}
+
+///
+/// Word wrap `text` to `width` columns, keeping its line breaks. Words too
+/// long for a line, like long links, are broken across lines.
+///
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            if !line.is_empty() && Span::raw(format!("{} {}", line, word)).width() > width {
+                lines.push(std::mem::take(&mut line));
+            } else if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+
+            while Span::raw(line.as_str()).width() > width {
+                let split = line
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .skip(1)
+                    .take_while(|i| Span::raw(&line[..*i]).width() <= width)
+                    .last()
+                    .or_else(|| line.char_indices().nth(1).map(|(i, _)| i))
+                    .unwrap_or(line.len());
+                let rest = line.split_off(split);
+                lines.push(std::mem::replace(&mut line, rest));
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+// The words of a line with the column each starts at, spaces included as
+// their own words so the line can be put back together
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut column = 0;
+    for (i, word) in line.split(' ').enumerate() {
+        if i > 0 {
+            words.push((column, " "));
+            column += 1;
+        }
+        if !word.is_empty() {
+            words.push((column, word));
+            column += Span::raw(word).width();
+        }
+    }
+    words
+}
+
+// The link in a word, without punctuation around it
+fn link(word: &str) -> Option<&str> {
+    let start = word.find("https://").or_else(|| word.find("http://"))?;
+    let url = word[start..].trim_end_matches(|c: char| ".,;:!?)]}>'\"".contains(c));
+    match url.ends_with("//") {
+        true => None,
+        false => Some(url),
+    }
+}
+
+///
+/// The preview next to a list: the thought under the cursor, if it is one.
+///
+pub fn render_preview(
+    frame: &mut Frame,
+    area: Rect,
+    thought: Option<&Thought>,
+    scroll: usize,
+    theme: &ComindColors,
+) {
+    match thought {
+        Some(thought) => render_thought_detail(frame, area, thought, scroll, theme),
+        None => frame.render_widget(
+            Paragraph::new("nothing to preview")
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.border())
+                        .padding(Padding::uniform(1)),
+                )
+                .style(theme.muted()),
+            area,
+        ),
+    }
+}
+
+///
+/// A click or scroll on a list with `len` rows, which may have a preview of
+/// `thought` next to it.
+///
+pub fn preview_mouse(
+    mouse: MouseEvent,
+    area: Rect,
+    data: &Data,
+    cursor: usize,
+    len: usize,
+    thought: Option<&Thought>,
+    scroll: usize,
+) -> Option<Action> {
+    let (list, preview) = match data.panes.split(area) {
+        Some(panes) => panes,
+        None => return list_mouse(mouse, area, cursor, len),
+    };
+
+    match preview.contains(Position::new(mouse.column, mouse.row)) {
+        true => detail_mouse(mouse, preview, thought?, scroll),
+        false => list_mouse(mouse, list, cursor, len),
+    }
+}
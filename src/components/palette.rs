@@ -0,0 +1,300 @@
+// Command palette: type part of a command's name to find and run it
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+use tui_textarea::TextArea;
+
+use crate::app::{Action, Data, Effect};
+use crate::components::{centered, move_cursor, Component};
+use crate::keys::{Command, Context};
+use comode::profile::Who;
+
+///
+/// What a palette entry does.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Command(Command),
+    Compose { public: bool },
+    SwitchProfile(String),
+    // Someone's profile, typed as @name
+    UserProfile(String),
+    // Thoughts as Markdown, typed as `export <dir>`
+    Export(String),
+}
+
+impl Target {
+    pub fn action(&self, default_public: bool) -> Action {
+        match self {
+            Target::Command(command) => command.action(default_public),
+            Target::Compose { public } => Action::Compose { public: *public },
+            Target::SwitchProfile(profile) => Action::SwitchProfile(profile.clone()),
+            Target::UserProfile(username) => Action::OpenProfile(Who::Name(username.clone())),
+            Target::Export(dir) => Action::Export(dir.clone()),
+        }
+    }
+}
+
+///
+/// One row in the palette.
+///
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub label: String,
+    // The keys that do the same thing, if any
+    pub keys: String,
+    pub target: Target,
+}
+
+pub struct PaletteView {
+    pub open: bool,
+    query: TextArea<'static>,
+    cursor: usize,
+    entries: Vec<Entry>,
+    // The profile to open when the query is @name, or the folder to export
+    // to when it's `export <dir>`
+    lookup: Option<Entry>,
+}
+
+impl Default for PaletteView {
+    fn default() -> Self {
+        PaletteView {
+            open: false,
+            query: PaletteView::empty_query(),
+            cursor: 0,
+            entries: Vec::new(),
+            lookup: None,
+        }
+    }
+}
+
+impl PaletteView {
+    fn empty_query() -> TextArea<'static> {
+        let mut query = TextArea::default();
+        query.set_cursor_line_style(Style::default());
+        query.set_placeholder_text("search commands, @name for a profile, export <dir>");
+        query
+    }
+
+    ///
+    /// Open the palette with a fresh query over `entries`.
+    ///
+    pub fn show(&mut self, entries: Vec<Entry>) {
+        self.open = true;
+        self.query = PaletteView::empty_query();
+        self.cursor = 0;
+        self.entries = entries;
+        self.lookup = None;
+    }
+
+    pub fn hide(&mut self) {
+        self.open = false;
+    }
+
+    ///
+    /// Entries matching the query, best first.
+    ///
+    pub fn matches(&self) -> Vec<&Entry> {
+        if let Some(lookup) = &self.lookup {
+            return vec![lookup];
+        }
+        let query = self.query.lines()[0].trim().to_lowercase();
+        let mut scored = self
+            .entries
+            .iter()
+            .filter_map(|entry| fuzzy_score(&query, &entry.label).map(|score| (score, entry)))
+            .collect::<Vec<(i32, &Entry)>>();
+
+        // Stable, so equal scores keep the order they were listed in
+        scored.sort_by_key(|(score, _)| -score);
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// The entry under the cursor.
+    pub fn selected(&self) -> Option<Target> {
+        self.matches()
+            .get(self.cursor)
+            .map(|entry| entry.target.clone())
+    }
+}
+
+impl Component for PaletteView {
+    fn handle_key(&self, key: KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => Some(Action::Back),
+            KeyCode::Enter => Some(Action::Select),
+            KeyCode::Up => Some(Action::Up),
+            KeyCode::Char('p') if ctrl => Some(Action::Up),
+            KeyCode::Down => Some(Action::Down),
+            KeyCode::Char('n') if ctrl => Some(Action::Down),
+            _ => Some(Action::Input(key)),
+        }
+    }
+
+    fn context(&self) -> Context {
+        Context::Editor
+    }
+
+    fn update(&mut self, action: &Action, _data: &Data) -> Vec<Effect> {
+        match action {
+            Action::Up | Action::Down => {
+                self.cursor = move_cursor(self.cursor, self.matches().len(), action);
+            }
+            // One line only
+            Action::Input(key) if key.code != KeyCode::Enter => {
+                self.query.input(*key);
+                self.cursor = 0;
+                self.lookup = lookup(&self.query.lines()[0]);
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let theme = &data.theme;
+        let matches = self.matches();
+        let height = (matches.len() as u16).clamp(1, 12) + 5;
+        let area = centered(area, 64, height);
+        frame.render_widget(Clear, area);
+        frame.render_widget(Block::default().style(theme.base()), area);
+
+        let layout = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(3), Constraint::Fill(1)],
+        )
+        .split(area);
+
+        let mut query = self.query.clone();
+        query.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.focused())
+                .title(" : "),
+        );
+        frame.render_widget(query.widget(), layout[0]);
+
+        let width = layout[1].width.saturating_sub(2) as usize;
+        let items = matches
+            .iter()
+            .map(|entry| {
+                let gap =
+                    width.saturating_sub(entry.label.chars().count() + entry.keys.chars().count());
+                ListItem::new(Line::from(vec![
+                    Span::raw(entry.label.as_str()),
+                    Span::raw(" ".repeat(gap)),
+                    Span::styled(entry.keys.as_str(), theme.muted()),
+                ]))
+            })
+            .collect::<Vec<ListItem>>();
+
+        let mut state = ListState::default().with_selected(Some(self.cursor));
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
+                    .border_style(theme.border())
+                    .title_bottom(" enter: run · esc: close "),
+            )
+            .highlight_style(theme.selected());
+        frame.render_stateful_widget(list, layout[1], &mut state);
+    }
+}
+
+// An entry for @name or `export <dir>`, if the query is one. Names can't
+// have spaces, or characters that would change the request's URL.
+fn lookup(query: &str) -> Option<Entry> {
+    if let Some(dir) = query.trim().strip_prefix("export ") {
+        let dir = dir.trim();
+        return Some(Entry {
+            label: format!("export thoughts as Markdown to {}", dir),
+            keys: String::new(),
+            target: Target::Export(dir.to_string()),
+        });
+    }
+
+    let username = query.trim().strip_prefix('@')?;
+    if username.is_empty() || username.contains(|c: char| c.is_whitespace() || "/?#".contains(c)) {
+        return None;
+    }
+    Some(Entry {
+        label: format!("show @{}'s profile", username),
+        keys: String::new(),
+        target: Target::UserProfile(username.to_string()),
+    })
+}
+
+///
+/// Score `text` against `query` if every character of the query appears in
+/// it in order. Runs of consecutive characters and matches at the start of
+/// words score higher, and shorter texts win ties.
+///
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text = text.to_lowercase().chars().collect::<Vec<char>>();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match = None;
+    for (i, c) in text.iter().enumerate() {
+        if next == query.len() {
+            break;
+        }
+        if *c != query[next] {
+            continue;
+        }
+
+        score += 1;
+        if last_match.map(|last| last + 1 == i).unwrap_or(false) {
+            score += 5;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last_match = Some(i);
+        next += 1;
+    }
+
+    match next == query.len() {
+        true => Some(score * 10 - text.len() as i32),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_query_character_must_appear_in_order() {
+        assert!(fuzzy_score("pngs", "Go to pings").is_some());
+        assert!(fuzzy_score("gnip", "Go to pings").is_none());
+        assert!(fuzzy_score("pingz", "Go to pings").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("  ", "anything"), Some(0));
+    }
+
+    #[test]
+    fn runs_and_word_starts_score_higher() {
+        let run = fuzzy_score("out", "Log out").unwrap();
+        let scattered = fuzzy_score("out", "Go to thoughts").unwrap();
+        assert!(run > scattered);
+
+        let word_start = fuzzy_score("r", "Refresh").unwrap();
+        let mid_word = fuzzy_score("r", "Prefs").unwrap();
+        assert!(word_start > mid_word);
+    }
+
+    #[test]
+    fn shorter_texts_win_ties() {
+        assert!(fuzzy_score("quit", "Quit").unwrap() > fuzzy_score("quit", "Quit now").unwrap());
+    }
+}
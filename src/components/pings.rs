@@ -0,0 +1,79 @@
+// Pings (notifications)
+use crossterm::event::MouseEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::components::{
+    list_mouse, move_cursor, render_list, render_placeholder, row_style, Component,
+};
+use comode::profile::Who;
+use comode::utils::datetime_to_relative;
+
+#[derive(Default)]
+pub struct PingsView {
+    pub cursor: usize,
+}
+
+impl Component for PingsView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        list_mouse(mouse, area, self.cursor, data.pings.len())
+    }
+
+    // The user whose thought linked to yours
+    fn author(&self, data: &Data) -> Option<Who> {
+        data.pings
+            .get(self.cursor)
+            .map(|ping| Who::Id(ping.linking_user_id))
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        if let Action::Up
+        | Action::Down
+        | Action::Top
+        | Action::Bottom
+        | Action::ScrollUp
+        | Action::ScrollDown
+        | Action::Click(_) = action
+        {
+            self.cursor = move_cursor(self.cursor, data.pings.len(), action);
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        if data.pings.is_empty() {
+            render_placeholder(frame, area, data, Load::Pings, "no pings yet");
+            return;
+        }
+
+        let theme = &data.theme;
+        let items = data
+            .pings
+            .iter()
+            .enumerate()
+            .map(|(i, ping)| {
+                let cursor = if i == self.cursor { "👉 " } else { "   " };
+
+                // Unread pings stand out
+                let style = if ping.read_status {
+                    Style::default()
+                } else {
+                    theme.unread()
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{}[{}] ", cursor, ping.r#type), style),
+                    Span::styled(ping.message.as_str(), style),
+                    Span::styled(
+                        format!("  {}", datetime_to_relative(&ping.created_at)),
+                        theme.muted(),
+                    ),
+                ]))
+                .style(row_style(i, i == self.cursor, theme))
+            })
+            .collect::<Vec<ListItem>>();
+
+        render_list(frame, area, items, Some(self.cursor), theme);
+    }
+}
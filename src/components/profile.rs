@@ -0,0 +1,253 @@
+// Someone's profile: their public thoughts, how many there are, and which
+// of them link to yours or are linked from yours
+use crossterm::event::MouseEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::colors::ComindColors;
+use crate::components::{
+    detail_mouse, move_cursor, preview_mouse, render_list, render_placeholder, render_preview,
+    render_thought_detail, row_style, Component,
+};
+use comode::profile::{Link, Who};
+use comode::types::Thought;
+use comode::utils::datetime_to_relative;
+
+#[derive(Default)]
+pub struct ProfileView {
+    // Whose profile this is, as it was asked for
+    pub who: Option<Who>,
+    pub cursor: usize,
+    pub open: bool,
+    // Lines scrolled down in the open or previewed thought
+    pub scroll: usize,
+}
+
+impl ProfileView {
+    fn thought<'a>(&self, data: &'a Data) -> Option<&'a Thought> {
+        data.profile.as_ref()?.thoughts.get(self.cursor)
+    }
+
+    // The name to show before the profile has loaded
+    fn name(&self, data: &Data) -> String {
+        match (&data.profile, &self.who) {
+            (Some(profile), _) => profile.user.username.clone(),
+            (None, Some(Who::Name(username))) => username.clone(),
+            (None, _) => "someone".to_string(),
+        }
+    }
+}
+
+// "1 thought", "2 thoughts"
+fn count(n: usize, what: &str) -> String {
+    match n {
+        1 => format!("1 {}", what),
+        n => format!("{} {}s", n, what),
+    }
+}
+
+// The name and counts over the list
+fn render_header(frame: &mut Frame, area: Rect, name: &str, data: &Data) {
+    let theme = &data.theme;
+    let mut title = vec![Span::styled(format!("@{}", name), theme.heading())];
+    if data.following.is_following(name) {
+        title.push(Span::styled("  following", theme.accent()));
+    }
+    let mut lines = vec![Line::from(title)];
+
+    if let Some(profile) = &data.profile {
+        let counts = profile.counts(&data.thoughts);
+        let mut spans = vec![
+            Span::raw(count(counts.thoughts, "public thought")),
+            Span::styled(" · ", theme.muted()),
+            Span::raw(count(counts.links.max(0) as usize, "link")),
+        ];
+        if counts.to_mine > 0 {
+            spans.push(Span::styled(" · ", theme.muted()));
+            spans.push(Span::styled(
+                format!("{} to yours", counts.to_mine),
+                theme.accent(),
+            ));
+        }
+        if counts.from_mine > 0 {
+            spans.push(Span::styled(" · ", theme.muted()));
+            spans.push(Span::styled(
+                format!("{} from yours", counts.from_mine),
+                theme.accent(),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().padding(Padding::horizontal(2)))
+            .style(theme.base()),
+        area,
+    );
+}
+
+// One of their thoughts, with arrows for links to and from yours
+fn profile_item<'a>(
+    thought: &'a Thought,
+    links: &[Link],
+    i: usize,
+    selected: bool,
+    theme: &ComindColors,
+) -> ListItem<'a> {
+    let cursor = if selected { "👉 " } else { "   " };
+    let text = match &thought.title {
+        Some(title) if !title.trim().is_empty() => title.trim(),
+        _ => thought.body.lines().next().unwrap_or(""),
+    };
+
+    let mut spans = vec![
+        Span::raw(cursor),
+        Span::raw(text),
+        Span::styled(
+            format!("  {}", datetime_to_relative(&thought.date_created)),
+            theme.muted(),
+        ),
+    ];
+    for link in links.iter().filter(|link| link.theirs == thought.id) {
+        let arrow = if link.to_mine {
+            "  → yours"
+        } else {
+            "  ← yours"
+        };
+        spans.push(Span::styled(arrow, theme.accent()));
+    }
+
+    ListItem::new(Line::from(spans)).style(row_style(i, selected, theme))
+}
+
+// The header over the rest
+fn layout(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(2), Constraint::Fill(1)],
+    )
+    .split(area)
+}
+
+impl Component for ProfileView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        let thought = self.thought(data);
+        if self.open {
+            return detail_mouse(mouse, area, thought?, self.scroll);
+        }
+        let rows = data
+            .profile
+            .as_ref()
+            .map(|profile| profile.thoughts.len())
+            .unwrap_or(0);
+        preview_mouse(
+            mouse,
+            layout(area)[1],
+            data,
+            self.cursor,
+            rows,
+            thought,
+            self.scroll,
+        )
+    }
+
+    // Whoever this is, so they can be followed from here
+    fn author(&self, data: &Data) -> Option<Who> {
+        match &data.profile {
+            Some(profile) => Some(Who::Name(profile.user.username.clone())),
+            None => self.who.clone(),
+        }
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        let rows = data
+            .profile
+            .as_ref()
+            .map(|profile| profile.thoughts.len())
+            .unwrap_or(0);
+
+        match action {
+            Action::ScrollDetailUp => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::ScrollDetailDown => {
+                self.scroll += 1;
+            }
+            Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::Click(_) => {
+                self.cursor = move_cursor(self.cursor, rows, action);
+                self.scroll = 0;
+            }
+            Action::Select => {
+                self.open = self.cursor < rows;
+                self.scroll = 0;
+            }
+            Action::Back => {
+                self.open = false;
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        if self.open {
+            if let Some(thought) = self.thought(data) {
+                render_thought_detail(frame, area, thought, self.scroll, &data.theme);
+                return;
+            }
+        }
+
+        let layout = layout(area);
+        let name = self.name(data);
+        render_header(frame, layout[0], &name, data);
+
+        let profile = match &data.profile {
+            Some(profile) if !profile.thoughts.is_empty() => profile,
+            Some(_) => {
+                let empty = format!("{} has no public thoughts yet", name);
+                render_placeholder(frame, layout[1], data, Load::Profile, &empty);
+                return;
+            }
+            None => {
+                render_placeholder(
+                    frame,
+                    layout[1],
+                    data,
+                    Load::Profile,
+                    "couldn't load this profile, r tries again",
+                );
+                return;
+            }
+        };
+
+        // The thought under the cursor next to the list, if there's room
+        let area = match data.panes.split(layout[1]) {
+            Some((list, preview)) => {
+                render_preview(frame, preview, self.thought(data), self.scroll, &data.theme);
+                list
+            }
+            None => layout[1],
+        };
+
+        let links = profile.links_with(&data.thoughts);
+        let items = profile
+            .thoughts
+            .iter()
+            .enumerate()
+            .map(|(i, thought)| profile_item(thought, &links, i, i == self.cursor, &data.theme))
+            .collect::<Vec<ListItem>>();
+        render_list(frame, area, items, Some(self.cursor), &data.theme);
+    }
+}
@@ -0,0 +1,259 @@
+// Search through loaded thoughts
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{layout::Position, prelude::*, widgets::*};
+use tui_textarea::TextArea;
+
+use crate::app::{Action, Data, Effect};
+use crate::components::{
+    detail_mouse, move_cursor, preview_mouse, render_preview, render_thought_detail,
+    render_thought_list, Component,
+};
+use crate::keys::Context;
+use comode::profile::Who;
+use comode::types::Thought;
+
+pub struct SearchView {
+    pub query: TextArea<'static>,
+    // Typing into the query box, rather than moving through results
+    pub editing: bool,
+    pub cursor: usize,
+    pub open: bool,
+    // Lines scrolled down in the open or previewed thought
+    pub scroll: usize,
+}
+
+// The query box over the results
+fn layout(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(3), Constraint::Fill(1)],
+    )
+    .split(area)
+}
+
+impl Default for SearchView {
+    fn default() -> Self {
+        let mut query = TextArea::default();
+        query.set_cursor_line_style(Style::default());
+        query.set_placeholder_text("search your thoughts");
+
+        SearchView {
+            query,
+            editing: true,
+            cursor: 0,
+            open: false,
+            scroll: 0,
+        }
+    }
+}
+
+impl SearchView {
+    // The result under the cursor, once we're moving through them
+    fn thought<'a>(&self, data: &'a Data) -> Option<&'a Thought> {
+        match self.editing {
+            true => None,
+            false => self.results(data).get(self.cursor).copied(),
+        }
+    }
+
+    pub fn query(&self) -> String {
+        self.query.lines()[0].trim().to_lowercase()
+    }
+
+    ///
+    /// Thoughts whose title or body contain the query, case-insensitively.
+    ///
+    pub fn results<'a>(&self, data: &'a Data) -> Vec<&'a Thought> {
+        let query = self.query();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        data.thoughts
+            .iter()
+            .filter(|thought| {
+                thought.body.to_lowercase().contains(&query)
+                    || thought
+                        .title
+                        .as_ref()
+                        .map(|title| title.to_lowercase().contains(&query))
+                        .unwrap_or(false)
+            })
+            .collect()
+    }
+}
+
+impl Component for SearchView {
+    fn handle_key(&self, key: KeyEvent) -> Option<Action> {
+        if !self.editing {
+            return None;
+        }
+
+        match key.code {
+            // Done typing, move to the results
+            KeyCode::Enter | KeyCode::Down => Some(Action::Select),
+            KeyCode::Tab => Some(Action::NextTab),
+            KeyCode::BackTab => Some(Action::PrevTab),
+            _ => Some(Action::Input(key)),
+        }
+    }
+
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        let results = self.results(data);
+        if self.open {
+            return detail_mouse(mouse, area, results.get(self.cursor)?, self.scroll);
+        }
+        let thought = self.thought(data);
+
+        // Clicking the query box goes back to typing
+        let layout = layout(area);
+        let position = Position::new(mouse.column, mouse.row);
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left) && layout[0].contains(position) {
+            return Some(Action::Search);
+        }
+        let cursor = if self.editing { 0 } else { self.cursor };
+        preview_mouse(
+            mouse,
+            layout[1],
+            data,
+            cursor,
+            results.len(),
+            thought,
+            self.scroll,
+        )
+    }
+
+    fn context(&self) -> Context {
+        match (self.editing, self.open) {
+            (true, _) => Context::Editor,
+            (false, true) => Context::Detail,
+            (false, false) => Context::List,
+        }
+    }
+
+    fn hints(&self) -> &'static [(&'static str, &'static str)] {
+        match self.editing {
+            true => &[
+                ("enter, down", "show the results"),
+                ("tab, backtab", "switch tabs"),
+            ],
+            false => &[("up", "back to the search box, from the first result")],
+        }
+    }
+
+    fn author(&self, data: &Data) -> Option<Who> {
+        self.thought(data)
+            .map(|thought| Who::Name(thought.username.clone()))
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        let count = self.results(data).len();
+
+        match action {
+            Action::Input(key) if self.editing => {
+                self.query.input(*key);
+                self.cursor = 0;
+            }
+            Action::Select | Action::Back if self.editing => {
+                self.editing = false;
+            }
+            Action::ScrollUp | Action::ScrollDown if self.editing => {}
+            Action::ScrollDetailUp => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::ScrollDetailDown => {
+                self.scroll += 1;
+            }
+            Action::Click(_) => {
+                self.editing = false;
+                self.cursor = move_cursor(self.cursor, count, action);
+                self.scroll = 0;
+            }
+            Action::Select => {
+                self.open = count > 0;
+                self.scroll = 0;
+            }
+            Action::Up if self.cursor == 0 && !self.open => {
+                self.editing = true;
+            }
+            Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::ScrollUp
+            | Action::ScrollDown => {
+                self.cursor = move_cursor(self.cursor, count, action);
+                self.scroll = 0;
+            }
+            Action::Back if self.open => {
+                self.open = false;
+            }
+            Action::Back => {
+                self.editing = true;
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let results = self.results(data);
+
+        if self.open {
+            if let Some(thought) = results.get(self.cursor) {
+                render_thought_detail(frame, area, thought, self.scroll, &data.theme);
+                return;
+            }
+        }
+
+        let layout = layout(area);
+
+        let mut query = self.query.clone();
+        let border = if self.editing {
+            data.theme.focused()
+        } else {
+            data.theme.border()
+        };
+        query.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(" search "),
+        );
+        if !self.editing {
+            query.set_cursor_style(Style::default());
+        }
+        frame.render_widget(query.widget(), layout[0]);
+
+        if results.is_empty() && !self.query().is_empty() {
+            frame.render_widget(
+                Paragraph::new("no matching thoughts")
+                    .block(Block::default().padding(Padding::uniform(2)))
+                    .style(data.theme.muted()),
+                layout[1],
+            );
+            return;
+        }
+
+        // The result under the cursor next to the results, if there's room
+        let area = match data.panes.split(layout[1]) {
+            Some((list, preview)) => {
+                render_preview(frame, preview, self.thought(data), self.scroll, &data.theme);
+                list
+            }
+            None => layout[1],
+        };
+
+        let cursor = if self.editing {
+            None
+        } else {
+            Some(self.cursor)
+        };
+        render_thought_list(frame, area, results.into_iter(), cursor, &data.theme);
+    }
+}
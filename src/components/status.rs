@@ -0,0 +1,110 @@
+// Status bar along the bottom of the screen
+use std::time::Duration;
+
+use chrono::Utc;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::colors::ComindColors;
+use comode::auth::DEFAULT_PROFILE;
+use comode::utils::datetime_to_relative;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Connection {
+    /// Nothing has come back from the server yet
+    Connecting,
+    Online,
+    Offline,
+}
+
+///
+/// Everything the status bar shows, gathered by the app.
+///
+#[derive(Clone, Debug)]
+pub struct Status {
+    pub mode: &'static str,
+    pub username: String,
+    pub profile: String,
+    pub connection: Connection,
+    pub unread: usize,
+    pub unsent: usize,
+    /// Time since the last successful refresh
+    pub refreshed: Option<Duration>,
+    /// A standing note, and whether it needs attention
+    pub note: Option<(String, bool)>,
+    /// A transient message, which takes the note's place
+    pub message: Option<String>,
+}
+
+pub fn render_status(frame: &mut Frame, area: Rect, status: &Status, theme: &ComindColors) {
+    let bar = theme.row(1);
+    let separator = Span::styled(" · ", theme.muted());
+
+    // Mode, account and connection on the left
+    let account = if status.profile == DEFAULT_PROFILE {
+        status.username.clone()
+    } else {
+        format!("{} ({})", status.username, status.profile)
+    };
+    let connection = match status.connection {
+        Connection::Connecting => Span::styled("● connecting", theme.warning()),
+        Connection::Online => Span::styled("● online", theme.success()),
+        Connection::Offline => Span::styled("● offline", theme.error()),
+    };
+    let mut left = vec![
+        Span::styled(
+            format!(" {} ", status.mode),
+            theme.heading().add_modifier(Modifier::REVERSED),
+        ),
+        Span::raw(" "),
+        Span::raw(account),
+        separator.clone(),
+        connection,
+    ];
+
+    // Counts, only when there's something to count
+    if status.unread > 0 {
+        left.push(separator.clone());
+        left.push(Span::styled(
+            format!("{} unread", status.unread),
+            theme.unread(),
+        ));
+    }
+    if status.unsent > 0 {
+        left.push(separator.clone());
+        left.push(Span::styled(
+            format!("{} unsent", status.unsent),
+            theme.warning(),
+        ));
+    }
+    if let Some(refreshed) = status.refreshed {
+        let at = Utc::now()
+            - chrono::Duration::from_std(refreshed).unwrap_or_else(|_| chrono::Duration::zero());
+        left.push(separator.clone());
+        left.push(Span::styled(
+            format!("refreshed {}", datetime_to_relative(&at)),
+            theme.muted(),
+        ));
+    }
+
+    // The message, or a note, on the right
+    let right = match (&status.message, &status.note) {
+        (Some(message), _) => Span::styled(format!("{} ", message), theme.accent()),
+        (None, Some((note, true))) => Span::styled(format!("{} ", note), theme.warning()),
+        (None, Some((note, false))) => Span::styled(format!("{} ", note), theme.muted()),
+        (None, None) => Span::raw(""),
+    };
+
+    let left = Line::from(left);
+    let columns = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Length(left.width() as u16), Constraint::Fill(1)],
+    )
+    .split(area);
+
+    frame.render_widget(Block::default().style(bar), area);
+    frame.render_widget(Paragraph::new(left), columns[0]);
+    frame.render_widget(
+        Paragraph::new(Line::from(right)).alignment(Alignment::Right),
+        columns[1],
+    );
+}
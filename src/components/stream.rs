@@ -0,0 +1,129 @@
+// Stream of recent thoughts, newest first
+use std::cmp::Reverse;
+
+use crossterm::event::MouseEvent;
+use ratatui::prelude::*;
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::components::{
+    detail_mouse, move_cursor, preview_mouse, render_placeholder, render_preview,
+    render_thought_detail, render_thought_list, Component,
+};
+use comode::profile::Who;
+use comode::types::Thought;
+
+#[derive(Default)]
+pub struct StreamView {
+    pub cursor: usize,
+    pub open: bool,
+    // Lines scrolled down in the open or previewed thought
+    pub scroll: usize,
+}
+
+// Thoughts in stream order
+fn stream(data: &Data) -> Vec<&Thought> {
+    let mut thoughts = data.thoughts.iter().collect::<Vec<&Thought>>();
+    thoughts.sort_by_key(|thought| Reverse(thought.date_created));
+    thoughts
+}
+
+impl Component for StreamView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        let thoughts = stream(data);
+        let thought = thoughts.get(self.cursor).copied();
+        if self.open {
+            return detail_mouse(mouse, area, thought?, self.scroll);
+        }
+        preview_mouse(
+            mouse,
+            area,
+            data,
+            self.cursor,
+            thoughts.len(),
+            thought,
+            self.scroll,
+        )
+    }
+
+    fn author(&self, data: &Data) -> Option<Who> {
+        let thoughts = stream(data);
+        thoughts
+            .get(self.cursor)
+            .map(|thought| Who::Name(thought.username.clone()))
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        match action {
+            Action::ScrollDetailUp => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::ScrollDetailDown => {
+                self.scroll += 1;
+            }
+            Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::Click(_) => {
+                self.cursor = move_cursor(self.cursor, data.thoughts.len(), action);
+                self.scroll = 0;
+            }
+            Action::Select => {
+                self.open = !data.thoughts.is_empty();
+                self.scroll = 0;
+            }
+            Action::Back => {
+                self.open = false;
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let thoughts = stream(data);
+
+        if self.open {
+            if let Some(thought) = thoughts.get(self.cursor) {
+                render_thought_detail(frame, area, thought, self.scroll, &data.theme);
+                return;
+            }
+        }
+
+        if data.thoughts.is_empty() {
+            render_placeholder(
+                frame,
+                area,
+                data,
+                Load::Thoughts,
+                "nothing in your stream yet",
+            );
+            return;
+        }
+
+        // The thought under the cursor next to the list, if there's room
+        let area = match data.panes.split(area) {
+            Some((list, preview)) => {
+                let thought = thoughts.get(self.cursor).copied();
+                render_preview(frame, preview, thought, self.scroll, &data.theme);
+                list
+            }
+            None => area,
+        };
+
+        render_thought_list(
+            frame,
+            area,
+            thoughts.into_iter(),
+            Some(self.cursor),
+            &data.theme,
+        );
+    }
+}
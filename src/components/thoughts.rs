@@ -0,0 +1,176 @@
+// Your thoughts, with any still waiting in the outbox at the top, and the
+// filters in use as chips above them
+use crossterm::event::MouseEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::{Action, Data, Effect, Load};
+use crate::components::{
+    detail_mouse, move_cursor, pending_item, preview_mouse, render_list, render_placeholder,
+    render_preview, render_thought_detail, thought_item, Component,
+};
+use crate::keys::Context;
+use comode::profile::Who;
+use comode::types::Thought;
+
+#[derive(Default)]
+pub struct ThoughtsView {
+    // Counts outbox rows first, then thoughts
+    pub cursor: usize,
+    // Showing the thought under the cursor full screen
+    pub open: bool,
+    // Lines scrolled down in the open or previewed thought
+    pub scroll: usize,
+}
+
+impl ThoughtsView {
+    // The sent thought under the cursor, not an outbox row
+    fn thought<'a>(&self, data: &'a Data) -> Option<&'a Thought> {
+        let i = self.cursor.checked_sub(data.outbox.len())?;
+        data.filtered_thoughts().get(i).copied()
+    }
+}
+
+// A line for the filter chips, if any filters are on, over the rest
+fn chips_layout(area: Rect, data: &Data) -> (Option<Rect>, Rect) {
+    if !data.filter.is_active() {
+        return (None, area);
+    }
+    let layout = Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(1), Constraint::Fill(1)],
+    )
+    .split(area);
+    (Some(layout[0]), layout[1])
+}
+
+fn render_chips(frame: &mut Frame, area: Rect, data: &Data) {
+    let theme = &data.theme;
+    let mut spans = vec![Span::raw("  ")];
+    for chip in data.filter.chips() {
+        spans.push(Span::styled(format!(" {} ", chip), theme.selected()));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(" X: clear", theme.muted()));
+    frame.render_widget(Paragraph::new(Line::from(spans)).style(theme.base()), area);
+}
+
+impl Component for ThoughtsView {
+    fn handle_mouse(&self, mouse: MouseEvent, area: Rect, data: &Data) -> Option<Action> {
+        let thought = self.thought(data);
+        if self.open {
+            return detail_mouse(mouse, area, thought?, self.scroll);
+        }
+        let (_, area) = chips_layout(area, data);
+        let rows = data.outbox.len() + data.filtered_thoughts().len();
+        preview_mouse(mouse, area, data, self.cursor, rows, thought, self.scroll)
+    }
+
+    fn context(&self) -> Context {
+        match self.open {
+            true => Context::Detail,
+            false => Context::List,
+        }
+    }
+
+    fn author(&self, data: &Data) -> Option<Who> {
+        self.thought(data)
+            .map(|thought| Who::Name(thought.username.clone()))
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.open
+    }
+
+    fn update(&mut self, action: &Action, data: &Data) -> Vec<Effect> {
+        let rows = data.outbox.len() + data.filtered_thoughts().len();
+
+        match action {
+            Action::ScrollDetailUp => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::ScrollDetailDown => {
+                self.scroll += 1;
+            }
+            Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::Click(_) => {
+                self.cursor = move_cursor(self.cursor, rows, action);
+                self.scroll = 0;
+            }
+            Action::Select => {
+                // Only sent thoughts open; outbox rows are changed with `e`
+                self.open = self.cursor >= data.outbox.len() && self.cursor < rows;
+                self.scroll = 0;
+            }
+            Action::Back => {
+                self.open = false;
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, data: &Data) {
+        let pending = data.outbox.len();
+
+        if self.open {
+            if let Some(thought) = self.thought(data) {
+                render_thought_detail(frame, area, thought, self.scroll, &data.theme);
+                return;
+            }
+        }
+
+        if pending == 0 && data.thoughts.is_empty() {
+            render_placeholder(
+                frame,
+                area,
+                data,
+                Load::Thoughts,
+                "no thoughts yet, press n to write one",
+            );
+            return;
+        }
+
+        let thoughts = data.filtered_thoughts();
+        let (chips, area) = chips_layout(area, data);
+        if let Some(chips) = chips {
+            render_chips(frame, chips, data);
+        }
+        if pending == 0 && thoughts.is_empty() {
+            render_placeholder(
+                frame,
+                area,
+                data,
+                Load::Thoughts,
+                "no thoughts match these filters, X clears them",
+            );
+            return;
+        }
+
+        // The thought under the cursor next to the list, if there's room
+        let area = match data.panes.split(area) {
+            Some((list, preview)) => {
+                render_preview(frame, preview, self.thought(data), self.scroll, &data.theme);
+                list
+            }
+            None => area,
+        };
+
+        let selected = |i: usize| i == self.cursor;
+        let items = data
+            .outbox
+            .iter()
+            .enumerate()
+            .map(|(i, item)| pending_item(item, i, selected(i), data))
+            .chain(thoughts.into_iter().enumerate().map(|(i, thought)| {
+                thought_item(thought, pending + i, selected(pending + i), &data.theme)
+            }))
+            .collect::<Vec<ListItem>>();
+        render_list(frame, area, items, Some(self.cursor), &data.theme);
+    }
+}
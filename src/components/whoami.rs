@@ -0,0 +1,29 @@
+// Session info overlay
+use ratatui::{prelude::*, widgets::*};
+
+use crate::app::Data;
+
+pub fn render_whoami(frame: &mut Frame, area: Rect, data: &Data) {
+    let lines = data
+        .session
+        .iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:>12}: ", label), Style::default().bold()),
+                Span::raw(value.as_str()),
+            ])
+        })
+        .collect::<Vec<Line>>();
+
+    let whoami = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(data.theme.border())
+                .title(" whoami ")
+                .title_bottom(" w/esc: back · L: log out "),
+        )
+        .style(data.theme.base());
+
+    frame.render_widget(whoami, area);
+}
@@ -0,0 +1,572 @@
+// Settings from ~/.config/comode/config.toml
+//
+// Every setting has a default, so the file is optional and only needs the
+// settings you want to change. Unknown settings are errors rather than
+// silently ignored, so a typo doesn't go unnoticed.
+//
+//     # Which [profiles.*] section to use; COMODE_PROFILE or --profile override it
+//     profile = "default"
+//     server = "https://nimbus.pfiffer.org"
+//     keymap = "vim"   # key bindings to start from: default, vim or emacs
+//     mouse = false    # leave the mouse to the terminal, to select text
+//
+//     [profiles.work]
+//     server = "https://comind.example.com"
+//
+//     [thoughts]
+//     page_size = 100
+//     default_visibility = "public"
+//
+//     [layout]
+//     preview = true   # the thought under the cursor next to the list
+//     list_width = 45  # percent of the width for the list
+//     min_width = 100  # narrower than this, thoughts open full screen
+//
+//     [refresh]
+//     interval = 60    # seconds between background refreshes
+//     poll = 50        # milliseconds to wait for a key before redrawing
+//     autosave = 3     # seconds between draft autosaves
+//
+//     [colors]
+//     theme = "light"  # dark, light, high-contrast, or one from [themes.*]
+//     accent = "#0089c8"
+//
+//     [themes.solarized]
+//     theme = "dark"   # the theme to start from
+//     background = "#002b36"
+//
+//     [keys]
+//     quit = ["q", "ctrl-q"]
+//     top = "g g"
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use colored::CustomColor;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use comode::auth::DEFAULT_PROFILE;
+use comode::utils::config_dir;
+use comode::DEFAULT_SERVER;
+
+use crate::colors::{no_color, ComindColors, DEFAULT_THEME};
+use crate::keys::{Command, Keymap, Preset};
+
+///
+/// Everything in the config file.
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub profile: String,
+    pub server: String,
+    #[serde(rename = "keymap")]
+    pub preset: Preset,
+    /// Clicking and scrolling in the TUI
+    pub mouse: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    pub thoughts: ThoughtsConfig,
+    pub layout: LayoutConfig,
+    pub refresh: RefreshConfig,
+    pub colors: ThemeConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, ThemeConfig>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+///
+/// A named account setup, e.g. one per server.
+///
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub server: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThoughtsConfig {
+    pub page_size: u32,
+    pub default_visibility: Visibility,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Show the thought under the cursor next to the list
+    pub preview: bool,
+    /// Percent of the width the list gets next to the preview
+    pub list_width: u16,
+    /// Columns needed for the preview
+    pub min_width: u16,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RefreshConfig {
+    /// Seconds between background refreshes
+    pub interval: u64,
+    /// Milliseconds to wait for input before redrawing
+    pub poll: u64,
+    /// Seconds between draft autosaves
+    pub autosave: u64,
+}
+
+///
+/// Colors to set on top of a theme, in `[colors]` or `[themes.*]`. Brand
+/// colors are hex; the rest can also be color names like "blue".
+///
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// The theme to start from
+    pub theme: Option<String>,
+    pub primary: Option<String>,
+    pub secondary: Option<String>,
+    pub tertiary: Option<String>,
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub muted: Option<String>,
+    pub stripe: Option<String>,
+    pub accent: Option<String>,
+    pub selection: Option<String>,
+    pub border: Option<String>,
+    pub unread: Option<String>,
+    pub private: Option<String>,
+    pub error: Option<String>,
+    pub warning: Option<String>,
+    pub success: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            profile: DEFAULT_PROFILE.to_string(),
+            server: DEFAULT_SERVER.to_string(),
+            preset: Preset::default(),
+            mouse: true,
+            profiles: BTreeMap::new(),
+            thoughts: ThoughtsConfig::default(),
+            layout: LayoutConfig::default(),
+            refresh: RefreshConfig::default(),
+            colors: ThemeConfig::default(),
+            themes: BTreeMap::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ThoughtsConfig {
+    fn default() -> Self {
+        ThoughtsConfig {
+            page_size: 100,
+            default_visibility: Visibility::Public,
+        }
+    }
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            preview: true,
+            list_width: 45,
+            min_width: 100,
+        }
+    }
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig {
+            interval: 60,
+            poll: 50,
+            autosave: 3,
+        }
+    }
+}
+
+///
+/// The server and keyring profile to use.
+///
+#[derive(Clone, Debug)]
+pub struct Account {
+    pub profile: String,
+    pub server: String,
+}
+
+///
+/// Why the config couldn't be used.
+///
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: Option<PathBuf>,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => writeln!(f, "problems in {}:", path.display())?,
+            None => writeln!(f, "problems in the config:")?,
+        }
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+// Limits for numbers that would make comode misbehave
+const MAX_PAGE_SIZE: u32 = 1000;
+const MIN_REFRESH: u64 = 5;
+pub const LIST_WIDTHS: std::ops::RangeInclusive<u16> = 20..=80;
+const MIN_PREVIEW_WIDTH: u16 = 40;
+
+impl Config {
+    /// Where the config file lives.
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    ///
+    /// Load and check the config file, with `profile` (from the command line
+    /// or `COMODE_PROFILE`) overriding the one it names.
+    ///
+    /// A missing file gives the defaults.
+    ///
+    pub fn load(profile: Option<String>) -> Result<Config, ConfigError> {
+        let path = Config::path();
+        let contents = match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(contents)) => Some(contents),
+            Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => None,
+            None => None,
+            Some(Err(e)) => {
+                return Err(ConfigError {
+                    path,
+                    problems: vec![format!("couldn't read it: {}", e)],
+                })
+            }
+        };
+
+        let mut config = match contents {
+            Some(contents) => match toml::from_str::<Config>(&contents) {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(ConfigError {
+                        path,
+                        problems: vec![e.to_string().trim().to_string()],
+                    })
+                }
+            },
+            None => Config::default(),
+        };
+
+        if let Some(profile) = profile.or_else(|| std::env::var("COMODE_PROFILE").ok()) {
+            config.profile = profile;
+        }
+
+        match config.validate() {
+            Ok(()) => Ok(config),
+            Err(problems) => Err(ConfigError { path, problems }),
+        }
+    }
+
+    ///
+    /// Check the values serde can't, returning every problem found.
+    ///
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        if self.profile != DEFAULT_PROFILE && !self.profiles.contains_key(&self.profile) {
+            let known = self.profile_names();
+            problems.push(format!(
+                "profile `{}` isn't defined, add a [profiles.{}] section (known profiles: {})",
+                self.profile,
+                self.profile,
+                known.join(", ")
+            ));
+        }
+        check_server("server", &self.server, &mut problems);
+        for (name, profile) in &self.profiles {
+            if let Some(server) = &profile.server {
+                check_server(&format!("profiles.{}.server", name), server, &mut problems);
+            }
+        }
+
+        if self.thoughts.page_size == 0 || self.thoughts.page_size > MAX_PAGE_SIZE {
+            problems.push(format!(
+                "thoughts.page_size must be between 1 and {}, got {}",
+                MAX_PAGE_SIZE, self.thoughts.page_size
+            ));
+        }
+
+        if !LIST_WIDTHS.contains(&self.layout.list_width) {
+            problems.push(format!(
+                "layout.list_width is a percentage and must be between {} and {}, got {}",
+                LIST_WIDTHS.start(),
+                LIST_WIDTHS.end(),
+                self.layout.list_width
+            ));
+        }
+        if self.layout.min_width < MIN_PREVIEW_WIDTH {
+            problems.push(format!(
+                "layout.min_width is in columns and must be at least {}, got {}",
+                MIN_PREVIEW_WIDTH, self.layout.min_width
+            ));
+        }
+
+        if self.refresh.interval < MIN_REFRESH {
+            problems.push(format!(
+                "refresh.interval is in seconds and must be at least {}, got {}",
+                MIN_REFRESH, self.refresh.interval
+            ));
+        }
+        if !(10..=1000).contains(&self.refresh.poll) {
+            problems.push(format!(
+                "refresh.poll is in milliseconds and must be between 10 and 1000, got {}",
+                self.refresh.poll
+            ));
+        }
+        if self.refresh.autosave == 0 {
+            problems.push("refresh.autosave is in seconds and must be at least 1".to_string());
+        }
+
+        if let Err(errors) = ComindColors::from_config(&self.colors, &self.themes) {
+            problems.extend(errors);
+        }
+
+        if let Err(errors) = self.keymap() {
+            problems.extend(errors);
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems),
+        }
+    }
+
+    /// Every profile that can be used, the default first.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names = vec![DEFAULT_PROFILE.to_string()];
+        names.extend(
+            self.profiles
+                .keys()
+                .filter(|name| *name != DEFAULT_PROFILE)
+                .cloned(),
+        );
+        names
+    }
+
+    /// The server and keyring profile to use.
+    pub fn account(&self) -> Account {
+        let server = self
+            .profiles
+            .get(&self.profile)
+            .and_then(|profile| profile.server.clone())
+            .unwrap_or_else(|| self.server.clone());
+
+        Account {
+            profile: self.profile.clone(),
+            server: server.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn default_public(&self) -> bool {
+        self.thoughts.default_visibility == Visibility::Public
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh.interval)
+    }
+
+    pub fn poll(&self) -> Duration {
+        Duration::from_millis(self.refresh.poll)
+    }
+
+    pub fn autosave(&self) -> Duration {
+        Duration::from_secs(self.refresh.autosave)
+    }
+
+    ///
+    /// The theme, or no colors at all if `NO_COLOR` is set.
+    ///
+    pub fn theme(&self) -> Result<ComindColors, Vec<String>> {
+        let theme = ComindColors::from_config(&self.colors, &self.themes)?;
+        match no_color() {
+            true => Ok(ComindColors::plain()),
+            false => Ok(theme),
+        }
+    }
+
+    /// Key bindings, the preset with `[keys]` on top.
+    pub fn keymap(&self) -> Result<Keymap, Vec<String>> {
+        let overrides = self
+            .keys
+            .iter()
+            .map(|(name, keys)| (name.clone(), keys.clone()))
+            .collect::<HashMap<String, Vec<String>>>();
+        Keymap::new(self.preset, &overrides)
+    }
+
+    ///
+    /// The settings in effect, as TOML, for `comode config`. Every key
+    /// binding is listed, not just the ones the file changes.
+    ///
+    pub fn effective(&self) -> String {
+        let mut effective = self.clone();
+        if effective.colors.theme.is_none() {
+            effective.colors.theme = Some(DEFAULT_THEME.to_string());
+        }
+        let keymap = self.keymap().unwrap_or_default();
+        for command in Command::all() {
+            let keys = keymap
+                .keys_for(*command)
+                .iter()
+                .map(|chord| chord.to_string())
+                .collect();
+            effective.keys.insert(command.name().to_string(), keys);
+        }
+
+        match toml::to_string_pretty(&effective) {
+            Ok(toml) => toml,
+            Err(e) => format!("# couldn't show the settings: {}\n", e),
+        }
+    }
+}
+
+// Servers are http(s) URLs
+fn check_server(name: &str, server: &str, problems: &mut Vec<String>) {
+    if !(server.starts_with("https://") || server.starts_with("http://")) {
+        problems.push(format!(
+            "{} must start with https:// or http://, got `{}`",
+            name, server
+        ));
+    }
+}
+
+///
+/// Parse a `#rrggbb` color.
+///
+pub fn parse_color(value: &str) -> Result<CustomColor, String> {
+    let hex = match value.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => hex,
+        _ => {
+            return Err(format!(
+                "expected a color like \"#0089c8\", got `{}`",
+                value
+            ))
+        }
+    };
+    // Checked before slicing, as a multi-byte character would split
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("`{}` isn't a hex color", value));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    match (channel(0), channel(2), channel(4)) {
+        (Ok(r), Ok(g), Ok(b)) => Ok(CustomColor::new(r, g, b)),
+        _ => Err(format!("`{}` isn't a hex color", value)),
+    }
+}
+
+// `quit = "q"` or `quit = ["q", "ctrl-q"]`
+fn one_or_many<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let keys = BTreeMap::<String, OneOrMany>::deserialize(deserializer)?;
+    Ok(keys
+        .into_iter()
+        .map(|(name, keys)| match keys {
+            OneOrMany::One(key) => (name, vec![key]),
+            OneOrMany::Many(keys) => (name, keys),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn the_defaults_are_valid() {
+        assert_eq!(Config::default().validate(), Ok(()));
+        assert_eq!(parse("").account().server, DEFAULT_SERVER);
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let config = parse(
+            r##"
+            profile = "work"
+            server = "nimbus.pfiffer.org"
+
+            [thoughts]
+            page_size = 0
+
+            [refresh]
+            interval = 1
+            poll = 5000
+
+            [colors]
+            secondary = "red"
+            "##,
+        );
+        let problems = config.validate().unwrap_err();
+        assert_eq!(problems.len(), 6, "{:#?}", problems);
+        assert!(problems[0].starts_with("profile `work` isn't defined"));
+        assert!(problems.iter().any(|p| p.starts_with("colors.secondary:")));
+    }
+
+    #[test]
+    fn profiles_pick_their_server() {
+        let config = parse(
+            r#"
+            profile = "work"
+
+            [profiles.work]
+            server = "https://comind.example.com/"
+            "#,
+        );
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.account().server, "https://comind.example.com");
+        assert_eq!(config.account().profile, "work");
+    }
+
+    #[test]
+    fn typos_are_errors() {
+        assert!(toml::from_str::<Config>("[refresh]\nintervl = 30").is_err());
+    }
+
+    #[test]
+    fn colors_are_hex() {
+        let color = parse_color("#0089c8").unwrap();
+        assert_eq!((color.r, color.g, color.b), (0, 137, 200));
+
+        for bad in ["0089c8", "#0089c", "#0089c8ff", "#00g9c8", "#é0000", "blue"] {
+            assert!(parse_color(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Thoughts still being written.
+//!
+//! The composer is saved as a draft every few seconds and on quit, so a
+//! crash or an accidental Ctrl-C doesn't lose it. A draft is deleted once
+//! its thought is sent.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::types::Thought;
+
+///
+/// An unsent compose buffer.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Draft {
+    pub id: Uuid,
+    pub title: String,
+    pub body: String,
+    pub public: bool,
+    /// The thought this draft edits, if it isn't a new one
+    pub editing: Option<Thought>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Draft {
+    pub fn new(id: Uuid, title: &str, body: &str, public: bool, editing: Option<Thought>) -> Draft {
+        let now = Utc::now();
+        Draft {
+            id,
+            title: title.to_string(),
+            body: body.to_string(),
+            public,
+            editing,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// True if saving `title`, `body` and `public` would change nothing.
+    pub fn is_same(&self, title: &str, body: &str, public: bool) -> bool {
+        self.title == title && self.body == body && self.public == public
+    }
+
+    ///
+    /// Replace the text, keeping when the draft was started.
+    ///
+    pub fn update(&mut self, title: &str, body: &str, public: bool) {
+        self.title = title.to_string();
+        self.body = body.to_string();
+        self.public = public;
+        self.updated_at = Utc::now();
+    }
+
+    /// A one-line summary: the title, or the first line of the body.
+    pub fn summary(&self) -> &str {
+        if !self.title.trim().is_empty() {
+            return self.title.trim();
+        }
+        self.body
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("(empty)")
+    }
+}
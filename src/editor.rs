@@ -0,0 +1,238 @@
+//! Write thoughts in the user's own editor.
+//!
+//! The thought is written to a temporary Markdown file with a small
+//! front-matter header for the title and visibility:
+//!
+//! ```text
+//! ---
+//! title: a title, or nothing
+//! public: true
+//! ---
+//! # Save and quit to continue. An empty body cancels.
+//!
+//! The body, in Markdown.
+//! ```
+//!
+//! `$VISUAL` or `$EDITOR` (falling back to `vi`) is run on the file, and the
+//! file is parsed again when the editor exits.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use uuid::Uuid;
+
+///
+/// A thought as written in the editor.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pub title: String,
+    pub body: String,
+    pub public: bool,
+}
+
+impl Document {
+    pub fn new(title: &str, body: &str, public: bool) -> Document {
+        Document {
+            title: title.to_string(),
+            body: body.to_string(),
+            public,
+        }
+    }
+
+    ///
+    /// The file contents: front matter, a hint, then the body.
+    ///
+    pub fn to_markdown(&self) -> String {
+        format!(
+            "---\ntitle: {}\npublic: {}\n---\n# Save and quit to continue. An empty body cancels.\n\n{}\n",
+            self.title, self.public, self.body
+        )
+    }
+
+    ///
+    /// Parse a file written by `to_markdown`, after editing.
+    ///
+    /// The front matter is optional; without it the whole file is the body
+    /// and the thought is public. Lines starting with `#` in the front matter
+    /// are comments, as is the hint line just after it.
+    ///
+    pub fn parse(text: &str) -> Result<Document, String> {
+        let mut document = Document {
+            public: true,
+            ..Document::default()
+        };
+
+        let mut lines = text.lines().peekable();
+        if lines.peek().map(|line| line.trim()) == Some("---") {
+            lines.next();
+            loop {
+                let line = match lines.next() {
+                    Some(line) => line.trim(),
+                    None => return Err("the front matter isn't closed with ---".to_string()),
+                };
+                if line == "---" {
+                    break;
+                }
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let (key, value) = match line.split_once(':') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => {
+                        return Err(format!(
+                            "expected `key: value` in the front matter, got `{}`",
+                            line
+                        ))
+                    }
+                };
+                match key {
+                    "title" => document.title = value.to_string(),
+                    "public" => document.public = parse_bool(value)?,
+                    "private" => document.public = !parse_bool(value)?,
+                    // Keys from other tools are fine
+                    _ => {}
+                }
+            }
+
+            // The hint written by to_markdown
+            if lines.peek().map(|line| line.starts_with("# Save and quit")) == Some(true) {
+                lines.next();
+            }
+        }
+
+        document.body = lines.collect::<Vec<&str>>().join("\n").trim().to_string();
+        Ok(document)
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("expected true or false, got `{}`", value)),
+    }
+}
+
+///
+/// The editor to run: `$VISUAL`, then `$EDITOR`, then `vi`.
+///
+pub fn editor_command() -> String {
+    for variable in ["VISUAL", "EDITOR"] {
+        if let Ok(editor) = env::var(variable) {
+            if !editor.trim().is_empty() {
+                return editor;
+            }
+        }
+    }
+
+    "vi".to_string()
+}
+
+///
+/// Open `document` in the user's editor and return what they wrote.
+///
+/// Returns `None` if the editor exits with an error (e.g. `:cq` in vim) or
+/// the body is left empty. If the file can't be parsed it is kept, and the
+/// error says where.
+///
+/// The caller is responsible for the terminal: a TUI has to leave raw mode
+/// and the alternate screen first.
+///
+pub fn edit(document: &Document) -> io::Result<Option<Document>> {
+    let path = env::temp_dir().join(format!("comode-{}.md", Uuid::new_v4()));
+    fs::write(&path, document.to_markdown())?;
+
+    let text = match run_editor(&path) {
+        Ok(true) => fs::read_to_string(&path),
+        Ok(false) => {
+            let _ = fs::remove_file(&path);
+            return Ok(None);
+        }
+        Err(e) => Err(e),
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+
+    match Document::parse(&text) {
+        Ok(document) => {
+            let _ = fs::remove_file(&path);
+            if document.body.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(document))
+        }
+        Err(e) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} (your text is still in {})", e, path.display()),
+        )),
+    }
+}
+
+// Run the editor on `path`, returning whether it exited successfully.
+// `$EDITOR` may have arguments, like `code --wait`.
+fn run_editor(path: &Path) -> io::Result<bool> {
+    let command = editor_command();
+    let mut words = command.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => return Err(io::Error::new(io::ErrorKind::NotFound, "no editor set")),
+    };
+
+    let status = match Command::new(program).args(words).arg(path).status() {
+        Ok(status) => status,
+        Err(e) => {
+            return Err(io::Error::new(
+                e.kind(),
+                format!("couldn't run {}: {}, set $EDITOR", program, e),
+            ))
+        }
+    };
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_markdown() {
+        let document = Document::new("A title", "Some *body*\n\nwith two paragraphs", false);
+        assert_eq!(Document::parse(&document.to_markdown()), Ok(document));
+
+        let untitled = Document::new("", "just a body", true);
+        assert_eq!(Document::parse(&untitled.to_markdown()), Ok(untitled));
+    }
+
+    #[test]
+    fn front_matter_is_optional() {
+        assert_eq!(
+            Document::parse("\n  no header here  \n"),
+            Ok(Document::new("", "no header here", true))
+        );
+    }
+
+    #[test]
+    fn front_matter_comments_and_other_keys_are_skipped() {
+        let text = "---\n# a comment\ntitle: Hi\ntags: a, b\nprivate: yes\n---\nbody";
+        assert_eq!(
+            Document::parse(text),
+            Ok(Document::new("Hi", "body", false))
+        );
+    }
+
+    #[test]
+    fn bad_front_matter_is_an_error() {
+        assert!(Document::parse("---\ntitle: never closed\nbody").is_err());
+        assert!(Document::parse("---\njust words\n---\nbody").is_err());
+        assert!(Document::parse("---\npublic: maybe\n---\nbody").is_err());
+    }
+}
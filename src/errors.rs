@@ -30,3 +30,111 @@ pub enum AuthenticationError {
     #[error("Username not found")]
     UsernameNotFound,
 }
+
+// Result type for talking to the server
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    // Network, TLS, timeouts
+    #[error("couldn't reach the server: {0}")]
+    Request(#[from] reqwest::Error),
+
+    // Anything but a 2xx
+    #[error("server returned {status}: {message}")]
+    Status { status: u16, message: String },
+
+    // The body wasn't what we expected
+    #[error("couldn't parse the server's response: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    // The client has no token
+    #[error("not logged in")]
+    NotLoggedIn,
+
+    // Token decoding and storage
+    #[error(transparent)]
+    Auth(#[from] AuthenticationError),
+}
+
+impl ApiError {
+    /// HTTP status code, if the server answered at all
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Worth retrying later: no connection, server trouble, or a session
+    /// that logging in again would fix. Other 4xx answers won't change.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ApiError::Request(_) | ApiError::NotLoggedIn | ApiError::Auth(_) => true,
+            ApiError::Status { status, .. } => {
+                *status >= 500 || *status == 401 || *status == 408 || *status == 429
+            }
+            ApiError::Parse(_) => false,
+        }
+    }
+}
+
+// Result type for backup archives
+pub type ArchiveResult<T> = std::result::Result<T, ArchiveError>;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("couldn't read or write the archive: {0}")]
+    Io(#[from] std::io::Error),
+
+    // Not JSON, or not the JSON we wrote
+    #[error("not a comode backup: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("not a comode backup")]
+    NotAnArchive,
+
+    // Written by a newer comode
+    #[error("the backup is format {0}, newer than this comode reads")]
+    Version(u32),
+
+    // Damaged or edited since it was written
+    #[error("the {0} in the backup don't match its checksum")]
+    Checksum(String),
+
+    #[error("the backup should have {expected} {section} but has {found}")]
+    Count {
+        section: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: u16) -> ApiError {
+        ApiError::Status {
+            status,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn server_trouble_is_transient() {
+        for code in [401, 408, 429, 500, 503] {
+            assert!(status(code).is_transient(), "{}", code);
+        }
+        assert!(ApiError::NotLoggedIn.is_transient());
+    }
+
+    #[test]
+    fn bad_requests_are_not() {
+        for code in [400, 403, 404, 422] {
+            assert!(!status(code).is_transient(), "{}", code);
+        }
+        let parse = serde_json::from_str::<u32>("nope").unwrap_err();
+        assert!(!ApiError::Parse(parse).is_transient());
+    }
+}
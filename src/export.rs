@@ -0,0 +1,120 @@
+// `comode export`: your thoughts as files, for a notes repo
+//
+//   comode export ~/notes/comind                    Markdown, one file each
+//   comode export --format markdown ~/notes/comind
+//
+// Running it again into the same folder only rewrites thoughts that changed
+// since the last export. It needs the server: the cache may hold only the
+// latest page, and thoughts missing from an export look deleted.
+use std::path::Path;
+
+use comode::auth;
+use comode::client::blocking::Client;
+use comode::vault;
+
+use crate::colors::ComindColors;
+use crate::config::Account;
+use crate::display::co_say;
+
+const USAGE: &str = "usage: comode export [--format markdown] <dir>";
+
+pub fn export(account: &Account, page_size: u32, args: &[String], colors: &ComindColors) {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let dir = match parse_args(args) {
+        Ok(dir) => dir,
+        Err(e) => {
+            co_say(&e, colors);
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    let user = match auth::load_user(&account.profile) {
+        Ok(user) => user,
+        Err(_) => {
+            co_say("you're not logged in, run `comode login` first", colors);
+            return;
+        }
+    };
+
+    // Every page of them
+    let client = Client::for_user(&account.server, &user).with_page_size(page_size);
+    let thoughts = match client.get_all_user_thoughts(&user.username) {
+        Ok(thoughts) => thoughts,
+        Err(e) => {
+            co_say(
+                &format!(
+                    "couldn't fetch your thoughts, so nothing was exported: {}",
+                    e
+                ),
+                colors,
+            );
+            return;
+        }
+    };
+
+    let report = match vault::export(Path::new(&dir), &thoughts) {
+        Ok(report) => report,
+        Err(e) => {
+            co_say(&format!("couldn't export to {}: {}", dir, e), colors);
+            return;
+        }
+    };
+
+    // What changed, and what was left alone
+    let mut summary = vec![format!("exported {} thoughts to {}", thoughts.len(), dir)];
+    summary.push(format!("{} written", report.written));
+    if report.unchanged > 0 {
+        summary.push(format!("{} unchanged", report.unchanged));
+    }
+    if report.renamed > 0 {
+        summary.push(format!("{} renamed", report.renamed));
+    }
+    co_say(&summary.join(" · "), colors);
+    if !report.gone.is_empty() {
+        co_say(
+            &format!(
+                "{} files are for thoughts that are gone, left in place: {}",
+                report.gone.len(),
+                report.gone.join(", ")
+            ),
+            colors,
+        );
+    }
+}
+
+// The folder to export to. Markdown is the only format so far.
+fn parse_args(args: &[String]) -> Result<String, String> {
+    let mut dir = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        match flag {
+            "--format" => {
+                let format = match inline.or_else(|| args.next().cloned()) {
+                    Some(format) => format,
+                    None => return Err("--format needs a value".to_string()),
+                };
+                if format != "markdown" && format != "md" {
+                    return Err(format!("--format takes markdown, not `{}`", format));
+                }
+            }
+            _ if flag.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if dir.is_some() => return Err(format!("only one folder, not `{}` too", arg)),
+            _ => dir = Some(arg.clone()),
+        }
+    }
+
+    match dir {
+        Some(dir) => Ok(dir),
+        None => Err("which folder? e.g. `comode export ~/notes/comind`".to_string()),
+    }
+}
@@ -0,0 +1,454 @@
+//! Filtering and sorting a list of thoughts.
+//!
+//! The TUI's thoughts tab and `comode list` share these. A filter narrows
+//! thoughts down by visibility, who wrote them, where and when they were
+//! written, and whether they link anywhere; the sort puts what's left in
+//! order. The TUI keeps its filter in the store so it survives a restart.
+use std::cmp::Reverse;
+
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Origin, Thought};
+
+///
+/// Which thoughts to show, and in what order. The default shows everything
+/// in the order the server sent it.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ThoughtFilter {
+    /// Only public (true) or only private (false) thoughts
+    pub public: Option<bool>,
+    /// Only synthetic (true) or only human (false) thoughts
+    pub synthetic: Option<bool>,
+    pub origin: Option<Origin>,
+    /// Written in the last day, week, month or year
+    pub within: Option<Period>,
+    /// Written on or after this day
+    pub since: Option<NaiveDate>,
+    /// Written on or before this day
+    pub until: Option<NaiveDate>,
+    /// Only thoughts that link to another, or that others link to
+    pub has_links: bool,
+    pub sort: Sort,
+}
+
+///
+/// A stretch of time back from now.
+///
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    pub fn all() -> &'static [Period] {
+        &[Period::Day, Period::Week, Period::Month, Period::Year]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Period> {
+        Period::all()
+            .iter()
+            .find(|period| period.name() == name)
+            .copied()
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            Period::Day => Duration::days(1),
+            Period::Week => Duration::weeks(1),
+            Period::Month => Duration::days(30),
+            Period::Year => Duration::days(365),
+        }
+    }
+}
+
+///
+/// Orders for a list of thoughts.
+///
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    /// As the server sent them
+    #[default]
+    Server,
+    /// Newest first
+    Created,
+    /// Most recently changed first
+    Updated,
+    /// Most linked first
+    Links,
+    /// By title, or the body for untitled thoughts, A to Z
+    Title,
+}
+
+impl Sort {
+    pub fn all() -> &'static [Sort] {
+        &[
+            Sort::Server,
+            Sort::Created,
+            Sort::Updated,
+            Sort::Links,
+            Sort::Title,
+        ]
+    }
+
+    /// The name used on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sort::Server => "server",
+            Sort::Created => "created",
+            Sort::Updated => "updated",
+            Sort::Links => "links",
+            Sort::Title => "title",
+        }
+    }
+
+    /// How the order reads in a sentence.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Sort::Server => "server order",
+            Sort::Created => "newest",
+            Sort::Updated => "recently updated",
+            Sort::Links => "most linked",
+            Sort::Title => "title",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Sort> {
+        Sort::all().iter().find(|sort| sort.name() == name).copied()
+    }
+}
+
+///
+/// One step of a filter control in the TUI. Each one cycles through its
+/// choices and back to showing everything.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Toggle {
+    Visibility,
+    Synthetic,
+    Origin,
+    Period,
+    Links,
+    Sort,
+    Clear,
+}
+
+// Origins to cycle through, the ones the server is known to send
+fn origins() -> [Origin; 5] {
+    [
+        Origin::App,
+        Origin::Web,
+        Origin::Cli,
+        Origin::Api,
+        Origin::Import,
+    ]
+}
+
+///
+/// How many thoughts link to this one, as far as the server said.
+///
+pub fn links(thought: &Thought) -> i32 {
+    thought.n_links.or(thought.numlinks).unwrap_or(0)
+}
+
+impl ThoughtFilter {
+    /// True if the filter hides or reorders anything.
+    pub fn is_active(&self) -> bool {
+        *self != ThoughtFilter::default()
+    }
+
+    pub fn matches(&self, thought: &Thought) -> bool {
+        if self.public.is_some_and(|public| thought.public != public) {
+            return false;
+        }
+        if self
+            .synthetic
+            .is_some_and(|synthetic| thought.synthetic != synthetic)
+        {
+            return false;
+        }
+        if self
+            .origin
+            .as_ref()
+            .is_some_and(|origin| thought.origin != *origin)
+        {
+            return false;
+        }
+
+        let day = thought.date_created.date_naive();
+        if let Some(period) = self.within {
+            if thought.date_created < Utc::now() - period.duration() {
+                return false;
+            }
+        }
+        if self.since.is_some_and(|since| day < since)
+            || self.until.is_some_and(|until| day > until)
+        {
+            return false;
+        }
+
+        !self.has_links || thought.to_id.is_some() || links(thought) > 0
+    }
+
+    ///
+    /// The thoughts that match, sorted. Ties keep the server's order.
+    ///
+    pub fn apply<'a>(&self, thoughts: &'a [Thought]) -> Vec<&'a Thought> {
+        let mut thoughts = thoughts
+            .iter()
+            .filter(|thought| self.matches(thought))
+            .collect::<Vec<&Thought>>();
+
+        match self.sort {
+            Sort::Server => {}
+            Sort::Created => thoughts.sort_by_key(|thought| Reverse(thought.date_created)),
+            Sort::Updated => thoughts.sort_by_key(|thought| Reverse(thought.date_updated)),
+            Sort::Links => thoughts.sort_by_key(|thought| -links(thought)),
+            Sort::Title => thoughts.sort_by_cached_key(|thought| {
+                thought
+                    .title
+                    .as_deref()
+                    .unwrap_or(&thought.body)
+                    .trim()
+                    .to_lowercase()
+            }),
+        }
+        thoughts
+    }
+
+    ///
+    /// Step a filter control on to its next choice.
+    ///
+    pub fn toggle(&mut self, toggle: Toggle) {
+        match toggle {
+            // Everything, then one side, then the other
+            Toggle::Visibility => {
+                self.public = match self.public {
+                    None => Some(true),
+                    Some(true) => Some(false),
+                    Some(false) => None,
+                }
+            }
+            Toggle::Synthetic => {
+                self.synthetic = match self.synthetic {
+                    None => Some(false),
+                    Some(false) => Some(true),
+                    Some(true) => None,
+                }
+            }
+            Toggle::Origin => {
+                let origins = origins();
+                let next = match &self.origin {
+                    None => 0,
+                    Some(origin) => match origins.iter().position(|o| o == origin) {
+                        Some(i) => i + 1,
+                        None => origins.len(),
+                    },
+                };
+                self.origin = origins.get(next).cloned();
+            }
+            // The TUI only has periods; dates come from the command line
+            Toggle::Period => {
+                let periods = Period::all();
+                let next = match self.within {
+                    None => 0,
+                    Some(period) => match periods.iter().position(|p| *p == period) {
+                        Some(i) => i + 1,
+                        None => periods.len(),
+                    },
+                };
+                self.within = periods.get(next).copied();
+                self.since = None;
+                self.until = None;
+            }
+            Toggle::Links => self.has_links = !self.has_links,
+            Toggle::Sort => {
+                let sorts = Sort::all();
+                let i = sorts
+                    .iter()
+                    .position(|sort| *sort == self.sort)
+                    .unwrap_or(0);
+                self.sort = sorts[(i + 1) % sorts.len()];
+            }
+            Toggle::Clear => *self = ThoughtFilter::default(),
+        }
+    }
+
+    ///
+    /// Short descriptions of what the filter does, one per setting.
+    ///
+    pub fn chips(&self) -> Vec<String> {
+        let mut chips = Vec::new();
+        match self.public {
+            Some(true) => chips.push("public".to_string()),
+            Some(false) => chips.push("private".to_string()),
+            None => {}
+        }
+        match self.synthetic {
+            Some(true) => chips.push("synthetic".to_string()),
+            Some(false) => chips.push("human".to_string()),
+            None => {}
+        }
+        if let Some(origin) = &self.origin {
+            chips.push(format!("from {}", origin));
+        }
+        if let Some(period) = self.within {
+            chips.push(format!("past {}", period.name()));
+        }
+        if let Some(since) = self.since {
+            chips.push(format!("since {}", since));
+        }
+        if let Some(until) = self.until {
+            chips.push(format!("until {}", until));
+        }
+        if self.has_links {
+            chips.push("has links".to_string());
+        }
+        if self.sort != Sort::Server {
+            chips.push(format!("sorted by {}", self.sort.label()));
+        }
+        chips
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_thought, ThoughtId};
+    use uuid::Uuid;
+
+    fn day(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    fn ids(thoughts: &[&Thought]) -> Vec<u128> {
+        thoughts
+            .iter()
+            .map(|thought| thought.id.0.as_u128())
+            .collect()
+    }
+
+    #[test]
+    fn the_default_shows_everything_in_server_order() {
+        let thoughts = [test_thought(2, "b", ""), test_thought(1, "a", "")];
+        let filter = ThoughtFilter::default();
+
+        assert!(!filter.is_active());
+        assert_eq!(ids(&filter.apply(&thoughts)), [2, 1]);
+    }
+
+    #[test]
+    fn matches_checks_every_setting() {
+        let mut thought = test_thought(1, "t", "");
+        thought.public = false;
+        thought.synthetic = true;
+        thought.origin = Origin::Cli;
+
+        let matches = |filter: ThoughtFilter| filter.matches(&thought);
+        assert!(matches(ThoughtFilter {
+            public: Some(false),
+            synthetic: Some(true),
+            origin: Some(Origin::Cli),
+            since: Some(day("2024-01-05")),
+            until: Some(day("2024-01-05")),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            public: Some(true),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            synthetic: Some(false),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            origin: Some(Origin::Web),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            since: Some(day("2024-01-06")),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            until: Some(day("2024-01-04")),
+            ..Default::default()
+        }));
+        assert!(!matches(ThoughtFilter {
+            within: Some(Period::Year),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn links_count_either_way() {
+        let unlinked = test_thought(1, "", "");
+        let mut linking = test_thought(2, "", "");
+        linking.to_id = Some(ThoughtId(Uuid::from_u128(1)));
+        let mut linked = test_thought(3, "", "");
+        linked.numlinks = Some(2);
+
+        let filter = ThoughtFilter {
+            has_links: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&filter.apply(&[unlinked, linking, linked])), [2, 3]);
+    }
+
+    #[test]
+    fn sorts_keep_ties_in_server_order() {
+        let mut old = test_thought(1, "b", "");
+        old.date_created -= Duration::days(1);
+        old.n_links = Some(3);
+        let mut new = test_thought(2, "", "A body");
+        new.date_updated += Duration::days(1);
+        let tie = test_thought(3, "C", "");
+        let thoughts = [old, new, tie];
+
+        let sorted = |sort: Sort| {
+            ids(&ThoughtFilter {
+                sort,
+                ..Default::default()
+            }
+            .apply(&thoughts))
+        };
+        assert_eq!(sorted(Sort::Created), [2, 3, 1]);
+        assert_eq!(sorted(Sort::Updated), [2, 1, 3]);
+        assert_eq!(sorted(Sort::Links), [1, 2, 3]);
+        assert_eq!(sorted(Sort::Title), [2, 1, 3]);
+    }
+
+    #[test]
+    fn toggles_cycle_back_to_everything() {
+        let mut filter = ThoughtFilter::default();
+        for public in [Some(true), Some(false), None] {
+            filter.toggle(Toggle::Visibility);
+            assert_eq!(filter.public, public);
+        }
+
+        filter.since = Some(day("2024-01-01"));
+        filter.toggle(Toggle::Period);
+        assert_eq!(filter.within, Some(Period::Day));
+        assert_eq!(filter.since, None);
+
+        filter.toggle(Toggle::Links);
+        assert_eq!(filter.chips(), ["past day", "has links"]);
+        filter.toggle(Toggle::Clear);
+        assert!(!filter.is_active());
+    }
+}
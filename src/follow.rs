@@ -0,0 +1,283 @@
+//! Following other users, and a feed of what they write.
+//!
+//! The follow list is kept locally, and follows and unfollows are pushed to
+//! the server as well when it has a follow endpoint. The feed doesn't rely on
+//! the server knowing who you follow: it polls each followed user's thoughts
+//! with `get_user_thoughts` and merges them, newest first.
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::blocking::Client;
+use crate::errors::ApiError;
+use crate::types::Thought;
+
+/// How many of each user's latest thoughts go into the feed.
+pub const FEED_PAGE: u32 = 20;
+
+///
+/// Who you follow, and the changes to that the server hasn't heard about.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Following {
+    /// Usernames, in the order they were followed
+    pub users: Vec<String>,
+    /// Follows and unfollows not on the server yet
+    pub pending: Vec<Change>,
+    /// The server has no follow endpoint, so follows stay on this device
+    pub local_only: bool,
+}
+
+///
+/// A follow or unfollow to push to the server.
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Change {
+    pub username: String,
+    pub follow: bool,
+}
+
+///
+/// What came of pushing changes.
+///
+#[derive(Debug)]
+pub struct PushOutcome {
+    /// The changes that were tried
+    pub pushed: Vec<Change>,
+    /// The ones the server has now
+    pub done: Vec<Change>,
+    /// The ones that failed, and why. They stay pending.
+    pub errors: Vec<(Change, ApiError)>,
+    /// The ones for users the server doesn't have. They're dropped.
+    pub missing: Vec<Change>,
+    /// False if the server has no follow endpoint
+    pub supported: bool,
+}
+
+///
+/// The latest thoughts of everyone followed, newest first.
+///
+#[derive(Debug)]
+pub struct Feed {
+    /// Who the feed was fetched for
+    pub users: Vec<String>,
+    pub thoughts: Vec<Thought>,
+    /// Users whose thoughts couldn't be fetched, and why
+    pub errors: Vec<(String, ApiError)>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl Following {
+    pub fn is_following(&self, username: &str) -> bool {
+        self.users.iter().any(|user| user == username)
+    }
+
+    ///
+    /// Follow `username`, or unfollow them if they're followed already.
+    /// Returns true if they're followed now.
+    ///
+    pub fn toggle(&mut self, username: &str) -> bool {
+        let follow = !self.is_following(username);
+        match follow {
+            true => self.users.push(username.to_string()),
+            false => self.users.retain(|user| user != username),
+        }
+
+        // Only the latest change for a user needs pushing
+        if !self.local_only {
+            self.pending.retain(|change| change.username != username);
+            self.pending.push(Change {
+                username: username.to_string(),
+                follow,
+            });
+        }
+        follow
+    }
+
+    ///
+    /// Apply what came of a push: settled changes are dropped, users that
+    /// don't exist are unfollowed, and if the server can't follow at all,
+    /// following becomes local only.
+    ///
+    pub fn settle(&mut self, outcome: &PushOutcome) {
+        for change in &outcome.missing {
+            self.users.retain(|user| *user != change.username);
+        }
+        self.pending
+            .retain(|change| !outcome.done.contains(change) && !outcome.missing.contains(change));
+
+        if !outcome.supported {
+            self.local_only = true;
+            self.pending.clear();
+        }
+    }
+}
+
+///
+/// Push follows and unfollows to the server, one at a time. Stops at the
+/// first sign the server has no follow endpoint. A 404 for a user that
+/// doesn't exist is reported in `missing`, and doesn't stop the rest.
+///
+pub fn push(client: &Client, changes: &[Change]) -> PushOutcome {
+    let mut outcome = PushOutcome {
+        pushed: changes.to_vec(),
+        done: Vec::new(),
+        errors: Vec::new(),
+        missing: Vec::new(),
+        supported: true,
+    };
+
+    for change in changes {
+        let result = match change.follow {
+            true => client.follow_user(&change.username),
+            false => client.unfollow_user(&change.username),
+        };
+        match result {
+            Ok(true) => outcome.done.push(change.clone()),
+            Ok(false) => {
+                outcome.supported = false;
+                break;
+            }
+            // No such user, or no follow route: the user lookup says which
+            Err(e @ ApiError::Status { status: 404, .. }) => {
+                match client.get_user(&change.username) {
+                    Err(ApiError::Status { status: 404, .. }) => {
+                        outcome.missing.push(change.clone())
+                    }
+                    Ok(_) => {
+                        outcome.supported = false;
+                        break;
+                    }
+                    Err(_) => outcome.errors.push((change.clone(), e)),
+                }
+            }
+            Err(e) => outcome.errors.push((change.clone(), e)),
+        }
+    }
+
+    outcome
+}
+
+///
+/// Fetch the latest public thoughts of each of `users` and merge them,
+/// newest first. One user failing doesn't stop the others.
+///
+pub fn feed(client: &Client, users: &[String]) -> Feed {
+    let mut thoughts = Vec::new();
+    let mut errors = Vec::new();
+
+    for user in users {
+        match client.get_user_thoughts(user, Some(FEED_PAGE), None) {
+            Ok(theirs) => thoughts.extend(theirs.into_iter().filter(|thought| thought.public)),
+            Err(e) => errors.push((user.clone(), e)),
+        }
+    }
+    thoughts.sort_by_key(|thought| Reverse(thought.date_created));
+
+    Feed {
+        users: users.to_vec(),
+        thoughts,
+        errors,
+        fetched_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(username: &str, follow: bool) -> Change {
+        Change {
+            username: username.to_string(),
+            follow,
+        }
+    }
+
+    #[test]
+    fn toggling_follows_then_unfollows() {
+        let mut following = Following::default();
+
+        assert!(following.toggle("ada"));
+        assert!(following.is_following("ada"));
+        assert!(!following.toggle("ada"));
+        assert!(!following.is_following("ada"));
+        // Only the unfollow is left to push
+        assert_eq!(following.pending, vec![change("ada", false)]);
+    }
+
+    #[test]
+    fn local_follows_push_nothing() {
+        let mut following = Following {
+            local_only: true,
+            ..Following::default()
+        };
+
+        following.toggle("ada");
+        assert_eq!(following.users, vec!["ada"]);
+        assert!(following.pending.is_empty());
+    }
+
+    #[test]
+    fn settling_keeps_what_failed() {
+        let mut following = Following::default();
+        following.toggle("ada");
+        following.toggle("bob");
+
+        following.settle(&PushOutcome {
+            pushed: following.pending.clone(),
+            done: vec![change("ada", true)],
+            errors: Vec::new(),
+            missing: Vec::new(),
+            supported: true,
+        });
+        assert_eq!(following.pending, vec![change("bob", true)]);
+    }
+
+    #[test]
+    fn missing_users_are_unfollowed() {
+        let mut following = Following::default();
+        following.toggle("ada");
+        following.toggle("ghost");
+
+        following.settle(&PushOutcome {
+            pushed: following.pending.clone(),
+            done: vec![change("ada", true)],
+            errors: Vec::new(),
+            missing: vec![change("ghost", true)],
+            supported: true,
+        });
+        assert_eq!(following.users, vec!["ada"]);
+        assert!(following.pending.is_empty());
+    }
+
+    #[test]
+    fn no_follow_endpoint_makes_following_local() {
+        let mut following = Following::default();
+        following.toggle("ada");
+
+        following.settle(&PushOutcome {
+            pushed: following.pending.clone(),
+            done: Vec::new(),
+            errors: Vec::new(),
+            missing: Vec::new(),
+            supported: false,
+        });
+        assert!(following.local_only);
+        assert!(following.pending.is_empty());
+        assert_eq!(following.users, vec!["ada"]);
+    }
+
+    #[test]
+    fn one_unreachable_user_per_error() {
+        // Nothing listens on port 1, so every fetch fails
+        let client = Client::new("http://127.0.0.1:1");
+        let users = vec!["ada".to_string(), "bob".to_string()];
+
+        let feed = feed(&client, &users);
+        assert!(feed.thoughts.is_empty());
+        let failed: Vec<&str> = feed.errors.iter().map(|(user, _)| user.as_str()).collect();
+        assert_eq!(failed, ["ada", "bob"]);
+    }
+}
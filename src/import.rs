@@ -0,0 +1,430 @@
+// `comode import`: thoughts from a folder of notes, or a dump of thoughts
+//
+//   comode import ~/notes/vault             Markdown notes, front matter and all
+//   comode import thoughts.jsonl            a JSON or JSONL dump of thoughts
+//   comode import --dry-run ~/notes/vault   say what would happen, send nothing
+//   comode import --rate 1 ...              at most one request a second
+//   comode import --private ...             notes that don't say are private
+//                                           (or --public)
+//
+// Notes that are thoughts already (the same id, or the same title and body)
+// are skipped, so importing the same folder twice is safe. Once the new
+// thoughts are made, their `[[wikilinks]]` or `links_to` become links. A
+// thought links to one other, so that's the first one that names a thought.
+use std::collections::HashSet;
+use std::io::{stdout, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use comode::auth;
+use comode::client::blocking::Client;
+use comode::errors::{ApiError, ApiResult};
+use comode::types::{Thought, ThoughtId, User};
+use comode::vault::{self, LinkTargets, Note, NoteFiles};
+use uuid::Uuid;
+
+use crate::colors::ComindColors;
+use crate::config::Account;
+use crate::display::co_say;
+
+const USAGE: &str =
+    "usage: comode import [--dry-run] [--rate N] [--private | --public] <dir or file>";
+
+/// Requests a second, unless --rate says otherwise
+pub const DEFAULT_RATE: f64 = 2.0;
+
+/// The slowest --rate: one request an hour
+pub const MIN_RATE: f64 = 1.0 / 3600.0;
+
+// Tries at a request the server said was too many
+const TRIES: u32 = 4;
+
+struct Options {
+    path: String,
+    dry_run: bool,
+    rate: f64,
+    public: Option<bool>,
+}
+
+pub fn import(account: &Account, default_public: bool, args: &[String], colors: &ComindColors) {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            co_say(&e, colors);
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    let user = match auth::load_user(&account.profile) {
+        Ok(user) => user,
+        Err(_) => {
+            co_say("you're not logged in, run `comode login` first", colors);
+            return;
+        }
+    };
+
+    // Read everything first, so a bad file doesn't leave half an import
+    let public = options.public.unwrap_or(default_public);
+    let NoteFiles { notes, skipped } = match vault::read_notes(Path::new(&options.path), public) {
+        Ok(files) => files,
+        Err(e) => {
+            co_say(&format!("couldn't read {}: {}", options.path, e), colors);
+            return;
+        }
+    };
+    for (file, e) in &skipped {
+        println!("  skipped {}: {}", file.display(), e);
+    }
+    if notes.is_empty() {
+        co_say(&format!("nothing to import from {}", options.path), colors);
+        return;
+    }
+
+    let client = Client::for_user(&account.server, &user);
+    run(
+        &client,
+        &user,
+        notes,
+        &[],
+        options.dry_run,
+        options.rate,
+        colors,
+    );
+}
+
+///
+/// Make thoughts of `notes`, skipping the ones that are thoughts already,
+/// then link them. Links to the ids in `keep` are left as they are, for
+/// thoughts that are on the server but not among the user's. `comode restore`
+/// uses this too.
+///
+pub fn run(
+    client: &Client,
+    user: &User,
+    notes: Vec<Note>,
+    keep: &[ThoughtId],
+    dry_run: bool,
+    rate: f64,
+    colors: &ComindColors,
+) {
+    // What's there already, to skip and to link to
+    let existing = match client.get_all_user_thoughts(&user.username) {
+        Ok(existing) => existing,
+        Err(e) => {
+            co_say(
+                &format!(
+                    "couldn't fetch your thoughts to check for duplicates: {}",
+                    e
+                ),
+                colors,
+            );
+            return;
+        }
+    };
+    let plan = vault::plan_import(notes, &existing);
+    let mut targets = LinkTargets::new(&existing);
+    for id in keep {
+        targets.keep(*id);
+    }
+    for (note, id) in &plan.existing {
+        targets.add(note, *id);
+    }
+
+    let mut summary = vec![format!("{} new", plan.new.len())];
+    if !plan.existing.is_empty() {
+        summary.push(format!("{} already there", plan.existing.len()));
+    }
+    if plan.repeated > 0 {
+        summary.push(format!("{} repeated", plan.repeated));
+    }
+    if plan.empty > 0 {
+        summary.push(format!("{} empty", plan.empty));
+    }
+
+    if dry_run {
+        rehearse(&plan.new, targets, &summary, colors);
+        return;
+    }
+    if plan.new.is_empty() {
+        co_say(
+            &format!("nothing new to import: {}", summary.join(" · ")),
+            colors,
+        );
+        return;
+    }
+
+    // Make the thoughts
+    let mut throttle = Throttle::new(rate);
+    let mut created = Vec::new();
+    let mut failed = Vec::new();
+    let total = plan.new.len();
+    for (i, note) in plan.new.into_iter().enumerate() {
+        print!("\rimporting {}/{}", i + 1, total);
+        let _ = stdout().flush();
+
+        let result = throttle
+            .send(|| client.make_new_thought(&user.user_id, &note.title, &note.body, note.public));
+        match result {
+            Ok(()) => created.push(note),
+            // No point going on without the server
+            Err(e @ ApiError::Request(_)) => {
+                println!();
+                co_say(
+                    &format!("stopped after {} of {}: {}", created.len(), total, e),
+                    colors,
+                );
+                failed.push((note.source, e));
+                break;
+            }
+            Err(e) => failed.push((note.source, e)),
+        }
+    }
+    println!();
+    for (source, e) in &failed {
+        println!("  couldn't import {}: {}", source, e);
+    }
+
+    let linked = link(
+        client,
+        &user.username,
+        &existing,
+        &created,
+        targets,
+        &mut throttle,
+        colors,
+    );
+
+    let mut summary = vec![format!("imported {} thoughts", created.len())];
+    if linked > 0 {
+        summary.push(format!("{} linked", linked));
+    }
+    if !plan.existing.is_empty() {
+        summary.push(format!("{} already there", plan.existing.len()));
+    }
+    if !failed.is_empty() {
+        summary.push(format!("{} failed", failed.len()));
+    }
+    co_say(&summary.join(" · "), colors);
+}
+
+// What an import would do, without sending anything
+fn rehearse(new: &[Note], mut targets: LinkTargets, summary: &[String], colors: &ComindColors) {
+    // Stand-in ids, so links between new notes resolve
+    let ids = new
+        .iter()
+        .map(|note| {
+            let id = ThoughtId(Uuid::new_v4());
+            targets.add(note, id);
+            id
+        })
+        .collect::<Vec<ThoughtId>>();
+
+    let mut links = 0;
+    for (note, id) in new.iter().zip(&ids) {
+        let target = targets.target(note).filter(|target| target != id);
+        if target.is_some() {
+            links += 1;
+        }
+        println!("  + {}", title_or_source(note));
+        let unresolved = targets.unresolved(note);
+        if !unresolved.is_empty() {
+            println!("    no note called {}", unresolved.join(", "));
+        }
+    }
+
+    co_say(
+        &format!(
+            "dry run, nothing sent: {} · {} would be linked",
+            summary.join(" · "),
+            links
+        ),
+        colors,
+    );
+}
+
+// Turn links in the new notes into links between thoughts. Returns how many
+// were linked.
+fn link(
+    client: &Client,
+    username: &str,
+    existing: &[Thought],
+    created: &[Note],
+    mut targets: LinkTargets,
+    throttle: &mut Throttle,
+    colors: &ComindColors,
+) -> usize {
+    let wants_link = |note: &Note| note.links_to.is_some() || !note.wikilinks.is_empty();
+    if !created.iter().any(wants_link) {
+        return 0;
+    }
+
+    // Making a thought doesn't say its id, so find the new ones
+    let all = match throttle.send(|| client.get_all_user_thoughts(username)) {
+        Ok(all) => all,
+        Err(e) => {
+            co_say(
+                &format!("couldn't fetch the new thoughts to link them: {}", e),
+                colors,
+            );
+            return 0;
+        }
+    };
+    let old = existing
+        .iter()
+        .map(|thought| thought.id)
+        .collect::<HashSet<ThoughtId>>();
+    let mut fresh = all
+        .into_iter()
+        .filter(|thought| !old.contains(&thought.id))
+        .collect::<Vec<Thought>>();
+
+    let mut made = Vec::new();
+    for note in created {
+        let position = match fresh.iter().position(|thought| note.same_as(thought)) {
+            Some(position) => position,
+            None => continue,
+        };
+        let thought = fresh.remove(position);
+        targets.add(note, thought.id);
+        made.push((note, thought));
+    }
+
+    let mut linked = 0;
+    for (note, thought) in made {
+        let target = match targets.target(note).filter(|target| *target != thought.id) {
+            Some(target) => target,
+            None => continue,
+        };
+        match throttle.send(|| client.link_thought(&thought, &target)) {
+            Ok(_) => linked += 1,
+            Err(e) => println!("  couldn't link {}: {}", note.source, e),
+        }
+    }
+    linked
+}
+
+fn title_or_source(note: &Note) -> &str {
+    match note.title.trim().is_empty() {
+        true => &note.source,
+        false => note.title.trim(),
+    }
+}
+
+// Keeps requests to a rate, and slows down when the server says so
+struct Throttle {
+    gap: Duration,
+    last: Option<Instant>,
+}
+
+impl Throttle {
+    fn new(rate: f64) -> Throttle {
+        Throttle {
+            gap: Duration::from_secs_f64(1.0 / rate),
+            last: None,
+        }
+    }
+
+    fn send<T>(&mut self, request: impl Fn() -> ApiResult<T>) -> ApiResult<T> {
+        let mut tries = 1;
+        loop {
+            if let Some(last) = self.last {
+                let since = last.elapsed();
+                if since < self.gap {
+                    sleep(self.gap - since);
+                }
+            }
+            self.last = Some(Instant::now());
+
+            // Too many requests: half the rate, and try again
+            match request() {
+                Err(ApiError::Status { status: 429, .. }) if tries < TRIES => {
+                    self.gap *= 2;
+                    tries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A `--rate`, in requests a second, from one an hour up.
+pub fn parse_rate(rate: &str) -> Result<f64, String> {
+    match rate.parse::<f64>() {
+        Ok(rate) if rate >= MIN_RATE && rate.is_finite() => Ok(rate),
+        Ok(rate) if rate > 0.0 => Err(format!(
+            "--rate {} is slower than one request an hour",
+            rate
+        )),
+        _ => Err(format!("--rate takes requests a second, not `{}`", rate)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: String::new(),
+        dry_run: false,
+        rate: DEFAULT_RATE,
+        public: None,
+    };
+    let mut path = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        match flag {
+            "--dry-run" | "-n" => options.dry_run = true,
+            "--private" | "-p" => options.public = Some(false),
+            "--public" => options.public = Some(true),
+            "--rate" => {
+                let rate = match inline.or_else(|| args.next().cloned()) {
+                    Some(rate) => rate,
+                    None => return Err("--rate needs a value".to_string()),
+                };
+                options.rate = parse_rate(&rate)?;
+            }
+            _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_some() => {
+                return Err(format!("only one folder or file, not `{}` too", arg))
+            }
+            _ => path = Some(arg.clone()),
+        }
+    }
+
+    options.path = match path {
+        Some(path) => path,
+        None => return Err("import what? e.g. `comode import ~/notes/vault`".to_string()),
+    };
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_are_positive_and_finite() {
+        assert_eq!(parse_rate("2"), Ok(2.0));
+        assert_eq!(parse_rate("0.5"), Ok(0.5));
+        for bad in ["0", "-1", "inf", "NaN", "fast"] {
+            assert!(parse_rate(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rates_stop_at_one_an_hour() {
+        assert!(parse_rate(&MIN_RATE.to_string()).is_ok());
+        assert!(parse_rate("1e-20").is_err());
+        // The slowest rate still makes a gap a Duration can hold
+        Throttle::new(MIN_RATE);
+    }
+}
@@ -0,0 +1,643 @@
+// Key bindings
+//
+// Keys are written as strings in the config file: a character (`q`, `N`,
+// `?`), a named key (`tab`, `enter`, `esc`, `up`, `f1`, ...), or either with
+// modifiers (`ctrl-s`, `alt-enter`, `shift-tab`). Keys separated by spaces
+// are a chord, pressed one after the other (`g g`, `ctrl-x ctrl-s`).
+//
+// Each command belongs to a context. List and detail views also get the
+// global commands, but text entry only gets its own, so typing a `q` into
+// the composer doesn't quit.
+use std::collections::HashMap;
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::app::Action;
+use comode::filter::Toggle;
+use comode::sync::Resolution;
+
+///
+/// Where a binding applies.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Context {
+    /// Everywhere but text entry
+    Global,
+    /// Moving through a list of thoughts, pings or drafts
+    List,
+    /// Reading one thought, or resolving a conflict
+    Detail,
+    /// Typing into the composer or the search box
+    Editor,
+}
+
+impl Context {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Context::Global => "everywhere",
+            Context::List => "lists",
+            Context::Detail => "detail views",
+            Context::Editor => "text entry",
+        }
+    }
+
+    /// The contexts whose bindings apply here, most specific first.
+    pub fn chain(&self) -> &'static [Context] {
+        match self {
+            Context::Global => &[Context::Global],
+            Context::List => &[Context::List, Context::Global],
+            Context::Detail => &[Context::Detail, Context::Global],
+            Context::Editor => &[Context::Editor],
+        }
+    }
+}
+
+///
+/// A set of default bindings to start from.
+///
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+// Commands that can be bound, with their config names, contexts and default keys
+macro_rules! commands {
+    ($($variant:ident => $name:literal $context:ident [$($key:literal),*] $help:literal,)*) => {
+        ///
+        /// Something a key can be bound to.
+        ///
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum Command {
+            $($variant,)*
+        }
+
+        impl Command {
+            pub fn all() -> &'static [Command] {
+                &[$(Command::$variant,)*]
+            }
+
+            /// The name used in the config file.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Command::$variant => $name,)*
+                }
+            }
+
+            pub fn context(&self) -> Context {
+                match self {
+                    $(Command::$variant => Context::$context,)*
+                }
+            }
+
+            pub fn default_keys(&self) -> &'static [&'static str] {
+                match self {
+                    $(Command::$variant => &[$($key),*],)*
+                }
+            }
+
+            /// What the command does, for `comode config`.
+            pub fn help(&self) -> &'static str {
+                match self {
+                    $(Command::$variant => $help,)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Command> {
+                match name {
+                    $($name => Some(Command::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Quit => "quit" Global ["q"] "quit comode",
+    NextTab => "next_tab" Global ["tab"] "next tab",
+    PrevTab => "prev_tab" Global ["backtab"] "previous tab",
+    Up => "up" Global ["up"] "move up",
+    Down => "down" Global ["down"] "move down",
+    Select => "select" Global ["enter"] "open the thing under the cursor",
+    Back => "back" Global ["esc"] "go back",
+    Refresh => "refresh" Global ["r"] "refresh thoughts and pings",
+    Compose => "compose" Global ["n"] "write a thought with the default visibility",
+    ComposeAlt => "compose_alt" Global ["N"] "write a thought with the other visibility",
+    Editor => "editor" Global ["E"] "write a thought in $EDITOR and send it",
+    Search => "search" Global ["/"] "search your thoughts",
+    Whoami => "whoami" Global ["w"] "show session info",
+    Logout => "logout" Global ["L"] "log out",
+    Conflicts => "conflicts" Global ["c"] "resolve sync conflicts",
+    Drafts => "drafts" Global ["D"] "show saved drafts",
+    Profile => "profile" Global ["u"] "show the profile of whoever wrote the thought or ping under the cursor",
+    Follow => "follow" Global ["f"] "follow or unfollow whoever wrote the thought under the cursor",
+    MarkRead => "mark_read" Global ["R"] "mark all pings read",
+    Help => "help" Global ["?"] "show the keys for this view",
+    Palette => "palette" Global [":", "ctrl-p"] "find a command by name",
+    Top => "top" List ["home"] "go to the first row",
+    Bottom => "bottom" List ["end"] "go to the last row",
+    Edit => "edit" List ["e"] "edit the thought or draft under the cursor",
+    Discard => "discard" List ["d"] "discard the unsent thought or draft under the cursor",
+    Preview => "preview" List ["p"] "show or hide the preview next to the list",
+    WiderList => "wider_list" List [">"] "make the list wider than the preview",
+    NarrowerList => "narrower_list" List ["<"] "make the list narrower than the preview",
+    FilterVisibility => "filter_visibility" List ["v"] "show public, private or all thoughts",
+    FilterSynthetic => "filter_synthetic" List ["s"] "show human, synthetic or all thoughts",
+    FilterOrigin => "filter_origin" List ["o"] "show thoughts from one origin, or all",
+    FilterPeriod => "filter_period" List ["t"] "show thoughts from the past day, week, month, year, or all",
+    FilterLinks => "filter_links" List ["l"] "show only thoughts with links, or all",
+    Sort => "sort" List ["S"] "sort by server order, newest, recently updated, most linked or title",
+    ClearFilters => "clear_filters" List ["X"] "clear filters and sorting",
+    KeepMine => "keep_mine" Detail ["m"] "resolve a conflict with your version",
+    KeepTheirs => "keep_theirs" Detail ["t"] "resolve a conflict with the server's version",
+    EditMerge => "edit_merge" Detail ["e"] "edit the merged text of a conflict",
+    Submit => "submit" Editor ["ctrl-s"] "send the thought",
+    Cancel => "cancel" Editor ["esc"] "stop typing",
+    ExternalEditor => "external_editor" Editor ["ctrl-e"] "continue the thought in $EDITOR",
+    EditorHelp => "editor_help" Editor ["f1"] "show the keys for text entry",
+}
+
+impl Command {
+    ///
+    /// The action for this command. Composing depends on the default
+    /// visibility.
+    ///
+    pub fn action(&self, default_public: bool) -> Action {
+        match self {
+            Command::Quit => Action::Quit,
+            Command::NextTab => Action::NextTab,
+            Command::PrevTab => Action::PrevTab,
+            Command::Up => Action::Up,
+            Command::Down => Action::Down,
+            Command::Select => Action::Select,
+            Command::Back => Action::Back,
+            Command::Refresh => Action::Refresh,
+            Command::Compose => Action::Compose {
+                public: default_public,
+            },
+            Command::ComposeAlt => Action::Compose {
+                public: !default_public,
+            },
+            Command::Editor => Action::OpenEditor { review: false },
+            Command::Search => Action::Search,
+            Command::Whoami => Action::ToggleWhoami,
+            Command::Logout => Action::Logout,
+            Command::Conflicts => Action::ShowConflicts,
+            Command::Drafts => Action::ShowDrafts,
+            Command::Profile => Action::ShowProfile,
+            Command::Follow => Action::ToggleFollow,
+            Command::MarkRead => Action::MarkPingsRead,
+            Command::Help | Command::EditorHelp => Action::ToggleHelp,
+            Command::Palette => Action::OpenPalette,
+            Command::Top => Action::Top,
+            Command::Bottom => Action::Bottom,
+            Command::Edit => Action::Edit,
+            Command::Discard => Action::Discard,
+            Command::Preview => Action::TogglePreview,
+            Command::WiderList => Action::WiderList,
+            Command::NarrowerList => Action::NarrowerList,
+            Command::FilterVisibility => Action::Filter(Toggle::Visibility),
+            Command::FilterSynthetic => Action::Filter(Toggle::Synthetic),
+            Command::FilterOrigin => Action::Filter(Toggle::Origin),
+            Command::FilterPeriod => Action::Filter(Toggle::Period),
+            Command::FilterLinks => Action::Filter(Toggle::Links),
+            Command::Sort => Action::Filter(Toggle::Sort),
+            Command::ClearFilters => Action::Filter(Toggle::Clear),
+            Command::KeepMine => Action::Resolve(Resolution::KeepMine),
+            Command::KeepTheirs => Action::Resolve(Resolution::KeepTheirs),
+            Command::EditMerge => Action::EditMerge,
+            Command::Submit => Action::Submit,
+            Command::Cancel => Action::Back,
+            Command::ExternalEditor => Action::OpenEditor { review: true },
+        }
+    }
+
+    ///
+    /// The keys for this command in `preset`, before any overrides.
+    ///
+    pub fn preset_keys(&self, preset: Preset) -> &'static [&'static str] {
+        let keys: &'static [&'static str] = match (preset, self) {
+            (Preset::Vim, Command::Up) => &["up", "k"],
+            (Preset::Vim, Command::Down) => &["down", "j"],
+            (Preset::Vim, Command::NextTab) => &["tab", "g t"],
+            (Preset::Vim, Command::PrevTab) => &["backtab", "g T"],
+            (Preset::Vim, Command::Top) => &["home", "g g"],
+            (Preset::Vim, Command::Bottom) => &["end", "G"],
+            (Preset::Vim, Command::Discard) => &["d d"],
+            (Preset::Emacs, Command::Up) => &["up", "ctrl-p"],
+            (Preset::Emacs, Command::Down) => &["down", "ctrl-n"],
+            (Preset::Emacs, Command::Top) => &["home", "alt-<"],
+            (Preset::Emacs, Command::Bottom) => &["end", "alt->"],
+            (Preset::Emacs, Command::Search) => &["/", "ctrl-s"],
+            (Preset::Emacs, Command::Palette) => &[":", "alt-x"],
+            (Preset::Emacs, Command::Back) => &["esc", "ctrl-g"],
+            (Preset::Emacs, Command::Quit) => &["q", "ctrl-x ctrl-c"],
+            (Preset::Emacs, Command::Submit) => &["ctrl-x ctrl-s"],
+            (Preset::Emacs, Command::Cancel) => &["esc", "ctrl-g"],
+            (Preset::Emacs, Command::ExternalEditor) => &["ctrl-x ctrl-e"],
+            _ => self.default_keys(),
+        };
+        keys
+    }
+}
+
+///
+/// A key with its modifiers, as bound in the config.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    ///
+    /// The key for a key press. Shift is part of the character for letters
+    /// and symbols (`N`, `?`), so it's dropped there, and for BackTab.
+    ///
+    pub fn from_event(event: KeyEvent) -> Key {
+        let mut modifiers =
+            event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) | KeyCode::BackTab = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        Key {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    ///
+    /// Parse a key as written in the config, e.g. `q`, `ctrl-s`, `shift-tab`.
+    ///
+    pub fn parse(text: &str) -> Result<Key, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+
+        // Modifiers come first; a lone "-" is the minus key
+        while let Some((modifier, tail)) = rest.split_once('-') {
+            if tail.is_empty() {
+                break;
+            }
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "a" | "meta" | "m" => modifiers |= KeyModifiers::ALT,
+                "shift" | "s" => modifiers |= KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, text)),
+            }
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            _ if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap_or(' ')),
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => return Err(format!("unknown key `{}`", text)),
+            },
+        };
+
+        // Shift is part of the character for letters and symbols
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => f.write_str("backtab"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+///
+/// One or more keys pressed in sequence.
+///
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Chord(pub Vec<Key>);
+
+impl Chord {
+    ///
+    /// Parse a chord as written in the config, e.g. `g g` or `ctrl-x ctrl-s`.
+    ///
+    pub fn parse(text: &str) -> Result<Chord, String> {
+        let keys = text
+            .split_whitespace()
+            .map(Key::parse)
+            .collect::<Result<Vec<Key>, String>>()?;
+        if keys.is_empty() {
+            return Err("empty key".to_string());
+        }
+        Ok(Chord(keys))
+    }
+
+    fn starts_with(&self, prefix: &[Key]) -> bool {
+        self.0.starts_with(prefix)
+    }
+
+    // Keys that would take over typing if bound in text entry
+    fn types_text(&self) -> bool {
+        let first = self.0[0];
+        let modified = first
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        !modified && !matches!(first.code, KeyCode::Esc | KeyCode::F(_))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// What a sequence of key presses means.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lookup {
+    Command(Command),
+    /// The start of a chord; wait for the next key
+    Pending,
+    None,
+}
+
+///
+/// Which command each chord runs, in each context.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Keymap {
+    bindings: HashMap<(Context, Chord), Command>,
+}
+
+impl Keymap {
+    ///
+    /// Build a keymap from a preset, with `overrides` replacing the keys for
+    /// the commands they name. Returns every problem found, not just the
+    /// first.
+    ///
+    pub fn new(
+        preset: Preset,
+        overrides: &HashMap<String, Vec<String>>,
+    ) -> Result<Keymap, Vec<String>> {
+        let mut errors = Vec::new();
+
+        for name in overrides.keys() {
+            if Command::from_name(name).is_none() {
+                let names = Command::all()
+                    .iter()
+                    .map(|c| c.name())
+                    .collect::<Vec<&str>>();
+                errors.push(format!(
+                    "[keys] unknown command `{}`, expected one of: {}",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        }
+
+        let mut keymap = Keymap::default();
+        for command in Command::all() {
+            let keys = match overrides.get(command.name()) {
+                Some(keys) => keys.iter().map(|key| key.as_str()).collect::<Vec<&str>>(),
+                None => command.preset_keys(preset).to_vec(),
+            };
+
+            for text in keys {
+                let chord = match Chord::parse(text) {
+                    Ok(chord) => chord,
+                    Err(e) => {
+                        errors.push(format!("[keys] {}: {}", command.name(), e));
+                        continue;
+                    }
+                };
+                if command.context() == Context::Editor && chord.types_text() {
+                    errors.push(format!(
+                        "[keys] {}: `{}` would stop you typing it, text entry keys need ctrl or alt",
+                        command.name(),
+                        text
+                    ));
+                    continue;
+                }
+                if let Some(other) = keymap.bindings.insert((command.context(), chord), *command) {
+                    errors.push(format!(
+                        "[keys] `{}` is bound to both {} and {}",
+                        text,
+                        other.name(),
+                        command.name()
+                    ));
+                }
+            }
+        }
+
+        // A chord can't be typed if a shorter one in reach already matched
+        for ((context, chord), command) in &keymap.bindings {
+            for ((other_context, other), other_command) in &keymap.bindings {
+                let reachable = context.chain().contains(other_context)
+                    || other_context.chain().contains(context);
+                if reachable && other.0.len() < chord.0.len() && chord.starts_with(&other.0) {
+                    errors.push(format!(
+                        "[keys] `{}` ({}) starts with `{}` ({}), so it can never be typed",
+                        chord,
+                        command.name(),
+                        other,
+                        other_command.name()
+                    ));
+                }
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(keymap),
+            false => Err(errors),
+        }
+    }
+
+    ///
+    /// The command for the keys pressed so far in `context`, or whether
+    /// they're the start of one.
+    ///
+    pub fn lookup(&self, context: Context, keys: &[Key]) -> Lookup {
+        let chord = Chord(keys.to_vec());
+        for context in context.chain() {
+            if let Some(command) = self.bindings.get(&(*context, chord.clone())) {
+                return Lookup::Command(*command);
+            }
+        }
+
+        let pending = self.bindings.keys().any(|(bound, bound_chord)| {
+            context.chain().contains(bound) && bound_chord.starts_with(keys)
+        });
+        match pending {
+            true => Lookup::Pending,
+            false => Lookup::None,
+        }
+    }
+
+    /// The command bound to a single key press in `context`.
+    pub fn get(&self, context: Context, event: KeyEvent) -> Option<Command> {
+        match self.lookup(context, &[Key::from_event(event)]) {
+            Lookup::Command(command) => Some(command),
+            _ => None,
+        }
+    }
+
+    /// The chords bound to a command, for help text.
+    pub fn keys_for(&self, command: Command) -> Vec<Chord> {
+        let mut chords = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == command)
+            .map(|((_, chord), _)| chord.clone())
+            .collect::<Vec<Chord>>();
+        chords.sort_by_key(|chord| chord.to_string());
+        chords
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<Key> {
+        Chord::parse(text).unwrap().0
+    }
+
+    fn overrides(bindings: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        bindings
+            .iter()
+            .map(|(name, keys)| {
+                let keys = keys.iter().map(|key| key.to_string()).collect();
+                (name.to_string(), keys)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_parse_and_print_the_same() {
+        let ctrl_s = Key::parse("C-s").unwrap();
+        assert_eq!(ctrl_s.code, KeyCode::Char('s'));
+        assert_eq!(ctrl_s.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(Key::parse("shift-tab").unwrap().code, KeyCode::BackTab);
+        assert_eq!(Key::parse("shift-N").unwrap().modifiers, KeyModifiers::NONE);
+        assert_eq!(Key::parse("-").unwrap().code, KeyCode::Char('-'));
+        assert!(Key::parse("hyper-x").is_err());
+        assert!(Key::parse("f13").is_err());
+
+        for text in ["ctrl-x ctrl-s", "g g", "alt-enter", "space", "f1", "pageup"] {
+            assert_eq!(Chord::parse(text).unwrap().to_string(), text);
+        }
+        assert!(Chord::parse("  ").is_err());
+    }
+
+    #[test]
+    fn every_preset_builds() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Emacs] {
+            assert!(Keymap::new(preset, &HashMap::new()).is_ok(), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn chords_wait_for_their_last_key() {
+        let keymap = Keymap::new(Preset::Vim, &HashMap::new()).unwrap();
+        assert_eq!(keymap.lookup(Context::List, &keys("g")), Lookup::Pending);
+        assert_eq!(
+            keymap.lookup(Context::List, &keys("g g")),
+            Lookup::Command(Command::Top)
+        );
+        assert_eq!(keymap.lookup(Context::List, &keys("g x")), Lookup::None);
+
+        // Global commands apply in lists, but not in text entry
+        assert_eq!(
+            keymap.lookup(Context::List, &keys("g t")),
+            Lookup::Command(Command::NextTab)
+        );
+        assert_eq!(keymap.lookup(Context::Editor, &keys("g")), Lookup::None);
+    }
+
+    #[test]
+    fn presets_give_way_to_overrides() {
+        let keymap = Keymap::new(Preset::Emacs, &overrides(&[("quit", &["ctrl-q"])])).unwrap();
+        assert_eq!(
+            keymap.lookup(Context::Editor, &keys("ctrl-x ctrl-s")),
+            Lookup::Command(Command::Submit)
+        );
+        assert_eq!(
+            keymap.keys_for(Command::Quit),
+            [Chord::parse("ctrl-q").unwrap()]
+        );
+        assert_eq!(keymap.lookup(Context::Global, &keys("q")), Lookup::None);
+    }
+
+    #[test]
+    fn bad_bindings_are_all_reported() {
+        let errors = Keymap::new(
+            Preset::Default,
+            &overrides(&[
+                ("nope", &["x"]),
+                ("submit", &["s"]),
+                ("refresh", &["q"]),
+                ("top", &["z", "z z"]),
+            ]),
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 4, "{:#?}", errors);
+        assert!(errors[0].contains("unknown command `nope`"));
+        assert!(errors.iter().any(|e| e.contains("would stop you typing")));
+        assert!(errors.iter().any(|e| e.contains("bound to both")));
+        assert!(errors.iter().any(|e| e.contains("can never be typed")));
+    }
+}
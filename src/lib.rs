@@ -0,0 +1,39 @@
+//! Talk to Comind from Rust.
+//!
+//! - `types`: thoughts, pings and users as the server sends them
+//! - `archive`: a whole account in one file, for backups
+//! - `client`: async and blocking API clients
+//! - `auth`: token storage in the system keyring
+//! - `store`: local cache of thoughts and pings for offline use
+//! - `drafts`: autosaved compose buffers
+//! - `editor`: writing thoughts in `$EDITOR`, with a front-matter header
+//! - `follow`: following users, and a feed of their thoughts
+//! - `filter`: filtering and sorting lists of thoughts
+//! - `profile`: other users and how their thoughts link to yours
+//! - `outbox`: thoughts waiting to be sent, with retry and backoff
+//! - `sync`: two-way sync of edits, with conflict detection and merging
+//! - `vault`: thoughts as a folder of Markdown notes
+//! - `errors`: error types for all of the above
+//!
+//! The `comode` terminal app is built on this crate. Its dependencies sit
+//! behind the default `tui` feature, so use `default-features = false` to get
+//! just the library.
+
+pub mod archive;
+pub mod auth;
+pub mod client;
+pub mod drafts;
+pub mod editor;
+pub mod errors;
+pub mod filter;
+pub mod follow;
+pub mod outbox;
+pub mod profile;
+pub mod store;
+pub mod sync;
+pub mod types;
+pub mod utils;
+pub mod vault;
+
+/// The Comind server comode talks to unless told otherwise.
+pub const DEFAULT_SERVER: &str = "https://nimbus.pfiffer.org";
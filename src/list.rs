@@ -0,0 +1,211 @@
+// `comode list`: your thoughts, filtered and sorted, without the TUI
+//
+//   comode list                       everything, as the server sends it
+//   comode list --private --links     private thoughts that link somewhere
+//   comode list --within week --sort links
+//
+// The filters are the same ones the thoughts tab has. If the server can't be
+// reached, the cached thoughts are listed instead.
+use colored::Colorize;
+
+use comode::auth;
+use comode::client::blocking::Client;
+use comode::errors::ApiResult;
+use comode::filter::{links, Period, Sort, ThoughtFilter};
+use comode::store::Store;
+use comode::types::{Origin, Thought, User};
+use comode::utils::datetime_to_relative;
+
+use crate::colors::ComindColors;
+use crate::config::Account;
+use crate::display::co_say;
+
+const USAGE: &str = "usage: comode list [--public | --private] [--human | --synthetic]
+                   [--origin app|web|cli|api|import] [--within day|week|month|year]
+                   [--since YYYY-MM-DD] [--until YYYY-MM-DD] [--links]
+                   [--sort server|created|updated|links|title]";
+
+pub fn list(account: &Account, page_size: u32, args: &[String], colors: &ComindColors) {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let filter = match parse_filter(args) {
+        Ok(filter) => filter,
+        Err(e) => {
+            co_say(&e, colors);
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    let user = match auth::load_user(&account.profile) {
+        Ok(user) => user,
+        Err(_) => {
+            co_say("you're not logged in, run `comode login` first", colors);
+            return;
+        }
+    };
+
+    // Fresh from the server, or whatever we saw last
+    let client = Client::for_user(&account.server, &user).with_page_size(page_size);
+    let fetched = client.get_user_thoughts(&user.username, None, None);
+    let thoughts = match or_cached(fetched, account, &user, colors) {
+        Some(thoughts) => thoughts,
+        None => return,
+    };
+
+    let shown = filter.apply(&thoughts);
+    for thought in &shown {
+        println!("{}", thought_line(thought, colors));
+    }
+
+    // What was left out, and why
+    let mut summary = vec![match shown.len() {
+        1 => "1 thought".to_string(),
+        n => format!("{} thoughts", n),
+    }];
+    summary.extend(filter.chips());
+    co_say(&summary.join(" · "), colors);
+}
+
+///
+/// Thoughts from the server, or the cached ones if it couldn't be reached,
+/// saying which. `None` (after saying why) if there are neither, or if the
+/// server answered with an error that retrying won't fix.
+///
+pub fn or_cached(
+    fetched: ApiResult<Vec<Thought>>,
+    account: &Account,
+    user: &User,
+    colors: &ComindColors,
+) -> Option<Vec<Thought>> {
+    let e = match fetched {
+        Ok(thoughts) => return Some(thoughts),
+        Err(e) if e.is_transient() => e,
+        Err(e) => {
+            co_say(&format!("couldn't load your thoughts: {}", e), colors);
+            return None;
+        }
+    };
+
+    let cached = Store::open(&account.server, &user.username)
+        .ok()
+        .and_then(|store| store.load_thoughts());
+    match cached {
+        Some(cached) => {
+            co_say(
+                &format!(
+                    "couldn't reach the server ({}), these are from {}",
+                    e,
+                    datetime_to_relative(&cached.fetched_at)
+                ),
+                colors,
+            );
+            Some(cached.items)
+        }
+        None => {
+            co_say(&format!("couldn't load your thoughts: {}", e), colors);
+            None
+        }
+    }
+}
+
+// Date, title (or the start of the body), and links
+fn thought_line(thought: &Thought, colors: &ComindColors) -> String {
+    let text = match &thought.title {
+        Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+        _ => thought.body.lines().next().unwrap_or("").to_string(),
+    };
+    let lock = if thought.public { "" } else { "🔒 " };
+    let links = match links(thought) {
+        0 => String::new(),
+        1 => "  1 link".to_string(),
+        n => format!("  {} links", n),
+    };
+
+    format!(
+        "{}  {}{}{}",
+        thought
+            .date_created
+            .format("%Y-%m-%d")
+            .to_string()
+            .custom_color(colors.tertiary()),
+        lock,
+        text,
+        links.custom_color(colors.secondary())
+    )
+}
+
+///
+/// The filter given by `comode list` flags. Flags with a value take it as
+/// the next argument or after `=`.
+///
+fn parse_filter(args: &[String]) -> Result<ThoughtFilter, String> {
+    let mut filter = ThoughtFilter::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || match inline.clone().or_else(|| args.next().cloned()) {
+            Some(value) => Ok(value),
+            None => Err(format!("{} needs a value", flag)),
+        };
+
+        match flag {
+            "--public" => filter.public = Some(true),
+            "--private" => filter.public = Some(false),
+            "--human" => filter.synthetic = Some(false),
+            "--synthetic" => filter.synthetic = Some(true),
+            "--links" => filter.has_links = true,
+            "--origin" => filter.origin = Some(Origin::from(value()?.as_str())),
+            "--within" => {
+                let value = value()?;
+                filter.within = match Period::from_name(&value) {
+                    Some(period) => Some(period),
+                    None => {
+                        return Err(format!(
+                            "--within takes day, week, month or year, not `{}`",
+                            value
+                        ))
+                    }
+                };
+            }
+            "--since" => filter.since = Some(parse_date(flag, &value()?)?),
+            "--until" => filter.until = Some(parse_date(flag, &value()?)?),
+            "--sort" => {
+                let value = value()?;
+                filter.sort = match Sort::from_name(&value) {
+                    Some(sort) => sort,
+                    None => {
+                        let names = Sort::all()
+                            .iter()
+                            .map(|sort| sort.name())
+                            .collect::<Vec<&str>>();
+                        return Err(format!(
+                            "--sort takes one of {}, not `{}`",
+                            names.join(", "),
+                            value
+                        ));
+                    }
+                };
+            }
+            _ => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+
+    Ok(filter)
+}
+
+fn parse_date(flag: &str, value: &str) -> Result<chrono::NaiveDate, String> {
+    match chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Ok(date),
+        Err(_) => Err(format!(
+            "{} takes a date like 2024-03-19, not `{}`",
+            flag, value
+        )),
+    }
+}
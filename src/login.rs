@@ -1,82 +1,271 @@
-use keyring::Entry;
-use std::{
-    collections::HashMap,
-    io::{self, Write},
-};
+use std::io::{self, Write};
 
-// For talking to the server https
-use reqwest;
+use comode::auth::{self, clear_token, store_token};
+use comode::client::blocking::Client;
+use comode::errors::AuthenticationError;
+use comode::types::User;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal,
+};
 
-use crate::types::{self, User};
+use crate::config::Account;
 use crate::{colors::ComindColors, display::co_say};
 
-pub fn login() -> Option<User> {
-    let entry = Entry::new("comind", "token").unwrap();
-    let mut username = String::new();
-    let mut password = String::new();
-
+pub fn login(account: &Account) -> Option<User> {
     // Check if the user is already logged in.
     // If they are, return the user.
     // If this fails, try to clear the token from the keyring
     // and continue with the login process.
-    let token = entry.get_password();
-    if let Ok(token) = token {
-        let user = crate::types::User::create_from_entry(&entry);
-        return Some(user.unwrap());
-    } else {
-        if let Err(e) = token {
+    match auth::load_user(&account.profile) {
+        Ok(user) => return Some(user),
+        Err(AuthenticationError::TokenNotFound) => {}
+        Err(e) => {
             println!("Error: {}", e);
-            entry.delete_password().unwrap();
+            if let Err(e) = clear_token(&account.profile) {
+                println!("Error: {}", e);
+            }
         }
     }
 
-    // TODO #1 make username and password input secure
-    print!("Enter your username: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut username).unwrap();
-
-    print!("Enter your password: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut password).unwrap();
+    let username = prompt_line("Enter your username: ")?;
+    let password = prompt_password("Enter your password: ")?;
 
-    // Send the username and password to the server at
-    // https://nimbus.pfiffer.org/api/login
-    // and get a JWT back. Store the JWT in the keyring.
-    // The server requires a username or email with a password.
-    // TODO #2 make server URL configurable
-    let mut request_body = HashMap::new();
-    request_body.insert("username", username.trim());
-    request_body.insert("password", password.trim());
-
-    let client = reqwest::blocking::Client::new();
-    let res = client
-        .post("https://nimbus.pfiffer.org/api/login/")
-        .json(&request_body)
-        .send();
-
-    // Unpack the result
-    let res = match res {
-        Ok(res) => res,
+    // Log in with what we were given. Passwords are used as typed, spaces
+    // and all.
+    match login_with_credentials(account, username.trim(), &password) {
+        Ok(user) => Some(user),
         Err(e) => {
             println!("Error: {}", e);
-            return None;
+            None
         }
+    }
+}
+
+///
+/// Log in with a username (or email) and password.
+///
+/// Sends the credentials to the server, stores the returned JWT in the
+/// keyring and decodes it into a `User`.
+///
+pub fn login_with_credentials(
+    account: &Account,
+    username: &str,
+    password: &str,
+) -> Result<User, String> {
+    // Send the username and password to the server and get a JWT back.
+    // The server requires a username or email with a password.
+    let response = match Client::new(&account.server).login(username, password) {
+        Ok(response) => response,
+        Err(e) if e.status() == Some(401) => return Err("wrong username or password".to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // Store the JWT in the keyring, and create a user from it
+    match store_token(&account.profile, &response) {
+        Ok(user) => Ok(user),
+        Err(e) => Err(format!("couldn't store your token: {}", e)),
+    }
+}
+
+///
+/// Create a new account and log in to it.
+///
+/// The fields are validated locally first, so most mistakes never reach the
+/// server.
+///
+pub fn register(
+    account: &Account,
+    username: &str,
+    email: &str,
+    password: &str,
+    confirm: &str,
+) -> Result<User, String> {
+    // Client-side checks
+    validate_registration(username, email, password, confirm)?;
+
+    // Create the account
+    if let Err(e) = Client::new(&account.server).register_user(username, email, password) {
+        return match e.status() {
+            Some(409) => Err("that username or email is already taken".to_string()),
+            _ => Err(e.to_string()),
+        };
+    }
+
+    // And log in with it
+    login_with_credentials(account, username, password)
+}
+
+///
+/// Validate sign-up fields before sending them to the server.
+///
+/// Returns a message describing the first problem found.
+///
+pub fn validate_registration(
+    username: &str,
+    email: &str,
+    password: &str,
+    confirm: &str,
+) -> Result<(), String> {
+    // Usernames show up in URLs, so keep them simple
+    if username.len() < 3 || username.len() > 32 {
+        return Err("username must be 3 to 32 characters".to_string());
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("username may only contain letters, numbers, - and _".to_string());
+    }
+
+    // Just enough to catch typos, the server does the real check
+    let (local, domain) = match email.split_once('@') {
+        Some(parts) => parts,
+        None => return Err("email must contain an @".to_string()),
     };
+    if local.is_empty() || !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.')
+    {
+        return Err("email doesn't look right".to_string());
+    }
+    if email.chars().any(|c| c.is_whitespace()) {
+        return Err("email can't contain spaces".to_string());
+    }
 
-    // If the server returns a JWT, store it in the keyring
-    let token = res.text().unwrap();
-    entry.set_password(&token).unwrap();
+    // Password
+    if password.chars().count() < 8 {
+        return Err("password must be at least 8 characters".to_string());
+    }
+    if password != confirm {
+        return Err("passwords don't match".to_string());
+    }
 
-    // Create a user from the entry
-    let user = crate::types::User::create_from_entry(&entry);
-    return Some(user.unwrap());
+    Ok(())
+}
+
+///
+/// Sign up from the command line, for `comode register`.
+///
+pub fn register_prompt(account: &Account, colors: &ComindColors) -> Option<User> {
+    co_say("let's make you an account", colors);
+
+    let username = prompt_line("username: ")?;
+    let email = prompt_line("email: ")?;
+    let password = prompt_password("password: ")?;
+    let confirm = prompt_password("confirm password: ")?;
+
+    match register(account, username.trim(), email.trim(), &password, &confirm) {
+        Ok(user) => {
+            co_say(&format!("welcome to comind, {}", user.username), colors);
+            Some(user)
+        }
+        Err(e) => {
+            co_say(&format!("couldn't sign you up: {}", e), colors);
+            None
+        }
+    }
+}
+
+// Read a line from stdin after printing a prompt
+fn prompt_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).ok()?;
+    Some(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// Read a password without echoing it
+fn prompt_password(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+
+    terminal::enable_raw_mode().ok()?;
+    let mut password = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Some(password),
+                KeyCode::Backspace => {
+                    password.pop();
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Esc => break None,
+                KeyCode::Char(c) => password.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(_) => break None,
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    println!();
+
+    result
 }
 
 // Logout function
-pub fn logout(colors: &ComindColors) {
-    let entry = Entry::new("comind", "token").unwrap();
-    entry.delete_password().unwrap();
+//
+// Revokes the token on the server when the server supports it, then removes
+// it from the keyring. Local credentials are cleared even if revocation fails.
+pub fn logout(account: &Account, user: Option<&User>, colors: &ComindColors) {
+    // Revoke the token server-side
+    if let Some(user) = user {
+        match Client::for_user(&account.server, user).revoke_token() {
+            Ok(true) => co_say("i revoked your session on the server", colors),
+            Ok(false) => {}
+            Err(e) => co_say(&format!("couldn't revoke your session: {}", e), colors),
+        }
+    }
+
+    // Clear the keyring entry
+    if let Err(e) = clear_token(&account.profile) {
+        co_say(&format!("couldn't remove your token: {}", e), colors);
+        return;
+    }
 
     // Print a message
     co_say("i've logged you out, go out and crush stuff", colors);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(username: &str, email: &str, password: &str) -> Result<(), String> {
+        validate_registration(username, email, password, password)
+    }
+
+    #[test]
+    fn good_sign_ups_pass() {
+        assert_eq!(
+            check("cameron", "cameron@example.org", "long enough"),
+            Ok(())
+        );
+        assert_eq!(check("a-b_c", "a@b.co", "12345678"), Ok(()));
+    }
+
+    #[test]
+    fn usernames_stay_url_safe() {
+        assert!(check("ab", "a@b.co", "12345678").is_err());
+        assert!(check(&"a".repeat(33), "a@b.co", "12345678").is_err());
+        assert!(check("cam/eron", "a@b.co", "12345678").is_err());
+        assert!(check("cam eron", "a@b.co", "12345678").is_err());
+    }
+
+    #[test]
+    fn emails_need_a_local_part_and_a_domain() {
+        for email in ["nope", "@b.co", "a@b", "a@.co", "a@b.", "a b@c.co"] {
+            assert!(check("cameron", email, "12345678").is_err(), "{}", email);
+        }
+    }
+
+    #[test]
+    fn passwords_are_long_and_match() {
+        assert!(check("cameron", "a@b.co", "short").is_err());
+        assert_eq!(
+            validate_registration("cameron", "a@b.co", "12345678", "12345679"),
+            Err("passwords don't match".to_string())
+        );
+    }
+}
@@ -1,29 +1,50 @@
-use colored::CustomColor;
-use jwt::{Claims, Header, Token};
-use keyring::Entry;
-use serde_json::Value;
 use std::{
-    cmp::{max, min},
-    io::{self, stdin, stdout, Write},
+    collections::VecDeque,
+    io,
+    time::{Duration, Instant},
 };
-use types::Thought;
 
 // terminal shit
-use crossterm::{
-    event::{self, Event, KeyCode},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use crossterm::event::{self, Event, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
-use termimad; // for markdown printing
 
-// Error types
-mod errors;
-use errors::{AuthResult, AuthenticationError};
+// The library: types, API client, auth
+use comode::client::blocking::Client;
+use comode::store::{AccountInfo, Store};
+use comode::{auth, editor, types};
+
+// Settings and key bindings
+mod config;
+mod keys;
+use config::{Account, Config};
+use keys::{Command, Context};
 
 // Login
 mod login;
-use login::login;
+use login::{login, logout, register_prompt};
+
+// Terminal setup, teardown and suspend
+mod terminal;
+use terminal::{install_panic_hook, TerminalGuard};
+
+// `comode think`
+mod think;
+
+// `comode list`
+mod list;
+
+// `comode export`
+mod export;
+
+// `comode import`
+mod import;
+
+// `comode backup` and `comode restore`
+mod backup;
+
+// Log in / sign up screens
+mod signup;
+use signup::{auth_screen, SignedIn};
 
 // display types
 mod display;
@@ -35,69 +56,124 @@ use colors::ComindColors;
 
 // repl shit
 mod ui;
-use ui::{mode_strings, next_mode, prev_mode, UIMode};
 
-// Types
-mod types;
+// App state, actions and views
+mod app;
+mod components;
+use app::{Action, App, Task};
 
-// api
-mod api;
-use api::get_user_thoughts;
+// Background network worker
+mod worker;
+use worker::Worker;
 
-use crate::utils::iso8601_to_datetime;
+fn main() -> io::Result<()> {
+    // `--profile NAME` can go anywhere
+    let (profile, args) = take_profile(std::env::args().skip(1).collect());
 
-// Utils
-mod utils;
+    // `comode config` shows problems in the file rather than failing on them
+    if args.first().map(|arg| arg.as_str()) == Some("config") {
+        show_config(profile);
+        return Ok(());
+    }
 
-fn main() -> io::Result<()> {
-    // Set up the terminal
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-
-    // Default colors
-    let colors = ComindColors::default();
-
-    // // Print blank line
-    // println!();
-
-    // Print the welcome.
-    // co_say(
-    //     &format!(
-    //         "{}",
-    //         "hey, welcome to comind",
-    //         // "{".bold().custom_color(colors.primary()),
-    //         // "co".bold().custom_color(colors.secondary()),
-    //         // "mind".bold().custom_color(colors.tertiary()),
-    //         // "}".bold().custom_color(colors.primary())
-    //     ),
-    //     &colors,
-    // );
-
-    //     "co: {} {}{}{}{}",
-    //     "hey, welcome to",
-    //     "{".bold().custom_color(colors.primary()),
-    //     "co".bold().custom_color(colors.secondary()),
-    //     "mind".bold().custom_color(colors.tertiary()),
-    //     "}".bold().custom_color(colors.primary())
-    // );
-
-    // Load the token from the keyring
-    let token_entry = match Entry::new("comind", "token") {
-        Ok(entry) => entry,
+    // Settings, from the config file or the defaults
+    let config = match Config::load(profile) {
+        Ok(config) => config,
         Err(e) => {
-            println!("Keyring load error: {:?}", e);
-            return Err(io::Error::new(io::ErrorKind::Other, "Keyring load error"));
+            eprint!("{}", e);
+            eprintln!("run `comode config` to see the settings in effect");
+            std::process::exit(1);
         }
     };
+    let account = config.account();
+    // The config was checked, so the theme is fine
+    let colors = config.theme().unwrap_or_default();
+    if colors::no_color() {
+        colored::control::set_override(false);
+    }
 
-    // Log in
-    let user = match login() {
-        Some(user) => user,
-        None => {
-            println!("Login failed");
+    // Subcommands that run without the TUI
+    match args.first().map(|arg| arg.as_str()) {
+        Some("logout") => {
+            let user = current_user(&account);
+            logout(&account, user.as_ref(), &colors);
+            return Ok(());
+        }
+        Some("login") => {
+            if let Some(user) = login(&account) {
+                co_say(&format!("you're logged in as {}", user.username), &colors);
+            }
+            return Ok(());
+        }
+        Some("register") | Some("signup") => {
+            register_prompt(&account, &colors);
+            return Ok(());
+        }
+        Some("think") => {
+            think::think(&account, config.default_public(), &args[1..], &colors);
+            return Ok(());
+        }
+        Some("list") => {
+            list::list(&account, config.thoughts.page_size, &args[1..], &colors);
+            return Ok(());
+        }
+        Some("export") => {
+            export::export(&account, config.thoughts.page_size, &args[1..], &colors);
+            return Ok(());
+        }
+        Some("import") => {
+            import::import(&account, config.default_public(), &args[1..], &colors);
+            return Ok(());
+        }
+        Some("backup") => {
+            backup::backup(&account, config.thoughts.page_size, &args[1..], &colors);
+            return Ok(());
+        }
+        Some("restore") => {
+            backup::restore(&account, config.thoughts.page_size, &args[1..], &colors);
+            return Ok(());
+        }
+        Some("whoami") => {
+            whoami(&account, &colors);
             return Ok(());
         }
+        Some("help") | Some("--help") | Some("-h") => {
+            print_usage();
+            return Ok(());
+        }
+        Some(command) => {
+            println!("unknown command: {}", command);
+            print_usage();
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Set up the terminal. The guard restores it on every way out of main,
+    // and the panic hook restores it before a panic message is printed.
+    install_panic_hook();
+    let mut terminal_guard = TerminalGuard::new(config.mouse)?;
+
+    // Log in, or show the log in / sign up screen if there's no session.
+    // Without a connection, the last account's cache can be browsed instead.
+    let (user, server) = match current_user(&account) {
+        Some(user) => (user, account.server.clone()),
+        None => match auth_screen(
+            &mut terminal_guard.terminal,
+            &account,
+            Store::last_account(),
+            &colors,
+            config.poll(),
+        )? {
+            Some(SignedIn::Online(user)) => (user, account.server.clone()),
+            // The cache lives under the server it was fetched from
+            Some(SignedIn::Offline(last)) => (last.offline_user(), last.server),
+            None => {
+                drop(terminal_guard);
+                println!("Login failed");
+                return Ok(());
+            }
+        },
     };
 
     // Skip prameter
@@ -106,233 +182,354 @@ fn main() -> io::Result<()> {
     if !skip {
         let mut should_quit = false;
         while !should_quit {
-            terminal.draw(|f| {
-                start_screen(f, &user.username);
+            terminal_guard.terminal.draw(|f| {
+                start_screen(f, &user.username, &colors);
             })?;
-            should_quit = handle_events()?.should_quit;
+            should_quit = matches!(handle_events(None, config.poll())?, Some(Action::Quit));
         }
     }
 
-    // Set the REPL mode
-    let mut repl_mode = UIMode::ThinkPublic;
-
-    // Tell the user
-    // co_say(
-    //     &format!(
-    //         "you're in the comind repl, type {} to see commands.",
-    //         "help".bold()
-    //     ),
-    //     &colors,
-    // );
-
-    // Explain Think mode
-    // co_say(
-    //     &format!(
-    //         "you're in {} mode. type a new thought to send to comind",
-    //         repl_mode.prompt(&colors)
-    //     ),
-    //     &colors,
-    // );
-
-    // Get user thoughts
-    let user_thoughts = get_user_thoughts(&user, None, None);
-    // co_say(
-    //     &format!("we loaded {} of your thoughts", user_thoughts.len()),
-    //     &colors,
-    // );
+    // Set up the app, and the worker that does its network calls
+    let mut app = App::new(&user.username, user.session_info(&server), &config);
+    let store = Store::open(&server, &user.username).ok();
+    let client = Client::for_user(&server, &user).with_page_size(config.thoughts.page_size);
+    let (worker, results) = Worker::new(client, user.clone(), store.clone());
+
+    // Start from what we've seen before, then refresh in the background
+    if let Some(store) = &store {
+        let _ = store.save_account_info(&AccountInfo::new(&server, &user));
+        app.load_cache(store.load_thoughts(), store.load_pings(), store.load_feed());
+        app.load_outbox(store.load_outbox());
+        app.load_sync(store.load_sync());
+        app.load_filter(store.load_filter());
+        app.load_following(store.load_following());
+        app.load_pings_read(store.load_pings_read());
+        app.load_drafts(store.load_drafts());
+    }
+    let tasks = app.update(Action::Refresh);
+    run_tasks(
+        tasks,
+        &worker,
+        store.as_ref(),
+        &mut app,
+        &mut terminal_guard,
+    )?;
+    app.set_message(&format!("hey {}, welcome to comind", user.username));
 
     // Enter REPL
-    let mut should_quit = false;
-    let mut ui_state = UIState {
-        mode: UIMode::Thoughts,
-        tab: 0,
-        thoughts: user_thoughts,
-        cursor_position: 0,
-        selected_thought: None,
-    };
-    while !should_quit {
-        let thought_count = ui_state.thoughts.len();
-        terminal.draw(|f| {
-            ui(f, &ui_state);
-        })?;
-        match handle_events() {
-            Ok(result) => {
-                if result.should_quit {
-                    should_quit = true;
-                }
-                if result.next_tab {
-                    ui_state = UIState {
-                        mode: next_mode(ui_state.mode),
-                        tab: (ui_state.tab + 1) % 2,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: ui_state.cursor_position,
-                        selected_thought: ui_state.selected_thought,
-                    };
-                }
-                if result.prev_tab {
-                    ui_state = UIState {
-                        mode: prev_mode(ui_state.mode),
-                        tab: (ui_state.tab + 1) % 2,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: ui_state.cursor_position,
-                        selected_thought: ui_state.selected_thought,
-                    };
-                }
-                if result.up {
-                    ui_state = UIState {
-                        mode: ui_state.mode,
-                        tab: ui_state.tab,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: if ui_state.cursor_position == 0 {
-                            thought_count - 1
-                        } else {
-                            ui_state.cursor_position - 1
-                        },
-                        selected_thought: ui_state.selected_thought,
-                    };
-                }
-                if result.down {
-                    ui_state = UIState {
-                        mode: ui_state.mode,
-                        tab: ui_state.tab,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: if ui_state.cursor_position == thought_count - 1 {
-                            0
-                        } else {
-                            ui_state.cursor_position + 1
-                        },
-                        selected_thought: ui_state.selected_thought,
-                    };
-                }
-                if result.selected {
-                    let thought = ui_state.thoughts[ui_state.cursor_position].clone();
-                    ui_state = UIState {
-                        mode: ui_state.mode,
-                        tab: ui_state.tab,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: ui_state.cursor_position,
-                        selected_thought: Some(thought),
-                    };
-                }
-                if result.escape {
-                    ui_state = UIState {
-                        mode: ui_state.mode,
-                        tab: ui_state.tab,
-                        thoughts: ui_state.thoughts,
-                        cursor_position: ui_state.cursor_position,
-                        selected_thought: None,
-                    };
-                }
+    let mut should_logout = false;
+    let mut switch_to = None;
+    let mut last_tick = Instant::now();
+    loop {
+        terminal_guard.terminal.draw(|f| app.render(f))?;
+
+        let mut tasks = Vec::new();
+
+        // Network results
+        while let Ok(action) = results.try_recv() {
+            tasks.extend(app.update(action));
+        }
+
+        // Key presses
+        if let Some(action) = handle_events(Some(&mut app), config.poll())? {
+            tasks.extend(app.update(action));
+        }
+
+        // Timer ticks, for spinners, messages and background refresh
+        if last_tick.elapsed() >= config.poll() {
+            last_tick = Instant::now();
+            tasks.extend(app.update(Action::Tick));
+        }
+
+        match run_tasks(
+            tasks,
+            &worker,
+            store.as_ref(),
+            &mut app,
+            &mut terminal_guard,
+        )? {
+            Exit::Continue => {}
+            Exit::Quit => break,
+            Exit::Logout => {
+                should_logout = true;
+                break;
             }
-            Err(e) => {
-                println!("Error: {:?}", e);
-                should_quit = true;
+            Exit::SwitchProfile(profile) => {
+                switch_to = Some(profile);
+                break;
             }
         }
     }
 
     // CLose up shop
-    disable_raw_mode()?;
-    stdout().execute(LeaveAlternateScreen)?;
+    drop(terminal_guard);
+
+    // Log out once the terminal is back to normal, so messages are visible
+    if should_logout {
+        logout(&account, Some(&user), &colors);
+    }
+
+    // Start over with the other profile's server and login
+    if let Some(profile) = switch_to {
+        return restart_with(&profile);
+    }
+
+    Ok(())
+}
+
+// Load the logged-in user from the keyring without prompting
+fn current_user(account: &Account) -> Option<types::User> {
+    auth::load_user(&account.profile).ok()
+}
+
+// Pull `--profile NAME` or `--profile=NAME` out of the arguments
+fn take_profile(args: Vec<String>) -> (Option<String>, Vec<String>) {
+    let mut profile = None;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            profile = args.next();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
+        } else {
+            rest.push(arg);
+        }
+    }
 
-    return Ok(());
+    (profile, rest)
 }
 
-// Event handling return struct
-struct EventResult {
-    should_quit: bool,
-    next_tab: bool,
-    prev_tab: bool,
-    up: bool,
-    down: bool,
-    selected: bool,
-    escape: bool,
+// Print the settings in effect for `comode config`
+fn show_config(profile: Option<String>) {
+    let path = Config::path();
+    match &path {
+        Some(path) if path.exists() => println!("# config file: {}", path.display()),
+        Some(path) => println!(
+            "# config file: {} (not found, using defaults)",
+            path.display()
+        ),
+        None => println!("# no config directory, using defaults"),
+    }
+
+    let config = match Config::load(profile) {
+        Ok(config) => config,
+        Err(e) => {
+            print!("{}", e);
+            return;
+        }
+    };
+    let account = config.account();
+    println!("# profile: {}, server: {}", account.profile, account.server);
+    println!();
+    print!("{}", config.effective());
+
+    println!();
+    println!("# commands that can be bound in [keys], by where they work:");
+    for context in [
+        Context::Global,
+        Context::List,
+        Context::Detail,
+        Context::Editor,
+    ] {
+        println!("#");
+        println!("# {}", context.name());
+        for command in Command::all()
+            .iter()
+            .filter(|command| command.context() == context)
+        {
+            println!("#   {:<16} {}", command.name(), command.help());
+        }
+    }
 }
 
-// Default event result, no events
-fn default_event_result() -> EventResult {
-    return EventResult {
-        should_quit: false,
-        next_tab: false,
-        prev_tab: false,
-        up: false,
-        down: false,
-        selected: false,
-        escape: false,
+// Print session info for `comode whoami`
+fn whoami(account: &Account, colors: &ComindColors) {
+    let user = match current_user(account) {
+        Some(user) => user,
+        None => {
+            co_say("you're not logged in", colors);
+            return;
+        }
     };
+
+    for (label, value) in user.session_info(&account.server) {
+        println!("{:>12}: {}", label, value);
+    }
 }
 
-fn handle_events() -> io::Result<EventResult> {
-    if event::poll(std::time::Duration::from_millis(50))? {
-        if let Event::Key(key) = event::read()? {
-            // Show the key
-            // println!("{:?}", key);
-
-            // Character matching
-            match key.code {
-                KeyCode::Char('c') => {
-                    if key
-                        .modifiers
-                        .contains(crossterm::event::KeyModifiers::CONTROL)
-                    {
-                        return Ok(EventResult {
-                            should_quit: true,
-                            ..default_event_result()
-                        });
-                    }
-                }
-                KeyCode::Up => {
-                    return Ok(EventResult {
-                        up: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::Down => {
-                    return Ok(EventResult {
-                        down: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::Tab => {
-                    return Ok(EventResult {
-                        next_tab: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::BackTab => {
-                    return Ok(EventResult {
-                        prev_tab: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::Char('q') => {
-                    return Ok(EventResult {
-                        should_quit: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::Enter => {
-                    return Ok(EventResult {
-                        selected: true,
-                        ..default_event_result()
-                    })
-                }
-                KeyCode::Esc => {
-                    return Ok(EventResult {
-                        escape: true,
-                        ..default_event_result()
-                    })
+// Usage for the command line
+fn print_usage() {
+    println!("usage: comode [--profile NAME] [command]");
+    println!();
+    println!("commands:");
+    println!("  (none)    start the comind TUI");
+    println!("  login     log in from the command line");
+    println!("  register  create a new comind account");
+    println!("  think     post a thought: think [--private] [--edit] [text]");
+    println!("  list      list your thoughts, with filters: list --help");
+    println!("  export    write your thoughts to a folder as Markdown: export <dir>");
+    println!("  import    make thoughts from Markdown notes or a JSON dump: import --help");
+    println!("  backup    save every thought, ping and link to one file: backup [file]");
+    println!("  restore   make the thoughts in a backup again, here: restore --help");
+    println!("  whoami    show the logged-in user and session");
+    println!("  logout    revoke and forget your session token");
+    println!("  config    show the settings in effect and where they're read from");
+    println!("  help      show this message");
+}
+
+// What the main loop should do after running tasks
+enum Exit {
+    Continue,
+    Quit,
+    Logout,
+    SwitchProfile(String),
+}
+
+// Run tasks left by App::update. Network requests go to the worker,
+// and their results come back through its channel. Tasks that finish
+// straight away, like the editor, feed their result back into the app.
+fn run_tasks(
+    tasks: Vec<Task>,
+    worker: &Worker,
+    store: Option<&Store>,
+    app: &mut App,
+    terminal_guard: &mut TerminalGuard,
+) -> io::Result<Exit> {
+    let mut tasks = VecDeque::from(tasks);
+    while let Some(task) = tasks.pop_front() {
+        match task {
+            Task::Quit => return Ok(Exit::Quit),
+            Task::Logout => return Ok(Exit::Logout),
+            Task::SwitchProfile(profile) => return Ok(Exit::SwitchProfile(profile)),
+            Task::Suspend => terminal_guard.suspend()?,
+            Task::Spawn(job, request) => worker.spawn(job, request),
+            Task::Cancel(job) => worker.cancel(job),
+            Task::SaveOutbox(outbox) => save_local(store, app, "unsent thoughts", |store| {
+                store.save_outbox(&outbox)
+            }),
+            Task::SaveSync(state) => save_local(store, app, "unsynced edits", |store| {
+                store.save_sync(&state)
+            }),
+            Task::SaveDrafts(drafts) => {
+                save_local(store, app, "drafts", |store| store.save_drafts(&drafts))
+            }
+            Task::SaveFilter(filter) => {
+                save_local(store, app, "filters", |store| store.save_filter(&filter))
+            }
+            Task::SaveFollowing(following) => save_local(store, app, "follows", |store| {
+                store.save_following(&following)
+            }),
+            Task::SavePingsRead(read) => save_local(store, app, "read pings", |store| {
+                store.save_pings_read(&read)
+            }),
+            Task::OpenEditor { document, review } => {
+                let result = terminal_guard.run_outside(|| editor::edit(&document))?;
+                tasks.extend(app.update(Action::EditorClosed { result, review }));
+            }
+            Task::OpenLink(url) => {
+                if let Err(e) = open_link(&url) {
+                    app.set_message(&format!("couldn't open {}: {}", url, e));
                 }
-                _ => {}
             }
         }
     }
 
-    return Ok(default_event_result());
+    Ok(Exit::Continue)
+}
+
+// Run comode again with another profile, in place of this process
+#[cfg(unix)]
+fn restart_with(profile: &str) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // exec only returns if it failed
+    Err(std::process::Command::new(std::env::current_exe()?)
+        .arg("--profile")
+        .arg(profile)
+        .exec())
+}
+
+// Without exec, run it as a child and pass on how it exited
+#[cfg(not(unix))]
+fn restart_with(profile: &str) -> io::Result<()> {
+    let status = std::process::Command::new(std::env::current_exe()?)
+        .arg("--profile")
+        .arg(profile)
+        .status()?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+// Open a link in the browser, without letting it write over the TUI
+fn open_link(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+
+    command
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+// Write something that only exists locally, saying so if it can't be
+fn save_local<F>(store: Option<&Store>, app: &mut App, what: &str, save: F)
+where
+    F: FnOnce(&Store) -> io::Result<()>,
+{
+    let saved = match store {
+        Some(store) => save(store),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "no data directory")),
+    };
+    if let Err(e) = saved {
+        app.set_message(&format!("couldn't save {}: {}", what, e));
+    }
+}
+
+// Wait briefly for an event. Key presses become actions via the app (or just
+// quit on the start screen).
+fn handle_events(app: Option<&mut App>, poll: Duration) -> io::Result<Option<Action>> {
+    if !event::poll(poll)? {
+        return Ok(None);
+    }
+
+    match event::read()? {
+        Event::Key(key) => {
+            // Ignore key releases on terminals that report them
+            if key.kind != KeyEventKind::Press {
+                return Ok(None);
+            }
+
+            return match app {
+                Some(app) => Ok(app.handle_key(key)),
+                None => Ok(Some(Action::Quit)),
+            };
+        }
+        Event::Mouse(mouse) => {
+            // Clicks are worked out against the layout of the whole screen
+            let (width, height) = crossterm::terminal::size()?;
+            return match app {
+                Some(app) => Ok(app.handle_mouse(mouse, Rect::new(0, 0, width, height))),
+                None => Ok(None),
+            };
+        }
+        _ => {}
+    }
+
+    Ok(None)
 }
 
 // Intro screen, "welcome to comind" in center
-fn start_screen(frame: &mut Frame, username: &str) {
+fn start_screen(frame: &mut Frame, username: &str, colors: &ComindColors) {
     let size = frame.size();
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -346,7 +543,7 @@ fn start_screen(frame: &mut Frame, username: &str) {
     );
     frame.render_widget(
         Paragraph::new(welcome)
-            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .style(colors.base())
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -358,146 +555,3 @@ fn start_screen(frame: &mut Frame, username: &str) {
         layout[0],
     );
 }
-
-// UI State
-struct UIState {
-    mode: UIMode,
-    tab: usize,
-    thoughts: Vec<Thought>,
-    cursor_position: usize,
-    selected_thought: Option<Thought>,
-}
-
-// Main screen, with thoughts and pings
-fn ui(frame: &mut Frame, ui_state: &UIState) {
-    // Outer layout
-    let outer_layout =
-        Layout::new(Direction::Vertical, [Constraint::Percentage(100)]).split(frame.size());
-
-    // First, check if there's a selected thought
-    if let Some(thought) = &ui_state.selected_thought {
-        // Thought text
-        let thought_text = format!("{}\n\n", thought.body.as_str());
-
-        // Render the thought
-        let thought_text = Line::from(vec![Span::styled(
-            thought_text.as_str(),
-            Style::default().fg(Color::White).bg(Color::Black),
-        )]);
-
-        // Get Thought title
-        let title = match &thought.title {
-            Some(title) => title,
-            None => " ∘ ",
-        };
-
-        let thought = Paragraph::new(thought_text)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(format!(" {} ", title)),
-            )
-            .style(Style::default().fg(Color::White).bg(Color::Black))
-            .alignment(Alignment::Left);
-
-        frame.render_widget(thought, outer_layout[0]);
-
-        return;
-    }
-
-    // Tabs first
-    let modes = mode_strings();
-    let tabs = Tabs::new(modes)
-        .block(Block::default().borders(Borders::TOP).title("comind"))
-        .style(Style::default().white())
-        .highlight_style(Style::default().yellow())
-        .select(ui_state.tab)
-        .divider(symbols::DOT);
-
-    frame.render_widget(tabs, outer_layout[0]);
-
-    // Display thoughts if tab 0
-    if ui_state.mode == UIMode::Thoughts {
-        // Inner layout
-        let inner_layout =
-            Layout::new(Direction::Horizontal, [Constraint::Fill(1)]).split(outer_layout[0]);
-
-        // Make the left side
-        // let left = Block::default()
-        //     .title("Thoughts")
-        //     .borders(Borders::ALL)
-        //     .padding(Padding::uniform(4))
-        //     .border_type(BorderType::Rounded)
-        //     .style(Style::default().fg(Color::White).bg(Color::Red));
-        // frame.render_widget(left, inner_layout[0]);
-
-        // Thought strings
-        let thoughts = ui_state
-            .thoughts
-            .iter()
-            .enumerate()
-            .map(|(i, thought)| {
-                let col = if i % 2 == 0 {
-                    Color::Black
-                } else {
-                    Color::Rgb(1, 1, 1)
-                };
-
-                // Two forms of lines to display. If the thought has a newline in it, display only
-                // the title. Otherwise, display only the body. Include username and relative time
-                // in both.
-                //
-                // Start with a span for the username and relative time, since those are common to
-                // both forms.
-                let cursor = if ui_state.cursor_position == i {
-                    "👉 "
-                } else {
-                    "   "
-                };
-                let username_span = Span::styled(
-                    format!("{}[{}] ", cursor, thought.username),
-                    Style::default().fg(Color::White).bg(col).bold(),
-                );
-                let body_or_title = if thought.body.len() < 80 && !thought.body.contains("\n") {
-                    Span::styled(
-                        thought.body.as_str(),
-                        Style::default().fg(Color::White).bg(col),
-                    )
-                } else {
-                    match thought.title {
-                        Some(ref title) => Span::styled(
-                            title.as_str(),
-                            Style::default().fg(Color::White).bg(col).underlined(),
-                        ),
-                        None => Span::styled(
-                            thought.body.as_str(),
-                            Style::default().fg(Color::White).bg(col),
-                        ),
-                    }
-                };
-
-                let body_line = Line::from(vec![username_span, body_or_title]);
-                // let text = Text::from(vec![body_line, username_date_line]);
-
-                ListItem::new(body_line).style(Style::default().fg(Color::White).bg(col))
-            })
-            .collect::<Vec<ListItem>>();
-
-        let thoughts = List::new(thoughts).block(Block::default().padding(Padding::uniform(2)));
-
-        frame.render_widget(thoughts, inner_layout[0]);
-    }
-
-    // Thoughts
-    // let thoughts = vec![
-    //     ListItem::new("This is a thought"),
-    //     ListItem::new("This is another thought"),
-    // ];
-
-    // let thoughts = List::new(thoughts)
-    //     .block(Block::default().borders(Borders::ALL).title("Thoughts"))
-    //     .style(Style::default().fg(Color::White).bg(Color::Black))
-    //     .highlight_style(Style::default().fg(Color::Black).bg(Color::White));
-
-    // frame.render_widget(thoughts, main_layout[0]);
-}
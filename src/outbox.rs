@@ -0,0 +1,135 @@
+//! Thoughts waiting to be sent.
+//!
+//! Every new thought goes into the outbox first and is saved to disk, so it
+//! survives the network or server being down, or comode exiting. Items are
+//! retried with exponential backoff until they are sent, or until the server
+//! rejects them outright, in which case they keep the error so they can be
+//! edited or discarded.
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::ApiError;
+
+// First retry delay, doubled after each failure up to MAX_BACKOFF
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+///
+/// A thought that hasn't reached the server yet.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct OutboxItem {
+    /// Local id, only used to tell outbox items apart
+    pub local_id: Uuid,
+    pub title: String,
+    pub body: String,
+    pub public: bool,
+    pub created_at: DateTime<Utc>,
+
+    /// Failed attempts so far
+    pub attempts: u32,
+    /// Don't try again before this
+    pub next_attempt: DateTime<Utc>,
+    /// Why the last attempt failed, while we're still retrying
+    pub last_error: Option<String>,
+    /// Set when the server rejected the thought. Failed items aren't retried.
+    pub failed: Option<String>,
+}
+
+impl OutboxItem {
+    pub fn new(title: &str, body: &str, public: bool) -> OutboxItem {
+        let now = Utc::now();
+        OutboxItem {
+            local_id: Uuid::new_v4(),
+            title: title.to_string(),
+            body: body.to_string(),
+            public,
+            created_at: now,
+            attempts: 0,
+            next_attempt: now,
+            last_error: None,
+            failed: None,
+        }
+    }
+
+    /// Ready to be sent.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.failed.is_none() && self.next_attempt <= now
+    }
+
+    ///
+    /// Record a failed attempt. Temporary problems (no connection, server
+    /// errors) are retried later; anything else fails the item for good.
+    ///
+    pub fn record_failure(&mut self, error: &ApiError) {
+        self.attempts += 1;
+
+        if error.is_transient() {
+            self.last_error = Some(error.to_string());
+            self.next_attempt = Utc::now() + backoff(self.attempts);
+        } else {
+            self.failed = Some(error.to_string());
+        }
+    }
+
+    /// Try again as soon as possible, e.g. when the connection comes back.
+    pub fn retry_now(&mut self) {
+        self.next_attempt = Utc::now();
+    }
+}
+
+///
+/// Delay before retry number `attempts`.
+///
+pub fn backoff(attempts: u32) -> chrono::Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    let delay = BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF);
+    chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: u16) -> ApiError {
+        ApiError::Status {
+            status,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff(1), chrono::Duration::seconds(5));
+        assert_eq!(backoff(2), chrono::Duration::seconds(10));
+        assert_eq!(backoff(4), chrono::Duration::seconds(40));
+        assert_eq!(backoff(9), chrono::Duration::minutes(15));
+        assert_eq!(backoff(u32::MAX), chrono::Duration::minutes(15));
+    }
+
+    #[test]
+    fn transient_failures_are_retried_later() {
+        let mut item = OutboxItem::new("", "hello", true);
+        assert!(item.is_due(Utc::now()));
+
+        item.record_failure(&status(503));
+        assert_eq!(item.attempts, 1);
+        assert!(item.failed.is_none());
+        assert!(item.last_error.is_some());
+        assert!(!item.is_due(Utc::now()));
+
+        item.retry_now();
+        assert!(item.is_due(Utc::now()));
+    }
+
+    #[test]
+    fn rejected_thoughts_fail_for_good() {
+        let mut item = OutboxItem::new("", "hello", true);
+        item.record_failure(&status(422));
+        assert!(item.failed.is_some());
+        assert!(!item.is_due(Utc::now() + backoff(20)));
+    }
+}
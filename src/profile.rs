@@ -0,0 +1,124 @@
+//! Other users: who they are, their public thoughts, and how those thoughts
+//! link to yours.
+//!
+//! `client::Client::get_profile` looks a user up by name or id and fetches
+//! their thoughts. Only public thoughts are kept, whatever the server sends.
+use serde::{Deserialize, Serialize};
+
+use crate::filter::links;
+use crate::types::{Thought, ThoughtId, UserId};
+
+///
+/// A user as the server describes them to others.
+///
+/// # Example
+///
+/// ```json
+/// {
+///     "user_id": "6b129b9f-b958-4cf0-a6ad-3108d221177a",
+///    "username": "cameron"
+/// }
+/// ```
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UserInfo {
+    pub user_id: UserId,
+    pub username: String,
+}
+
+///
+/// A user to look up, by name or by id. Pings only have the id of the user
+/// who linked to you.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Who {
+    Name(String),
+    Id(UserId),
+}
+
+///
+/// A user and their public thoughts, in the order the server sent them.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub user: UserInfo,
+    pub thoughts: Vec<Thought>,
+}
+
+///
+/// A link between one of their thoughts and one of yours.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Link {
+    pub theirs: ThoughtId,
+    pub mine: ThoughtId,
+    /// True if their thought links to yours, false if yours links to theirs
+    pub to_mine: bool,
+}
+
+///
+/// Numbers for the top of a profile.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Counts {
+    /// Public thoughts
+    pub thoughts: usize,
+    /// Links to their thoughts from anyone, as far as the server said
+    pub links: i64,
+    /// Their thoughts that link to yours
+    pub to_mine: usize,
+    /// Your thoughts that link to theirs
+    pub from_mine: usize,
+}
+
+impl Profile {
+    /// A profile with the public ones of `thoughts`.
+    pub fn new(user: UserInfo, thoughts: Vec<Thought>) -> Profile {
+        Profile {
+            user,
+            thoughts: thoughts
+                .into_iter()
+                .filter(|thought| thought.public)
+                .collect(),
+        }
+    }
+
+    ///
+    /// Links between this user's thoughts and `mine`, either way round, in
+    /// the order of their thoughts.
+    ///
+    pub fn links_with(&self, mine: &[Thought]) -> Vec<Link> {
+        let mut found = Vec::new();
+        for theirs in &self.thoughts {
+            if let Some(to) = theirs.to_id.filter(|to| mine.iter().any(|m| m.id == *to)) {
+                found.push(Link {
+                    theirs: theirs.id,
+                    mine: to,
+                    to_mine: true,
+                });
+            }
+            for m in mine.iter().filter(|m| m.to_id == Some(theirs.id)) {
+                found.push(Link {
+                    theirs: theirs.id,
+                    mine: m.id,
+                    to_mine: false,
+                });
+            }
+        }
+        found
+    }
+
+    pub fn counts(&self, mine: &[Thought]) -> Counts {
+        let found = self.links_with(mine);
+        Counts {
+            thoughts: self.thoughts.len(),
+            links: self
+                .thoughts
+                .iter()
+                .map(|thought| links(thought) as i64)
+                .sum(),
+            to_mine: found.iter().filter(|link| link.to_mine).count(),
+            from_mine: found.iter().filter(|link| !link.to_mine).count(),
+        }
+    }
+}
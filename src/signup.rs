@@ -0,0 +1,300 @@
+// Log in / sign up screens for the TUI
+use std::io::{self, Stdout};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+use tui_textarea::TextArea;
+
+use comode::store::AccountInfo;
+use comode::types::User;
+
+use crate::colors::ComindColors;
+use crate::components::centered;
+use crate::config::Account;
+use crate::login::{login_with_credentials, register};
+
+type Term = Terminal<CrosstermBackend<Stdout>>;
+
+// Which form is being filled in
+#[derive(Clone, Copy, PartialEq)]
+enum FormKind {
+    Login,
+    Signup,
+}
+
+// One labelled text input
+struct Field<'a> {
+    label: &'static str,
+    input: TextArea<'a>,
+    secret: bool,
+}
+
+impl<'a> Field<'a> {
+    fn new(label: &'static str, secret: bool) -> Field<'a> {
+        let mut input = TextArea::default();
+        input.set_cursor_line_style(Style::default());
+        if secret {
+            input.set_mask_char('•');
+        }
+        Field {
+            label,
+            input,
+            secret,
+        }
+    }
+
+    // Passwords are kept as typed, other fields lose stray spaces
+    fn value(&self) -> String {
+        let line = &self.input.lines()[0];
+        match self.secret {
+            true => line.clone(),
+            false => line.trim().to_string(),
+        }
+    }
+}
+
+// A login or sign-up form
+struct Form<'a> {
+    kind: FormKind,
+    fields: Vec<Field<'a>>,
+    focus: usize,
+    message: Option<String>,
+}
+
+impl<'a> Form<'a> {
+    fn new(kind: FormKind) -> Form<'a> {
+        let fields = match kind {
+            FormKind::Login => vec![
+                Field::new("username or email", false),
+                Field::new("password", true),
+            ],
+            FormKind::Signup => vec![
+                Field::new("username", false),
+                Field::new("email", false),
+                Field::new("password", true),
+                Field::new("confirm password", true),
+            ],
+        };
+
+        Form {
+            kind,
+            fields,
+            focus: 0,
+            message: None,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self.kind {
+            FormKind::Login => " log in to comind ",
+            FormKind::Signup => " sign up for comind ",
+        }
+    }
+
+    // Send the fields to the server. Sign-ups are checked locally first,
+    // so most mistakes never make the round trip.
+    fn submit(&mut self, account: &Account) -> Option<User> {
+        let values = self
+            .fields
+            .iter()
+            .map(|f| f.value())
+            .collect::<Vec<String>>();
+
+        let result = match self.kind {
+            FormKind::Login => login_with_credentials(account, &values[0], &values[1]),
+            FormKind::Signup => register(account, &values[0], &values[1], &values[2], &values[3]),
+        };
+
+        match result {
+            Ok(user) => Some(user),
+            Err(e) => {
+                self.message = Some(e);
+                None
+            }
+        }
+    }
+}
+
+///
+/// How the auth screen was left.
+///
+pub enum SignedIn {
+    /// Logged in or signed up on the account's server
+    Online(User),
+    /// Browsing the cache of the last account, which may be on another server
+    Offline(AccountInfo),
+}
+
+///
+/// Shown when there is no stored session. Lets the user pick between
+/// logging in and creating an account.
+///
+/// If `offline` is the last account used, its cached thoughts can be browsed
+/// without logging in.
+///
+/// Returns `None` if the user quits.
+///
+pub fn auth_screen(
+    terminal: &mut Term,
+    account: &Account,
+    offline: Option<AccountInfo>,
+    theme: &ComindColors,
+    poll: Duration,
+) -> io::Result<Option<SignedIn>> {
+    loop {
+        terminal.draw(|f| choice_ui(f, offline.as_ref(), theme))?;
+
+        if !event::poll(poll)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let kind = match key.code {
+                KeyCode::Char('l') | KeyCode::Enter => FormKind::Login,
+                KeyCode::Char('s') => FormKind::Signup,
+                KeyCode::Char('o') => match &offline {
+                    Some(last) => return Ok(Some(SignedIn::Offline(last.clone()))),
+                    None => continue,
+                },
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(None)
+                }
+                _ => continue,
+            };
+
+            // Back to the choice screen if the form is cancelled
+            if let Some(user) = run_form(terminal, account, Form::new(kind), theme, poll)? {
+                return Ok(Some(SignedIn::Online(user)));
+            }
+        }
+    }
+}
+
+// Drive a form until it succeeds or is cancelled
+fn run_form(
+    terminal: &mut Term,
+    account: &Account,
+    mut form: Form,
+    theme: &ComindColors,
+    poll: Duration,
+) -> io::Result<Option<User>> {
+    loop {
+        terminal.draw(|f| form_ui(f, &form, theme))?;
+
+        if !event::poll(poll)? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Tab | KeyCode::Down => {
+                form.focus = (form.focus + 1) % form.fields.len();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                form.focus = (form.focus + form.fields.len() - 1) % form.fields.len();
+            }
+            KeyCode::Enter => {
+                // Enter moves down the form, and submits on the last field
+                if form.focus + 1 < form.fields.len() {
+                    form.focus += 1;
+                    continue;
+                }
+
+                form.message = Some("talking to comind...".to_string());
+                terminal.draw(|f| form_ui(f, &form, theme))?;
+                if let Some(user) = form.submit(account) {
+                    return Ok(Some(user));
+                }
+            }
+            _ => {
+                form.fields[form.focus].input.input(key);
+            }
+        }
+    }
+}
+
+// "log in or sign up"
+fn choice_ui(frame: &mut Frame, offline: Option<&AccountInfo>, theme: &ComindColors) {
+    let mut text = vec![
+        Line::from("you're not logged in"),
+        Line::from(""),
+        Line::from(vec![Span::raw("l").bold(), Span::raw(" log in")]),
+        Line::from(vec![Span::raw("s").bold(), Span::raw(" sign up")]),
+    ];
+    if let Some(last) = offline {
+        text.push(Line::from(vec![
+            Span::raw("o").bold(),
+            Span::raw(format!(" browse offline as {}", last.username)),
+        ]));
+    }
+    text.push(Line::from(vec![Span::raw("q").bold(), Span::raw(" quit")]));
+    let area = centered(frame.size(), 44, text.len() as u16 + 2);
+
+    frame.render_widget(Block::default().style(theme.base()), frame.size());
+    frame.render_widget(
+        Paragraph::new(text).alignment(Alignment::Center).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(theme.border())
+                .title(" comind "),
+        ),
+        area,
+    );
+}
+
+// Form with one row per field and a message line
+fn form_ui(frame: &mut Frame, form: &Form, theme: &ComindColors) {
+    let height = form.fields.len() as u16 * 3 + 4;
+    let area = centered(frame.size(), 50, height);
+    frame.render_widget(Block::default().style(theme.base()), frame.size());
+
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border())
+        .title(form.title())
+        .title_bottom(" tab: next field · enter: submit · esc: back ");
+    let inner = outer.inner(area);
+    frame.render_widget(outer, area);
+
+    let mut constraints = vec![Constraint::Length(3); form.fields.len()];
+    constraints.push(Constraint::Min(1));
+    let rows = Layout::new(Direction::Vertical, constraints).split(inner);
+
+    for (i, field) in form.fields.iter().enumerate() {
+        let style = if i == form.focus {
+            theme.focused()
+        } else {
+            theme.border()
+        };
+
+        let mut input = field.input.clone();
+        input.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(style)
+                .title(format!(" {} ", field.label)),
+        );
+        if i != form.focus {
+            input.set_cursor_style(Style::default());
+        }
+        frame.render_widget(input.widget(), rows[i]);
+    }
+
+    if let Some(message) = &form.message {
+        frame.render_widget(
+            Paragraph::new(message.as_str()).wrap(Wrap { trim: true }),
+            rows[form.fields.len()],
+        );
+    }
+}
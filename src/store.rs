@@ -0,0 +1,371 @@
+//! Local store for what comode has seen, so it can start instantly and work
+//! offline.
+//!
+//! Each account gets a directory under the data dir
+//! (`~/.local/share/comode/<server>/<username>/`) holding JSON files for
+//! thoughts, pings, the feed and whose account it is, plus things that only
+//! exist locally: the outbox, unsynced edits, drafts, the thoughts filter,
+//! the follow list and which pings were marked read. Files are replaced
+//! atomically, so a crash mid-write leaves the previous copy intact.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::drafts::Draft;
+use crate::filter::ThoughtFilter;
+use crate::follow::Following;
+use crate::outbox::OutboxItem;
+use crate::sync::SyncState;
+use crate::types::{Ping, Thought, User, UserId};
+use crate::utils::data_dir;
+
+///
+/// Something loaded from the store, with when it was fetched from the server.
+///
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Cached<T> {
+    pub fetched_at: DateTime<Utc>,
+    pub items: T,
+}
+
+///
+/// Who an account directory belongs to.
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AccountInfo {
+    pub username: String,
+    pub user_id: UserId,
+    pub server: String,
+}
+
+impl AccountInfo {
+    pub fn new(server: &str, user: &User) -> AccountInfo {
+        AccountInfo {
+            username: user.username.clone(),
+            user_id: user.user_id,
+            server: server.to_string(),
+        }
+    }
+
+    ///
+    /// A user without a token, for browsing the cache when we can't log in.
+    /// Any request made with it will fail.
+    ///
+    pub fn offline_user(&self) -> User {
+        User {
+            token: String::new(),
+            user_id: self.user_id,
+            username: self.username.clone(),
+            issued_at: None,
+            expires_at: Utc::now(),
+        }
+    }
+}
+
+///
+/// One account's local store.
+///
+#[derive(Clone, Debug)]
+pub struct Store {
+    dir: PathBuf,
+}
+
+// File names
+const THOUGHTS: &str = "thoughts.json";
+const PINGS: &str = "pings.json";
+const ACCOUNT_INFO: &str = "profile.json";
+const OUTBOX: &str = "outbox.json";
+const SYNC: &str = "sync.json";
+const DRAFTS: &str = "drafts.json";
+const FILTER: &str = "filter.json";
+const FEED: &str = "feed.json";
+const FOLLOWING: &str = "following.json";
+const PINGS_READ: &str = "pings-read.json";
+const LAST_ACCOUNT: &str = "last-profile.json";
+
+impl Store {
+    ///
+    /// The store for `username` on `server`, under the XDG data dir.
+    ///
+    pub fn open(server: &str, username: &str) -> io::Result<Store> {
+        let root = match data_dir() {
+            Some(root) => root,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "couldn't find a data directory, set XDG_DATA_HOME or HOME",
+                ))
+            }
+        };
+
+        Store::open_at(root.join(server_dir(server)).join(dir_name(username)))
+    }
+
+    ///
+    /// A store in a specific directory.
+    ///
+    pub fn open_at(dir: PathBuf) -> io::Result<Store> {
+        fs::create_dir_all(&dir)?;
+        Ok(Store { dir })
+    }
+
+    /// The directory this store lives in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn load_thoughts(&self) -> Option<Cached<Vec<Thought>>> {
+        read_json(&self.dir.join(THOUGHTS))
+    }
+
+    pub fn save_thoughts(&self, thoughts: &[Thought]) -> io::Result<()> {
+        write_cached(&self.dir.join(THOUGHTS), thoughts)
+    }
+
+    pub fn load_pings(&self) -> Option<Cached<Vec<Ping>>> {
+        read_json(&self.dir.join(PINGS))
+    }
+
+    pub fn save_pings(&self, pings: &[Ping]) -> io::Result<()> {
+        write_cached(&self.dir.join(PINGS), pings)
+    }
+
+    ///
+    /// Thoughts waiting to be sent.
+    ///
+    /// Unlike the cache, this is the only copy. A file that can't be parsed
+    /// is moved aside (so saving a new outbox doesn't overwrite it) and
+    /// reported as an error.
+    ///
+    pub fn load_outbox(&self) -> io::Result<Vec<OutboxItem>> {
+        read_precious(&self.dir.join(OUTBOX), "the outbox")
+    }
+
+    pub fn save_outbox(&self, outbox: &[OutboxItem]) -> io::Result<()> {
+        write_json(&self.dir.join(OUTBOX), outbox)
+    }
+
+    ///
+    /// Local edits not yet on the server, unresolved conflicts, and when we
+    /// last synced. Like the outbox, this is the only copy.
+    ///
+    pub fn load_sync(&self) -> io::Result<SyncState> {
+        read_precious(&self.dir.join(SYNC), "sync state")
+    }
+
+    pub fn save_sync(&self, state: &SyncState) -> io::Result<()> {
+        write_json(&self.dir.join(SYNC), state)
+    }
+
+    /// Unsent compose buffers.
+    pub fn load_drafts(&self) -> io::Result<Vec<Draft>> {
+        read_precious(&self.dir.join(DRAFTS), "drafts")
+    }
+
+    pub fn save_drafts(&self, drafts: &[Draft]) -> io::Result<()> {
+        write_json(&self.dir.join(DRAFTS), drafts)
+    }
+
+    /// The thoughts tab's filter and sort from last time.
+    pub fn load_filter(&self) -> Option<ThoughtFilter> {
+        read_json(&self.dir.join(FILTER))
+    }
+
+    pub fn save_filter(&self, filter: &ThoughtFilter) -> io::Result<()> {
+        write_json(&self.dir.join(FILTER), filter)
+    }
+
+    /// The latest thoughts of the people you follow.
+    pub fn load_feed(&self) -> Option<Cached<Vec<Thought>>> {
+        read_json(&self.dir.join(FEED))
+    }
+
+    pub fn save_feed(&self, thoughts: &[Thought]) -> io::Result<()> {
+        write_cached(&self.dir.join(FEED), thoughts)
+    }
+
+    ///
+    /// Who you follow. On servers without follows this is the only copy.
+    ///
+    pub fn load_following(&self) -> io::Result<Following> {
+        read_precious(&self.dir.join(FOLLOWING), "the follow list")
+    }
+
+    pub fn save_following(&self, following: &Following) -> io::Result<()> {
+        write_json(&self.dir.join(FOLLOWING), following)
+    }
+
+    ///
+    /// When pings were last marked read. The server keeps no read marks,
+    /// so pings up to then are shown as read.
+    ///
+    pub fn load_pings_read(&self) -> Option<DateTime<Utc>> {
+        read_json(&self.dir.join(PINGS_READ))
+    }
+
+    pub fn save_pings_read(&self, read: &DateTime<Utc>) -> io::Result<()> {
+        write_json(&self.dir.join(PINGS_READ), read)
+    }
+
+    pub fn load_account_info(&self) -> Option<AccountInfo> {
+        read_json(&self.dir.join(ACCOUNT_INFO))
+    }
+
+    ///
+    /// Save whose account this is, and remember it as the last account used
+    /// so it can be browsed offline.
+    ///
+    pub fn save_account_info(&self, info: &AccountInfo) -> io::Result<()> {
+        write_json(&self.dir.join(ACCOUNT_INFO), info)?;
+        if let Some(root) = data_dir() {
+            fs::create_dir_all(&root)?;
+            write_json(&root.join(LAST_ACCOUNT), info)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// The account comode was last used with, if any.
+    ///
+    pub fn last_account() -> Option<AccountInfo> {
+        read_json(&data_dir()?.join(LAST_ACCOUNT))
+    }
+}
+
+// "https://nimbus.pfiffer.org" -> "nimbus.pfiffer.org"
+fn server_dir(server: &str) -> String {
+    let host = server
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    dir_name(host)
+}
+
+// A name that's safe as one directory: no separators, and not `.` or `..`
+fn dir_name(name: &str) -> String {
+    match name {
+        "" | "." | ".." => "_".to_string(),
+        name => name.replace(['/', ':', '\\'], "_"),
+    }
+}
+
+// Read a JSON file, treating a missing or unreadable file as empty
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+// Read a file that holds the only copy of something. Missing is empty, but a
+// file that can't be parsed is moved aside, so saving doesn't overwrite it,
+// and reported.
+fn read_precious<T: DeserializeOwned + Default>(path: &Path, what: &str) -> io::Result<T> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(e),
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            let aside = path.with_extension(format!("json.bad-{}", Utc::now().timestamp()));
+            fs::rename(path, &aside)?;
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "couldn't read {} ({}), moved it to {}",
+                    what,
+                    e,
+                    aside.display()
+                ),
+            ))
+        }
+    }
+}
+
+// Write items with the current time
+fn write_cached<T: Serialize>(path: &Path, items: T) -> io::Result<()> {
+    write_json(
+        path,
+        &Cached {
+            fetched_at: Utc::now(),
+            items,
+        },
+    )
+}
+
+// Write a JSON file atomically: to a temporary file, then rename over
+pub(crate) fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_thought;
+
+    #[test]
+    fn servers_become_one_directory() {
+        assert_eq!(
+            server_dir("https://nimbus.pfiffer.org/"),
+            "nimbus.pfiffer.org"
+        );
+        assert_eq!(server_dir("http://localhost:8000"), "localhost_8000");
+        assert_eq!(
+            server_dir("https://example.org/comind"),
+            "example.org_comind"
+        );
+    }
+
+    #[test]
+    fn names_cant_leave_their_directory() {
+        assert_eq!(dir_name("cameron"), "cameron");
+        assert_eq!(dir_name("../etc"), ".._etc");
+        assert_eq!(dir_name(".."), "_");
+        assert_eq!(dir_name("."), "_");
+        assert_eq!(dir_name(""), "_");
+    }
+
+    #[test]
+    fn thoughts_survive_a_round_trip() {
+        let dir = std::env::temp_dir().join(format!("comode-store-{}", uuid::Uuid::new_v4()));
+        let store = Store::open_at(dir.clone()).unwrap();
+        assert!(store.load_thoughts().is_none());
+
+        let thoughts = vec![test_thought(1, "title", "body")];
+        store.save_thoughts(&thoughts).unwrap();
+        assert_eq!(store.load_thoughts().unwrap().items, thoughts);
+        assert!(!dir.join("thoughts.json.tmp").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_broken_outbox_is_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("comode-store-{}", uuid::Uuid::new_v4()));
+        let store = Store::open_at(dir.clone()).unwrap();
+        assert!(store.load_outbox().unwrap().is_empty());
+
+        fs::write(dir.join(OUTBOX), "[{\"half\": ").unwrap();
+        let error = store.load_outbox().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // The broken file is kept, and saving starts a new one
+        assert!(!dir.join(OUTBOX).exists());
+        let aside = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .find(|name| name.starts_with("outbox.json.bad-"));
+        assert!(aside.is_some());
+        assert!(store.load_outbox().unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,587 @@
+//! Two-way sync of the user's thoughts.
+//!
+//! Edits are made locally first and kept as `Edit`s, each remembering the
+//! version of the thought it started from (its base). A sync pulls the
+//! server's thoughts, then compares every edit's base revision with the
+//! server's current one:
+//!
+//! - unchanged on the server: the edit is pushed
+//! - the server already has the same text: nothing to do
+//! - both sides changed: the edit becomes a `Conflict`, kept until the user
+//!   picks a version or merges them
+//!
+//! `merge3` and `diff_lines` do the line-based diffing for the conflict view.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::blocking::Client;
+use crate::errors::{ApiError, ApiResult};
+use crate::types::{Thought, ThoughtId};
+
+///
+/// A local change to one of the user's thoughts.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Edit {
+    pub id: ThoughtId,
+    pub title: Option<String>,
+    pub body: String,
+    pub public: bool,
+    /// The thought as it was when the edit started
+    pub base: Thought,
+    pub edited_at: DateTime<Utc>,
+}
+
+impl Edit {
+    pub fn new(base: &Thought, title: Option<&str>, body: &str, public: bool) -> Edit {
+        Edit {
+            id: base.id,
+            title: title.filter(|title| !title.is_empty()).map(str::to_string),
+            body: body.to_string(),
+            public,
+            base: base.clone(),
+            edited_at: Utc::now(),
+        }
+    }
+
+    /// `thought` with this edit applied, as it will look once pushed.
+    pub fn apply(&self, thought: &Thought) -> Thought {
+        Thought {
+            title: self.title.clone(),
+            body: self.body.clone(),
+            public: self.public,
+            date_updated: self.edited_at,
+            ..thought.clone()
+        }
+    }
+
+    // The thought already says what this edit says
+    fn matches(&self, thought: &Thought) -> bool {
+        self.title == thought.title && self.body == thought.body && self.public == thought.public
+    }
+}
+
+///
+/// A thought that was changed both here and on the server.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Conflict {
+    /// Ours, including the common base
+    pub edit: Edit,
+    /// Theirs
+    pub remote: Thought,
+}
+
+///
+/// How to settle a conflict.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolution {
+    KeepMine,
+    KeepTheirs,
+    Merged { title: Option<String>, body: String },
+}
+
+impl Conflict {
+    pub fn base(&self) -> &Thought {
+        &self.edit.base
+    }
+
+    ///
+    /// Three-way merge of the title and body. Where both sides changed the
+    /// same lines, the body has conflict markers and `conflicted` is set.
+    ///
+    pub fn merge(&self) -> (Option<String>, Merge) {
+        let base = self.base();
+        let text = |title: &Option<String>| title.clone().unwrap_or_default();
+
+        // A title is one line: take whichever side changed it, mine if both
+        let title = if self.remote.title != base.title && self.edit.title == base.title {
+            self.remote.title.clone()
+        } else {
+            self.edit.title.clone()
+        };
+        let mut merge = merge3(&base.body, &self.edit.body, &self.remote.body);
+        if self.remote.title != base.title
+            && self.edit.title != base.title
+            && text(&self.remote.title) != text(&self.edit.title)
+        {
+            merge.conflicted = true;
+        }
+
+        (title, merge)
+    }
+
+    ///
+    /// Settle the conflict. Returns the edit to push, rebased onto the
+    /// server's version, or `None` if the server's version wins.
+    ///
+    pub fn resolve(self, resolution: Resolution) -> Option<Edit> {
+        let (title, body) = match resolution {
+            Resolution::KeepTheirs => return None,
+            Resolution::KeepMine => (self.edit.title.clone(), self.edit.body.clone()),
+            Resolution::Merged { title, body } => (title, body),
+        };
+
+        Some(Edit::new(
+            &self.remote,
+            title.as_deref(),
+            &body,
+            self.edit.public,
+        ))
+    }
+}
+
+///
+/// Everything sync needs to remember between runs.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SyncState {
+    pub last_sync: Option<DateTime<Utc>>,
+    pub edits: Vec<Edit>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl SyncState {
+    /// Anything to push or resolve.
+    pub fn is_dirty(&self) -> bool {
+        !self.edits.is_empty() || !self.conflicts.is_empty()
+    }
+}
+
+///
+/// What `reconcile` decided to do with each edit.
+///
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    pub push: Vec<Edit>,
+    pub conflicts: Vec<Conflict>,
+    /// Edits the server already has
+    pub settled: Vec<Edit>,
+}
+
+///
+/// Compare local edits with the server's thoughts.
+///
+/// Edits for thoughts that aren't in `remote` are pushed, and the server
+/// decides.
+///
+pub fn reconcile(remote: &[Thought], edits: Vec<Edit>) -> Plan {
+    let mut plan = Plan::default();
+
+    for mut edit in edits {
+        let current = match remote.iter().find(|thought| thought.id == edit.id) {
+            Some(current) => current,
+            None => {
+                plan.push.push(edit);
+                continue;
+            }
+        };
+
+        if edit.matches(current) {
+            plan.settled.push(edit);
+        } else if current.revision == edit.base.revision {
+            plan.push.push(edit);
+        } else if current.title == edit.base.title
+            && current.body == edit.base.body
+            && current.public == edit.base.public
+        {
+            // A new revision with the same text, nothing to merge
+            edit.base = current.clone();
+            plan.push.push(edit);
+        } else {
+            plan.conflicts.push(Conflict {
+                edit,
+                remote: current.clone(),
+            });
+        }
+    }
+
+    plan
+}
+
+///
+/// The result of a sync run.
+///
+#[derive(Debug)]
+pub struct SyncOutcome {
+    /// The server's thoughts, including anything just pushed
+    pub thoughts: Vec<Thought>,
+    /// Edits that are on the server now
+    pub settled: Vec<Edit>,
+    /// Newly found conflicts
+    pub conflicts: Vec<Conflict>,
+    /// Edits that couldn't be pushed, and why. They stay in the state and
+    /// are tried again next time.
+    pub errors: Vec<(Edit, ApiError)>,
+    pub synced_at: DateTime<Utc>,
+}
+
+impl SyncOutcome {
+    ///
+    /// Fold the outcome into `state`. Edits made while the sync was running
+    /// aren't in the outcome and are kept.
+    ///
+    pub fn apply_to(&self, state: &mut SyncState) {
+        let done = |edit: &Edit| {
+            self.settled.contains(edit)
+                || self.conflicts.iter().any(|conflict| &conflict.edit == edit)
+                || self
+                    .errors
+                    .iter()
+                    .any(|(failed, e)| failed == edit && e.status() == Some(404))
+        };
+        state.edits.retain(|edit| !done(edit));
+        state.conflicts.extend(self.conflicts.iter().cloned());
+        state.last_sync = Some(self.synced_at);
+    }
+}
+
+///
+/// Pull the user's thoughts, push local edits that don't conflict, and
+/// report conflicts.
+///
+/// Fails only if the pull fails; errors pushing single edits are in the
+/// outcome. Edits for thoughts deleted on the server (404) are dropped.
+///
+pub fn run(client: &Client, username: &str, state: &SyncState) -> ApiResult<SyncOutcome> {
+    // Pull all of them, so every edit has its thought to compare with
+    let mut thoughts = client.get_all_user_thoughts(username)?;
+
+    // Decide, then push
+    let plan = reconcile(&thoughts, state.edits.clone());
+    let mut settled = plan.settled;
+    let mut conflicts = plan.conflicts;
+    let mut errors = Vec::new();
+
+    for edit in plan.push {
+        let result = client.update_thought(
+            &edit.id,
+            edit.title.as_deref(),
+            &edit.body,
+            edit.public,
+            edit.base.revision,
+        );
+
+        match result {
+            Ok(updated) => {
+                match thoughts.iter_mut().find(|thought| thought.id == updated.id) {
+                    Some(thought) => *thought = updated,
+                    None => thoughts.push(updated),
+                }
+                settled.push(edit);
+            }
+            // Changed on the server between our pull and push
+            Err(ApiError::Status { status: 409, .. }) => {
+                match thoughts.iter().find(|thought| thought.id == edit.id) {
+                    Some(remote) => conflicts.push(Conflict {
+                        remote: remote.clone(),
+                        edit,
+                    }),
+                    None => errors.push((
+                        edit,
+                        ApiError::Status {
+                            status: 409,
+                            message: "changed on the server".to_string(),
+                        },
+                    )),
+                }
+            }
+            Err(e) => errors.push((edit, e)),
+        }
+    }
+
+    Ok(SyncOutcome {
+        thoughts,
+        settled,
+        conflicts,
+        errors,
+        synced_at: Utc::now(),
+    })
+}
+
+///
+/// Show pending edits on top of the server's thoughts.
+///
+pub fn apply_edits(thoughts: &mut [Thought], edits: &[Edit]) {
+    for thought in thoughts.iter_mut() {
+        if let Some(edit) = edits.iter().rev().find(|edit| edit.id == thought.id) {
+            *thought = edit.apply(thought);
+        }
+    }
+}
+
+///
+/// One line of a diff.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+///
+/// Line diff from `old` to `new`.
+///
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old = old.lines().collect::<Vec<&str>>();
+    let new = new.lines().collect::<Vec<&str>>();
+    let matches = matching(&old, &new);
+
+    let mut diff = Vec::new();
+    let mut j = 0;
+    for (i, line) in old.iter().enumerate() {
+        match matches[i] {
+            Some(m) => {
+                diff.extend(new[j..m].iter().map(|line| DiffLine::Added(line)));
+                diff.push(DiffLine::Same(line));
+                j = m + 1;
+            }
+            None => diff.push(DiffLine::Removed(line)),
+        }
+    }
+    diff.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+
+    diff
+}
+
+///
+/// A three-way merge.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    pub text: String,
+    /// Both sides changed the same lines; `text` has conflict markers there
+    pub conflicted: bool,
+}
+
+// Conflict markers, as git writes them
+const MINE_MARKER: &str = "<<<<<<< mine";
+const SPLIT_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>> theirs";
+
+///
+/// Merge `mine` and `theirs`, both changed from `base`, line by line.
+///
+/// Changes on one side only are taken as they are. Where both sides changed
+/// the same region differently, both versions are kept between git-style
+/// conflict markers.
+///
+pub fn merge3(base: &str, mine: &str, theirs: &str) -> Merge {
+    // The final newline merges like a line of its own
+    let newline = |text: &str| text.ends_with('\n');
+    let trailing = match newline(mine) == newline(base) {
+        true => newline(theirs),
+        false => newline(mine),
+    };
+
+    let base = base.lines().collect::<Vec<&str>>();
+    let mine = mine.lines().collect::<Vec<&str>>();
+    let theirs = theirs.lines().collect::<Vec<&str>>();
+    let to_mine = matching(&base, &mine);
+    let to_theirs = matching(&base, &theirs);
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut conflicted = false;
+    let (mut i, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        // Next base line that's unchanged on both sides
+        let stable = (i..base.len()).find(|&k| to_mine[k].is_some() && to_theirs[k].is_some());
+        let (end, mine_end, theirs_end) = match stable {
+            Some(k) => (
+                k,
+                to_mine[k].unwrap_or(mine.len()),
+                to_theirs[k].unwrap_or(theirs.len()),
+            ),
+            None => (base.len(), mine.len(), theirs.len()),
+        };
+
+        // The changed region before it
+        let old = &base[i..end];
+        let ours = &mine[a..mine_end];
+        let their = &theirs[b..theirs_end];
+        if ours == old {
+            out.extend(their);
+        } else if their == old || ours == their {
+            out.extend(ours);
+        } else {
+            conflicted = true;
+            out.push(MINE_MARKER);
+            out.extend(ours);
+            out.push(SPLIT_MARKER);
+            out.extend(their);
+            out.push(THEIRS_MARKER);
+        }
+
+        match stable {
+            Some(k) => {
+                out.push(base[k]);
+                i = k + 1;
+                a = mine_end + 1;
+                b = theirs_end + 1;
+            }
+            None => break,
+        }
+    }
+
+    let mut text = out.join("\n");
+    if trailing {
+        text.push('\n');
+    }
+    Merge { text, conflicted }
+}
+
+// For each line of `old`, its index in `new` if it's part of the longest
+// common subsequence
+fn matching(old: &[&str], new: &[&str]) -> Vec<Option<usize>> {
+    // lcs[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; old.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_thought;
+
+    // The thought as the server has it after someone else edited it
+    fn bumped(thought: &Thought, body: &str) -> Thought {
+        Thought {
+            body: body.to_string(),
+            revision: thought.revision + 1,
+            ..thought.clone()
+        }
+    }
+
+    #[test]
+    fn merge_takes_changes_from_both_sides() {
+        let merge = merge3("a\nb\nc\nd", "A\nb\nc\nd", "a\nb\nc\nD\ne");
+        assert_eq!(merge.text, "A\nb\nc\nD\ne");
+        assert!(!merge.conflicted);
+
+        // The same change on both sides isn't a conflict
+        let merge = merge3("a\nb", "a\nB", "a\nB");
+        assert_eq!(merge.text, "a\nB");
+        assert!(!merge.conflicted);
+    }
+
+    #[test]
+    fn merge_marks_clashing_changes() {
+        let merge = merge3("a\nb\nc", "a\nmine\nc", "a\ntheirs\nc");
+        assert!(merge.conflicted);
+        assert_eq!(
+            merge.text,
+            "a\n<<<<<<< mine\nmine\n=======\ntheirs\n>>>>>>> theirs\nc"
+        );
+    }
+
+    #[test]
+    fn merge_keeps_the_final_newline() {
+        assert_eq!(merge3("a\nb\n", "A\nb\n", "a\nb\n").text, "A\nb\n");
+        // Added or dropped on one side only, like any other change
+        assert_eq!(merge3("a", "a\n", "A").text, "A\n");
+        assert_eq!(merge3("a\n", "a\n", "A").text, "A");
+    }
+
+    #[test]
+    fn diff_shows_removed_then_added() {
+        assert_eq!(
+            diff_lines("a\nb\nc", "a\nB\nc"),
+            [
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("B"),
+                DiffLine::Same("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reconcile_sorts_edits() {
+        let unchanged = test_thought(1, "", "one");
+        let settled = test_thought(2, "", "two");
+        let retitled = test_thought(3, "Old", "three");
+        let clashing = test_thought(4, "", "four");
+        let gone = test_thought(5, "", "five");
+
+        let same_text = Thought {
+            revision: 1,
+            ..retitled.clone()
+        };
+        let remote = [
+            unchanged.clone(),
+            bumped(&settled, "two, edited"),
+            same_text.clone(),
+            bumped(&clashing, "four, theirs"),
+        ];
+        let plan = reconcile(
+            &remote,
+            vec![
+                Edit::new(&unchanged, None, "one, edited", true),
+                Edit::new(&settled, None, "two, edited", true),
+                Edit::new(&retitled, Some("New"), "three", true),
+                Edit::new(&clashing, None, "four, mine", true),
+                Edit::new(&gone, None, "five, edited", true),
+            ],
+        );
+
+        let ids = |edits: &[Edit]| edits.iter().map(|edit| edit.id).collect::<Vec<_>>();
+        assert_eq!(ids(&plan.push), [unchanged.id, retitled.id, gone.id]);
+        assert_eq!(ids(&plan.settled), [settled.id]);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].remote.body, "four, theirs");
+
+        // Pushed on top of the server's new revision
+        assert_eq!(plan.push[1].base, same_text);
+    }
+
+    #[test]
+    fn resolving_rebases_onto_theirs() {
+        let base = test_thought(1, "", "a\nb\nc");
+        let conflict = Conflict {
+            edit: Edit::new(&base, None, "A\nb\nc", true),
+            remote: bumped(&base, "a\nb\nC"),
+        };
+
+        let (title, merge) = conflict.merge();
+        assert_eq!(title, None);
+        assert_eq!(merge.text, "A\nb\nC");
+
+        let edit = conflict
+            .clone()
+            .resolve(Resolution::Merged {
+                title,
+                body: merge.text,
+            })
+            .unwrap();
+        assert_eq!(edit.base, conflict.remote);
+        assert_eq!(edit.body, "A\nb\nC");
+        assert_eq!(conflict.resolve(Resolution::KeepTheirs), None);
+    }
+}
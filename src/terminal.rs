@@ -0,0 +1,121 @@
+// Terminal setup and teardown
+//
+// Everything that puts the terminal into raw mode / the alternate screen goes
+// through here, so there is exactly one place that undoes it.
+use std::io::{self, stdout, Stdout};
+
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use ratatui::prelude::*;
+
+///
+/// Puts the terminal into raw mode and the alternate screen, and restores it
+/// when dropped.
+///
+/// Dropping happens on every exit path out of `main`, including early returns
+/// and `?`, so the shell is never left in raw mode.
+///
+pub struct TerminalGuard {
+    pub terminal: Terminal<CrosstermBackend<Stdout>>,
+    // Mouse events come to us rather than the terminal
+    mouse: bool,
+}
+
+impl TerminalGuard {
+    pub fn new(mouse: bool) -> io::Result<TerminalGuard> {
+        enter(mouse)?;
+        let terminal = match Terminal::new(CrosstermBackend::new(stdout())) {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                let _ = restore();
+                return Err(e);
+            }
+        };
+
+        Ok(TerminalGuard { terminal, mouse })
+    }
+
+    ///
+    /// Suspend to the shell (Ctrl-Z).
+    ///
+    /// Restores the terminal, stops the process with SIGTSTP, and sets the
+    /// terminal up again once the shell resumes us with `fg`.
+    ///
+    pub fn suspend(&mut self) -> io::Result<()> {
+        restore()?;
+
+        #[cfg(unix)]
+        unsafe {
+            // Returns once we receive SIGCONT
+            libc::raise(libc::SIGTSTP);
+        }
+
+        enter(self.mouse)?;
+
+        // Whatever the shell printed is still in the buffer ratatui diffs
+        // against, so force a full redraw
+        self.terminal.clear()
+    }
+
+    ///
+    /// Run something that needs the normal terminal, like an editor, and
+    /// set the TUI up again afterwards.
+    ///
+    pub fn run_outside<T, F: FnOnce() -> T>(&mut self, f: F) -> io::Result<T> {
+        restore()?;
+        let result = f();
+        enter(self.mouse)?;
+        self.terminal.clear()?;
+        Ok(result)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+// Raw mode + alternate screen, and the mouse if we want it
+fn enter(mouse: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    if mouse {
+        stdout().execute(EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+///
+/// Put the terminal back the way we found it.
+///
+/// Safe to call more than once. Every step is tried even if an earlier one
+/// fails, and the first error is returned.
+///
+pub fn restore() -> io::Result<()> {
+    let steps = [
+        disable_raw_mode(),
+        stdout().execute(DisableMouseCapture).map(drop),
+        stdout().execute(LeaveAlternateScreen).map(drop),
+        stdout().execute(Show).map(drop),
+    ];
+    steps.into_iter().collect()
+}
+
+///
+/// Restore the terminal before a panic message is printed.
+///
+/// Without this the message is written to the alternate screen in raw mode,
+/// and disappears (or comes out as a staircase) when the process exits.
+///
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,109 @@
+// `comode think`: post a thought without the TUI
+//
+//   comode think some words        post "some words"
+//   comode think --edit            write it in $EDITOR first
+//   comode think --private ...     post it privately (or --public, if
+//                                  private is the default)
+//
+// If the server can't be reached the thought goes into the outbox, and the
+// TUI sends it next time it starts.
+use comode::client::blocking::Client;
+use comode::editor::{self, Document};
+use comode::outbox::OutboxItem;
+use comode::store::Store;
+use comode::{auth, types::User};
+
+use crate::colors::ComindColors;
+use crate::config::Account;
+use crate::display::co_say;
+
+pub fn think(account: &Account, default_public: bool, args: &[String], colors: &ComindColors) {
+    // Flags, then the text
+    let mut edit = false;
+    let mut public = default_public;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--edit" | "-e" => edit = true,
+            "--private" | "-p" => public = false,
+            "--public" => public = true,
+            _ => words.push(arg.as_str()),
+        }
+    }
+
+    let user = match auth::load_user(&account.profile) {
+        Ok(user) => user,
+        Err(_) => {
+            co_say("you're not logged in, run `comode login` first", colors);
+            return;
+        }
+    };
+
+    // Anything on the command line is a starting point for the editor
+    let mut document = Document::new("", &words.join(" "), public);
+    if edit {
+        document = match editor::edit(&document) {
+            Ok(Some(document)) => document,
+            Ok(None) => {
+                co_say("nothing written, cancelled", colors);
+                return;
+            }
+            Err(e) => {
+                co_say(&format!("editor: {}", e), colors);
+                return;
+            }
+        };
+    }
+    if document.body.trim().is_empty() {
+        co_say(
+            "what are you thinking? usage: comode think [--private] [--edit] [text]",
+            colors,
+        );
+        return;
+    }
+
+    send(account, &user, &document, colors);
+}
+
+// Post it, or queue it if that fails for a reason that might go away
+fn send(account: &Account, user: &User, document: &Document, colors: &ComindColors) {
+    let client = Client::for_user(&account.server, user);
+    let error = match client.make_new_thought(
+        &user.user_id,
+        &document.title,
+        &document.body,
+        document.public,
+    ) {
+        Ok(()) => {
+            co_say("thought sent", colors);
+            return;
+        }
+        Err(e) => e,
+    };
+
+    if !error.is_transient() {
+        co_say(&format!("couldn't send your thought: {}", error), colors);
+        return;
+    }
+
+    let mut item = OutboxItem::new(&document.title, &document.body, document.public);
+    item.record_failure(&error);
+    let queued = Store::open(&account.server, &user.username).and_then(|store| {
+        let mut outbox = store.load_outbox()?;
+        outbox.push(item);
+        store.save_outbox(&outbox)
+    });
+    match queued {
+        Ok(()) => co_say(
+            &format!(
+                "couldn't send it ({}), it'll go out next time you open comode",
+                error
+            ),
+            colors,
+        ),
+        Err(e) => co_say(
+            &format!("couldn't send your thought ({}) or save it ({})", error, e),
+            colors,
+        ),
+    }
+}
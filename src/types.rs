@@ -1,15 +1,163 @@
-use crate::errors;
+use crate::auth::credential_backend;
 use crate::errors::AuthResult;
 use crate::errors::AuthenticationError;
-use colored::{Colorize, CustomColor};
+use crate::utils::datetime_to_relative;
+use chrono::{DateTime, Utc};
 use jwt::{Claims, Header, Token};
 use keyring::Entry;
-use serde::{Deserialize, Serialize};
-use serde_json::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use std::io::{stdin, stdout, Write};
+use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
+///
+/// A thought's id.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ThoughtId(pub Uuid);
+
+///
+/// A user's id.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct UserId(pub Uuid);
+
+// Display and parsing for the id newtypes, same as the wrapped Uuid
+macro_rules! uuid_newtype {
+    ($name:ident) => {
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = uuid::Error;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                Ok($name(Uuid::parse_str(s)?))
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(uuid: Uuid) -> Self {
+                $name(uuid)
+            }
+        }
+    };
+}
+
+uuid_newtype!(ThoughtId);
+uuid_newtype!(UserId);
+
+// String enums from the server. Values we don't know about are kept in
+// `Unknown` rather than failing the whole response.
+macro_rules! string_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    other => $name::Unknown(other.to_string()),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok($name::from(value.as_str()))
+            }
+        }
+    };
+}
+
+string_enum! {
+    ///
+    /// Where a thought was written.
+    ///
+    Origin {
+        App => "app",
+        Web => "web",
+        Cli => "cli",
+        Api => "api",
+        Import => "import",
+    }
+}
+
+string_enum! {
+    ///
+    /// What a ping is about.
+    ///
+    PingType {
+        Link => "link",
+        Mention => "mention",
+        Follow => "follow",
+    }
+}
+
+///
+/// Serde adapter for the server's timestamps.
+///
+/// The server sends UTC times without a timezone, e.g.
+/// `2024-03-19T15:18:54.058`. This also accepts RFC 3339 times with an
+/// offset, and writes the server's format back out.
+///
+pub mod lenient_datetime {
+    use chrono::{DateTime, Utc};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::utils::iso8601_to_datetime;
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.format("%Y-%m-%dT%H:%M:%S%.3f").to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        match iso8601_to_datetime(&value) {
+            Some(time) => Ok(time),
+            None => Err(D::Error::custom(format!("invalid timestamp: {}", value))),
+        }
+    }
+}
+
 /// # Example
 ///
 /// ```json
@@ -30,125 +178,52 @@ use uuid::Uuid;
 ///        "numlinks": 1
 /// }]
 /// ```
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub struct Thought {
     pub title: Option<String>,
     pub body: String,
-    pub date_created: String,
-    pub date_updated: String,
+    #[serde(with = "lenient_datetime")]
+    pub date_created: DateTime<Utc>,
+    #[serde(with = "lenient_datetime")]
+    pub date_updated: DateTime<Utc>,
     pub revision: i32,
-    pub id: String,
+    pub id: ThoughtId,
     pub public: bool,
     pub synthetic: bool,
-    pub origin: String,
-    pub user_id: String,
+    pub origin: Origin,
+    pub user_id: UserId,
     pub username: String,
-    pub to_id: Option<String>,
+    pub to_id: Option<ThoughtId>,
     pub n_links: Option<i32>,
     pub numlinks: Option<i32>,
 }
-impl Thought {
-    pub(crate) fn clone(&self) -> Thought {
-        Thought {
-            title: self.title.clone(),
-            body: self.body.clone(),
-            date_created: self.date_created.clone(),
-            date_updated: self.date_updated.clone(),
-            revision: self.revision,
-            id: self.id.clone(),
-            public: self.public,
-            synthetic: self.synthetic,
-            origin: self.origin.clone(),
-            user_id: self.user_id.clone(),
-            username: self.username.clone(),
-            to_id: self.to_id.clone(),
-            n_links: self.n_links.clone(),
-            numlinks: self.numlinks.clone(),
-        }
-    }
-}
-
-///
-/// Load a thought from a JSON string
-///
-pub fn load_thought(json: &str) -> Result<Thought> {
-    match serde_json::from_str(json) {
-        Ok(thought) => Ok(thought),
-        Err(e) => {
-            println!("Error deserializing thought: {}", e);
-            println!("{}", json);
-            Err(e)
-        }
-    }
-}
 
 ///
 /// Pings
 ///
 /// Notifications are sent to the user when a thought is linked to their thought
 ///
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub struct Ping {
     pub id: u32,
-    pub r#type: String,
+    pub r#type: PingType,
     pub message: String,
-    pub created_at: String,
+    #[serde(with = "lenient_datetime")]
+    pub created_at: DateTime<Utc>,
     pub read_status: bool,
-    pub user_thought_id: String,
-    pub linking_thought_id: String,
-    pub linking_user_id: String,
-    pub user_id: String,
+    pub user_thought_id: ThoughtId,
+    pub linking_thought_id: ThoughtId,
+    pub linking_user_id: UserId,
+    pub user_id: UserId,
 }
 
-///
-/// Load a ping from a JSON string
-///
-/// # Example
-///
-/// ```json
-/// {
-///    "id": 1,
-///   "type": "link",
-/// "message": "thought linked",
-/// "created_at": "2024-01-05T13:18:21.26",
-/// "read_status": false,
-/// "user_thought_id": "139f78be-c4e8-5f17-8d60-06162fbed802",
-/// "linking_thought_id": "139f78be-c4e8-5f17-8d60-06162fbed802",
-/// "linking_user_id": "6b129b9f-b958-4cf0-a6ad-3108d221177a",
-/// "user_id": "6b129b9f-b958-4cf0-a6ad-3108d221177a"
-/// }
-/// ```
-///
-pub fn load_ping(json: &str) -> Result<Ping> {
-    match serde_json::from_str(json) {
-        Ok(ping) => Ok(ping),
-        Err(e) => {
-            println!("Error deserializing ping: {}", e);
-            println!("{}", json);
-            Err(e)
-        }
-    }
-}
-
-pub(crate) struct User {
+#[derive(Clone)]
+pub struct User {
     pub token: String,
-    pub user_id: String,
+    pub user_id: UserId,
     pub username: String,
-}
-
-// Create a dummy user
-fn create_blank_user() -> User {
-    User {
-        token: String::new(),
-        user_id: String::new(),
-        username: String::new(),
-    }
-}
-
-struct LoginResponse {
-    token: String,
-    message: String,
-    success: bool,
+    pub issued_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl User {
@@ -162,8 +237,15 @@ impl User {
             Err(_) => return Err(AuthenticationError::TokenNotFound),
         };
 
+        User::from_login_response(&token)
+    }
+
+    ///
+    /// Decode the server's login response, `{"token": "<jwt>", ...}`.
+    ///
+    pub fn from_login_response(response: &str) -> AuthResult<User> {
         // The token is a string, so we need to parse it into JSON
-        let token: Value = match serde_json::from_str(&token) {
+        let token: Value = match serde_json::from_str(response) {
             Ok(token) => token,
             Err(_) => return Err(AuthenticationError::JsonParsingError),
         };
@@ -174,8 +256,20 @@ impl User {
             None => return Err(AuthenticationError::TokenNotFound),
         };
 
+        User::from_token(&token)
+    }
+
+    ///
+    /// Decode a JWT into a user, checking that it hasn't expired.
+    ///
+    /// The signature isn't verified, the server does that.
+    ///
+    pub fn from_token(token: &str) -> AuthResult<User> {
         // Now the token is a string, we need to decode the JWT
-        let unverified: Token<Header, Claims, _> = Token::parse_unverified(&token).unwrap();
+        let unverified: Token<Header, Claims, _> = match Token::parse_unverified(token) {
+            Ok(unverified) => unverified,
+            Err(_) => return Err(AuthenticationError::JsonParsingError),
+        };
         let issuer = unverified.claims();
 
         // Get expuration from issuer.registered
@@ -185,7 +279,7 @@ impl User {
         };
 
         // Get the current time
-        let now: u64 = chrono::Utc::now().timestamp().try_into().unwrap();
+        let now: u64 = chrono::Utc::now().timestamp().try_into().unwrap_or(0);
 
         // If the token is expired, return a TokenExpired error
         if now > exp {
@@ -193,9 +287,9 @@ impl User {
         }
 
         // Extract the user_id from the private field
-        let user_id = match issuer.private["user_id"].as_str() {
-            Some(private) => private.to_string(),
-            None => return Err(AuthenticationError::UserIdNotFound),
+        let user_id = match issuer.private["user_id"].as_str().map(UserId::from_str) {
+            Some(Ok(user_id)) => user_id,
+            _ => return Err(AuthenticationError::UserIdNotFound),
         };
 
         // Extract the username
@@ -204,11 +298,145 @@ impl User {
             None => return Err(AuthenticationError::UsernameNotFound),
         };
 
+        // Token issue and expiry times, for the whoami view
+        let issued_at = issuer
+            .registered
+            .issued_at
+            .and_then(|iat| chrono::DateTime::from_timestamp(iat as i64, 0));
+        let expires_at = match chrono::DateTime::from_timestamp(exp as i64, 0) {
+            Some(expires_at) => expires_at,
+            None => return Err(AuthenticationError::TokenExpired),
+        };
+
         // Return a blank user, DEBUG
-        return Ok(User {
-            token: token,
-            user_id: user_id,
-            username: username,
-        });
+        Ok(User {
+            token: token.to_string(),
+            user_id,
+            username,
+            issued_at,
+            expires_at,
+        })
+    }
+
+    ///
+    /// Session information shown by `comode whoami` and the whoami view.
+    ///
+    /// Returns (label, value) pairs taken from the decoded token claims.
+    ///
+    pub fn session_info(&self, server: &str) -> Vec<(&'static str, String)> {
+        let format_time = |time: &chrono::DateTime<chrono::Utc>| {
+            format!(
+                "{} ({})",
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                datetime_to_relative(time)
+            )
+        };
+
+        let issued_at = match &self.issued_at {
+            Some(issued_at) => format_time(issued_at),
+            None => "unknown".to_string(),
+        };
+
+        vec![
+            ("username", self.username.clone()),
+            ("user_id", self.user_id.to_string()),
+            ("server", server.to_string()),
+            ("issued", issued_at),
+            ("expires", format_time(&self.expires_at)),
+            ("credentials", credential_backend().to_string()),
+        ]
+    }
+}
+
+///
+/// A thought for tests: `id` as a UUID, a fixed date, and no links.
+///
+#[doc(hidden)]
+pub fn test_thought(id: u128, title: &str, body: &str) -> Thought {
+    let date = DateTime::parse_from_rfc3339("2024-01-05T13:18:21Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    Thought {
+        title: Some(title.to_string()).filter(|title| !title.is_empty()),
+        body: body.to_string(),
+        date_created: date,
+        date_updated: date,
+        revision: 0,
+        id: ThoughtId(Uuid::from_u128(id)),
+        public: true,
+        synthetic: false,
+        origin: Origin::App,
+        user_id: UserId(Uuid::from_u128(0)),
+        username: "me".to_string(),
+        to_id: None,
+        n_links: None,
+        numlinks: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THOUGHT: &str = r#"{
+        "title": null,
+        "body": "hello",
+        "date_created": "2024-01-05T13:18:21.26",
+        "date_updated": "2024-03-14T16:36:44.764+02:00",
+        "revision": 0,
+        "id": "139f78be-c4e8-5f17-8d60-06162fbed802",
+        "public": true,
+        "synthetic": false,
+        "origin": "telepathy",
+        "user_id": "6b129b9f-b958-4cf0-a6ad-3108d221177a",
+        "username": "cameron",
+        "to_id": null,
+        "n_links": null,
+        "numlinks": null
+    }"#;
+
+    #[test]
+    fn timestamps_with_and_without_an_offset() {
+        let thought: Thought = serde_json::from_str(THOUGHT).unwrap();
+        assert_eq!(
+            thought.date_created.to_rfc3339(),
+            "2024-01-05T13:18:21.260+00:00"
+        );
+        assert_eq!(
+            thought.date_updated.to_rfc3339(),
+            "2024-03-14T14:36:44.764+00:00"
+        );
+
+        // Written back in the server's format
+        let json = serde_json::to_value(&thought).unwrap();
+        assert_eq!(json["date_created"], "2024-01-05T13:18:21.260");
+        assert_eq!(json["date_updated"], "2024-03-14T14:36:44.764");
+    }
+
+    #[test]
+    fn bad_timestamps_are_an_error() {
+        let json = THOUGHT.replace("2024-01-05T13:18:21.26", "yesterday");
+        let error = serde_json::from_str::<Thought>(&json).unwrap_err();
+        assert!(error.to_string().contains("invalid timestamp: yesterday"));
+    }
+
+    #[test]
+    fn unknown_enum_values_are_kept() {
+        let thought: Thought = serde_json::from_str(THOUGHT).unwrap();
+        assert_eq!(thought.origin, Origin::Unknown("telepathy".to_string()));
+
+        let json = serde_json::to_value(&thought).unwrap();
+        assert_eq!(json["origin"], "telepathy");
+    }
+
+    #[test]
+    fn known_enum_values_round_trip() {
+        for value in ["link", "mention", "follow"] {
+            let ping_type = PingType::from(value);
+            assert!(!matches!(ping_type, PingType::Unknown(_)));
+            assert_eq!(ping_type.to_string(), value);
+        }
+        assert_eq!(Origin::from("cli"), Origin::Cli);
     }
 }
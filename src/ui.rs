@@ -1,36 +1,39 @@
-use colored::{Colorize, CustomColor};
-
-use crate::colors::ComindColors;
-
 // enums + convenience stuff for the repl
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum UIMode {
     ThinkPublic,
     ThinkPrivate,
-    ThoughtView,
     Thoughts,
     Stream,
+    Following,
     Pings,
     Search,
+    Conflicts,
+    Drafts,
+    Profile,
 }
 
-// REPL mode to prompt string
 impl UIMode {
-    pub fn prompt(&self, colors: &ComindColors) -> String {
+    /// The mode's name, for the status bar.
+    pub fn label(&self) -> &'static str {
         match self {
-            UIMode::ThinkPublic => format!("{}", "[think]".bold().custom_color(colors.primary())),
-            UIMode::ThinkPrivate => {
-                format!("{}", "[think 🔒]".bold().custom_color(colors.primary()))
-            }
-            UIMode::Thoughts => format!("{}", "[thoughts]".bold().custom_color(colors.secondary())),
-            UIMode::Stream => format!("{}", "[stream]".bold().custom_color(colors.tertiary())),
-            UIMode::Pings => format!("{}", "[pings]".bold().custom_color(colors.tertiary())),
-            UIMode::Search => format!("{}", "[search]".bold().custom_color(colors.secondary())),
-            UIMode::ThoughtView => {
-                format!("{}", "[thought view]".bold().custom_color(colors.primary()))
-            }
+            UIMode::ThinkPublic => "think",
+            UIMode::ThinkPrivate => "think 🔒",
+            UIMode::Thoughts => "thoughts",
+            UIMode::Stream => "stream",
+            UIMode::Following => "following",
+            UIMode::Pings => "pings",
+            UIMode::Search => "search",
+            UIMode::Conflicts => "conflicts",
+            UIMode::Drafts => "drafts",
+            UIMode::Profile => "profile",
         }
     }
+
+    /// Writing a new thought
+    pub fn is_compose(&self) -> bool {
+        matches!(self, UIMode::ThinkPublic | UIMode::ThinkPrivate)
+    }
 }
 ///
 /// A vector of modes to put on the tabs
@@ -41,6 +44,7 @@ pub fn modes() -> Vec<UIMode> {
         UIMode::Pings,
         UIMode::Search,
         UIMode::Stream,
+        UIMode::Following,
     ]
 }
 
@@ -53,37 +57,28 @@ pub fn mode_strings() -> Vec<String> {
         "Pings".to_string(),
         "Search".to_string(),
         "Stream".to_string(),
+        "Following".to_string(),
     ]
 }
 
 /// Return the next/previous mode.
 ///
-/// The order is as follows:
-/// - ThinkPublic
-/// - Thoughts
-/// - Pings
+/// Cycles through the tabs in the order of `modes()`. Modes that aren't
+/// tabs (composing, viewing a thought) go back to Thoughts.
 pub fn next_mode(mode: UIMode) -> UIMode {
-    match mode {
-        UIMode::ThinkPublic => UIMode::Thoughts,
-        UIMode::ThinkPrivate => UIMode::Thoughts,
-        UIMode::Thoughts => UIMode::Pings,
-        UIMode::Pings => UIMode::ThinkPublic,
-        UIMode::Stream => UIMode::ThinkPublic,
-        UIMode::Search => UIMode::ThinkPublic,
-        UIMode::ThoughtView => UIMode::ThinkPublic,
+    let modes = modes();
+    match modes.iter().position(|m| *m == mode) {
+        Some(i) => modes[(i + 1) % modes.len()],
+        None => UIMode::Thoughts,
     }
 }
 
 /// Return the previous mode.
 ///
 pub fn prev_mode(mode: UIMode) -> UIMode {
-    match mode {
-        UIMode::ThinkPublic => UIMode::Pings,
-        UIMode::ThinkPrivate => UIMode::Pings,
-        UIMode::Thoughts => UIMode::ThinkPublic,
-        UIMode::Pings => UIMode::Thoughts,
-        UIMode::Stream => UIMode::Thoughts,
-        UIMode::Search => UIMode::Thoughts,
-        UIMode::ThoughtView => UIMode::Thoughts,
+    let modes = modes();
+    match modes.iter().position(|m| *m == mode) {
+        Some(i) => modes[(i + modes.len() - 1) % modes.len()],
+        None => UIMode::Thoughts,
     }
 }
@@ -1,28 +1,21 @@
-use chrono::{Local, Utc};
+//! Misc utility functions
 
-/// Misc utility functions
-///
-///
-///
+use chrono::Utc;
 
 /// Converts an ISO8601 string timestamp to a chrono::DateTime.
 ///
-/// Assumes that the input string is a valid ISO8601 string, without a timezone.
-/// Examples include 2024-03-19T15:18:54.058
-pub fn iso8601_to_datetime(iso8601: &str) -> chrono::DateTime<chrono::Utc> {
-    // Via stack exchange
-    // let date_str = "2020-04-12";
-    // // From string to a NaiveDate
-    // let naive_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
-    // // Add some default time to convert it into a NaiveDateTime
-    // let naive_datetime: NaiveDateTime = naive_date.and_hms(0,0,0);
-    // // Add a timezone to the object to convert it into a DateTime<UTC>
-    // let datetime_utc = DateTime::<Utc>::from_utc(naive_datetime, Utc);
-
-    let naive = chrono::NaiveDateTime::parse_from_str(iso8601, "%Y-%m-%dT%H:%M:%S%.f").unwrap();
-    let datetime = naive.and_utc();
+/// The server sends UTC times without a timezone, such as
+/// 2024-03-19T15:18:54.058. Times with an offset (RFC 3339) are accepted too.
+/// Returns `None` if the string is neither.
+pub fn iso8601_to_datetime(iso8601: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    // With a timezone
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(iso8601) {
+        return Some(datetime.with_timezone(&Utc));
+    }
 
-    return datetime;
+    // Without one, assume UTC
+    let naive = chrono::NaiveDateTime::parse_from_str(iso8601, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Some(naive.and_utc())
 }
 
 /// Convert a chrono::DateTime to a relative time string,
@@ -32,7 +25,7 @@ pub fn iso8601_to_datetime(iso8601: &str) -> chrono::DateTime<chrono::Utc> {
 ///
 /// ```
 /// use chrono::Utc;
-/// use comind::utils::datetime_to_relative;
+/// use comode::utils::datetime_to_relative;
 ///
 /// let now = Utc::now();
 /// let five_minutes_ago = now - chrono::Duration::minutes(5);
@@ -43,25 +36,73 @@ pub fn datetime_to_relative(datetime: &chrono::DateTime<chrono::Utc>) -> String
     let now = chrono::Utc::now();
     let duration = now - *datetime;
     let duration = duration.num_seconds();
+
+    // Times in the future, e.g. token expiry
+    if duration <= -60 {
+        return format!("in {}", relative_span(-duration));
+    }
+
     if duration < 60 {
         return "just now".to_string();
     }
+    format!("{} ago", relative_span(duration))
+}
+
+/// Format a positive number of seconds as "5 minutes", "2 days", etc.
+fn relative_span(duration: i64) -> String {
     if duration < 3600 {
         let minutes = duration / 60;
-        return format!("{} minutes ago", minutes);
+        return format!("{} minutes", minutes);
     }
     if duration < 86400 {
         let hours = duration / 3600;
-        return format!("{} hours ago", hours);
+        return format!("{} hours", hours);
     }
     if duration < 2592000 {
         let days = duration / 86400;
-        return format!("{} days ago", days);
+        return format!("{} days", days);
     }
     if duration < 31536000 {
         let months = duration / 2592000;
-        return format!("{} months ago", months);
+        return format!("{} months", months);
     }
     let years = duration / 31536000;
-    return format!("{} years ago", years);
+    format!("{} years", years)
+}
+
+/// Directory for comode's data, following the XDG base directory spec:
+/// `$XDG_DATA_HOME/comode`, or `~/.local/share/comode`.
+///
+/// Falls back to `%APPDATA%\comode` on Windows. Returns `None` if no home
+/// directory can be found.
+pub fn data_dir() -> Option<std::path::PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for comode's settings: `$XDG_CONFIG_HOME/comode`, or
+/// `~/.config/comode`.
+pub fn config_dir() -> Option<std::path::PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+// $XDG_* if it's set to an absolute path, else ~/<fallback>, then "comode"
+fn xdg_dir(variable: &str, fallback: &str) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    if let Some(dir) = std::env::var_os(variable) {
+        let dir = PathBuf::from(dir);
+        if dir.is_absolute() {
+            return Some(dir.join("comode"));
+        }
+    }
+
+    if let Some(home) = std::env::var_os("HOME") {
+        return Some(PathBuf::from(home).join(fallback).join("comode"));
+    }
+
+    if let Some(appdata) = std::env::var_os("APPDATA") {
+        return Some(PathBuf::from(appdata).join("comode"));
+    }
+
+    None
 }
@@ -0,0 +1,818 @@
+//! Thoughts as a folder of Markdown notes, for Obsidian and the like.
+//!
+//! Each thought becomes one file, named from its title (or its id if it has
+//! none), with YAML front matter for what the body doesn't say:
+//!
+//! ```text
+//! ---
+//! id: 139f78be-c4e8-5f17-8d60-06162fbed802
+//! title: "Thought ID refresh issue"
+//! created: 2024-01-05T13:18:21.260Z
+//! updated: 2024-03-14T16:36:44.764Z
+//! public: true
+//! synthetic: false
+//! origin: app
+//! revision: 0
+//! links_to: 5d3c0f7e-...
+//! ---
+//! The body, in Markdown.
+//!
+//! → [[The thought it links to]]
+//! ```
+//!
+//! A link to another exported thought ends the file as a `[[wikilink]]`.
+//! The export keeps a manifest of what it wrote, so running it again only
+//! rewrites thoughts that changed.
+//!
+//! Going the other way, `read_notes` reads a folder of Markdown notes (from
+//! an export or another note tool) or a JSON dump of thoughts, for
+//! `comode import`.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{read_json, write_json};
+use crate::types::{Thought, ThoughtId};
+
+/// What the export wrote last time, kept in the export folder.
+pub const MANIFEST: &str = ".comode-export.json";
+
+/// Marks the line with a thought's link, after the body.
+pub const LINK_MARKER: &str = "→ ";
+
+// Longest file name, before the extension
+const MAX_STEM: usize = 100;
+
+///
+/// What an export did.
+///
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    /// Files written, new or changed
+    pub written: usize,
+    /// Files left alone because their thought hadn't changed
+    pub unchanged: usize,
+    /// Files written under a new name, after a title changed
+    pub renamed: usize,
+    /// Files of thoughts that aren't there any more. They're left in place.
+    pub gone: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Manifest {
+    files: BTreeMap<ThoughtId, Exported>,
+}
+
+// One thought's file, as last written
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Exported {
+    file: String,
+    updated: DateTime<Utc>,
+    // Names of the notes it links to, which change when their titles do
+    links: Vec<String>,
+}
+
+///
+/// Write `thoughts` to `dir` as Markdown, one file each. Thoughts whose
+/// `date_updated` (and link targets) haven't changed since the last export
+/// to `dir` are skipped.
+///
+pub fn export(dir: &Path, thoughts: &[Thought]) -> io::Result<ExportReport> {
+    fs::create_dir_all(dir)?;
+    let previous: Manifest = read_json(&dir.join(MANIFEST)).unwrap_or_default();
+    let names = note_names(thoughts);
+
+    let mut report = ExportReport::default();
+    let mut manifest = Manifest::default();
+    let mut stale = Vec::new();
+    for thought in thoughts {
+        let links = thought
+            .to_id
+            .and_then(|to| names.get(&to))
+            .cloned()
+            .into_iter()
+            .collect::<Vec<String>>();
+        let exported = Exported {
+            file: format!("{}.md", names[&thought.id]),
+            updated: thought.date_updated,
+            links,
+        };
+
+        let before = previous.files.get(&thought.id);
+        if before == Some(&exported) && dir.join(&exported.file).exists() {
+            report.unchanged += 1;
+        } else {
+            // A new title means a new file; the old one goes, once every
+            // file is written, as another thought may have taken its name
+            if let Some(before) = before.filter(|before| before.file != exported.file) {
+                stale.push(before.file.clone());
+                report.renamed += 1;
+            }
+            fs::write(
+                dir.join(&exported.file),
+                to_markdown(thought, &exported.links),
+            )?;
+            report.written += 1;
+        }
+        manifest.files.insert(thought.id, exported);
+    }
+
+    // Case-insensitive, for filesystems that are
+    let taken = manifest
+        .files
+        .values()
+        .map(|exported| exported.file.to_lowercase())
+        .collect::<HashSet<String>>();
+    for file in stale {
+        if !taken.contains(&file.to_lowercase()) {
+            let _ = fs::remove_file(dir.join(file));
+        }
+    }
+
+    report.gone = previous
+        .files
+        .iter()
+        .filter(|(id, _)| !manifest.files.contains_key(id))
+        .map(|(_, exported)| exported.file.clone())
+        .collect();
+
+    write_json(&dir.join(MANIFEST), &manifest)?;
+    Ok(report)
+}
+
+///
+/// A thought as a Markdown note: front matter, the body, then wikilinks to
+/// the notes named in `links`.
+///
+pub fn to_markdown(thought: &Thought, links: &[String]) -> String {
+    let date = |date: &DateTime<Utc>| date.to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let mut note = String::from("---\n");
+    note.push_str(&format!("id: {}\n", thought.id));
+    if let Some(title) = thought
+        .title
+        .as_deref()
+        .filter(|title| !title.trim().is_empty())
+    {
+        // A JSON string is a valid YAML one, quotes and all
+        note.push_str(&format!(
+            "title: {}\n",
+            serde_json::Value::from(title.trim())
+        ));
+    }
+    note.push_str(&format!("created: {}\n", date(&thought.date_created)));
+    note.push_str(&format!("updated: {}\n", date(&thought.date_updated)));
+    note.push_str(&format!("public: {}\n", thought.public));
+    note.push_str(&format!("synthetic: {}\n", thought.synthetic));
+    note.push_str(&format!("origin: {}\n", thought.origin));
+    note.push_str(&format!("revision: {}\n", thought.revision));
+    if let Some(to) = thought.to_id {
+        note.push_str(&format!("links_to: {}\n", to));
+    }
+    note.push_str("---\n");
+
+    note.push_str(thought.body.trim_end());
+    note.push('\n');
+    for link in links {
+        note.push_str(&format!("\n{}[[{}]]\n", LINK_MARKER, link));
+    }
+    note
+}
+
+///
+/// File names (without `.md`) for each thought. Titles that more than one
+/// thought share get the start of the id added, so the names don't depend
+/// on which thought comes first.
+///
+pub fn note_names(thoughts: &[Thought]) -> HashMap<ThoughtId, String> {
+    let stems = thoughts
+        .iter()
+        .map(|thought| (thought.id, stem(thought)))
+        .collect::<Vec<(ThoughtId, String)>>();
+
+    // Case-insensitive, for filesystems that are
+    let mut uses = HashMap::new();
+    for (_, stem) in &stems {
+        *uses.entry(stem.to_lowercase()).or_insert(0) += 1;
+    }
+
+    stems
+        .into_iter()
+        .map(|(id, stem)| match uses[&stem.to_lowercase()] {
+            1 => (id, stem),
+            _ => {
+                let short = id.to_string().chars().take(8).collect::<String>();
+                (id, format!("{} ({})", stem, short))
+            }
+        })
+        .collect()
+}
+
+// The title, made safe for a file name and a wikilink, or the id
+fn stem(thought: &Thought) -> String {
+    let title = thought.title.as_deref().unwrap_or("");
+    let cleaned = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>();
+    let words = cleaned.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let stem = words
+        .chars()
+        .take(MAX_STEM)
+        .collect::<String>()
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string();
+
+    match stem.is_empty() {
+        true => thought.id.to_string(),
+        false => stem,
+    }
+}
+
+///
+/// A note to import: a thought that isn't on the server yet.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Note {
+    /// Where it came from, for messages
+    pub source: String,
+    /// The file name without `.md`, which wikilinks to it use
+    pub name: Option<String>,
+    /// Its id where it was exported from, if it says
+    pub id: Option<ThoughtId>,
+    pub title: String,
+    pub body: String,
+    pub public: bool,
+    /// The id it linked to where it was exported from
+    pub links_to: Option<ThoughtId>,
+    /// Names in its `[[wikilinks]]`, in order
+    pub wikilinks: Vec<String>,
+}
+
+impl Note {
+    /// A thought from a JSON dump, as a note.
+    pub fn from_thought(thought: &Thought, source: &str) -> Note {
+        Note {
+            source: source.to_string(),
+            name: None,
+            id: Some(thought.id),
+            title: thought.title.clone().unwrap_or_default(),
+            body: thought.body.clone(),
+            public: thought.public,
+            links_to: thought.to_id,
+            wikilinks: wikilinks(&thought.body),
+        }
+    }
+
+    /// True if `thought` says the same thing, whitespace aside.
+    pub fn same_as(&self, thought: &Thought) -> bool {
+        thought.title.as_deref().unwrap_or("").trim() == self.title.trim()
+            && thought.body.trim() == self.body.trim()
+    }
+}
+
+///
+/// Parse a Markdown note, with or without front matter. Keys comode doesn't
+/// know, and YAML it can't read (lists, nesting), are skipped, so notes from
+/// other tools work. Without a `public` key the note gets `public`.
+///
+pub fn parse_note(text: &str, public: bool) -> Note {
+    let mut note = Note {
+        public,
+        ..Note::default()
+    };
+
+    let mut lines = text.lines().peekable();
+    if lines.peek().map(|line| line.trim()) == Some("---") {
+        lines.next();
+        let mut front = Vec::new();
+        let mut closed = false;
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                closed = true;
+                break;
+            }
+            front.push(line);
+        }
+
+        // Not front matter after all
+        if !closed {
+            note.body = text.trim().to_string();
+            note.wikilinks = wikilinks(&note.body);
+            return note;
+        }
+
+        for line in front {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) if !line.starts_with(char::is_whitespace) => {
+                    (key.trim(), unquote(value.trim()))
+                }
+                _ => continue,
+            };
+            match key {
+                "id" => note.id = value.parse().ok(),
+                "title" => note.title = value,
+                "public" => note.public = value != "false" && value != "no",
+                "private" => note.public = value == "false" || value == "no",
+                "links_to" => note.links_to = value.parse().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    // The link line `to_markdown` writes isn't part of the body
+    let mut body = lines.collect::<Vec<&str>>();
+    while body
+        .last()
+        .is_some_and(|line| line.trim().is_empty() || line.starts_with(LINK_MARKER))
+    {
+        body.pop();
+    }
+    note.body = body.join("\n").trim().to_string();
+    note.wikilinks = wikilinks(text);
+    note
+}
+
+// A YAML scalar without its quotes. Double-quoted ones are read as JSON,
+// which is what `to_markdown` writes.
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        if let Ok(value) = serde_json::from_str::<String>(value) {
+            return value;
+        }
+    }
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].replace("''", "'");
+    }
+    value.to_string()
+}
+
+///
+/// The note names in `[[wikilinks]]`, without any `|alias` or `#heading`.
+///
+pub fn wikilinks(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let end = match after.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let name = after[..end].split(['|', '#']).next().unwrap_or("").trim();
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+///
+/// What `read_notes` found.
+///
+#[derive(Debug, Default)]
+pub struct NoteFiles {
+    pub notes: Vec<Note>,
+    /// Files that couldn't be read, and why
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+///
+/// Notes to import from `path`: a folder of Markdown files (searched all the
+/// way down, skipping hidden folders like `.obsidian`), a single Markdown
+/// file, or a JSON or JSONL dump of thoughts. A Markdown note without a
+/// `title` is titled by its file name. Files that can't be read are
+/// returned with the reason, and don't stop the rest.
+///
+pub fn read_notes(path: &Path, public: bool) -> io::Result<NoteFiles> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        find_files(path, &mut files)?;
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut notes = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) => {
+                errors.push((file, e.to_string()));
+                continue;
+            }
+        };
+        let source = file.display().to_string();
+
+        match file.extension().and_then(|extension| extension.to_str()) {
+            Some("md") | Some("markdown") => {
+                let mut note = parse_note(&text, public);
+                note.source = source;
+                note.name = file
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string());
+
+                // In note tools the file name is the title, unless it's the
+                // id `export` names untitled thoughts by
+                let id = note.id.map(|id| id.to_string());
+                if note.title.trim().is_empty() && note.name != id {
+                    note.title = note.name.clone().unwrap_or_default();
+                }
+                notes.push(note);
+            }
+            Some("json") => {
+                // An array of thoughts, or just one
+                let thoughts = serde_json::from_str::<Vec<Thought>>(&text).or_else(|_| {
+                    serde_json::from_str::<Thought>(&text).map(|thought| vec![thought])
+                });
+                match thoughts {
+                    Ok(thoughts) => notes.extend(
+                        thoughts
+                            .iter()
+                            .map(|thought| Note::from_thought(thought, &source)),
+                    ),
+                    Err(e) => errors.push((file, e.to_string())),
+                }
+            }
+            Some("jsonl") => {
+                for (i, line) in text
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| !line.trim().is_empty())
+                {
+                    match serde_json::from_str::<Thought>(line) {
+                        Ok(thought) => notes.push(Note::from_thought(
+                            &thought,
+                            &format!("{}:{}", source, i + 1),
+                        )),
+                        Err(e) => errors.push((file.clone(), format!("line {}: {}", i + 1, e))),
+                    }
+                }
+            }
+            _ => errors.push((file, "not Markdown, JSON or JSONL".to_string())),
+        }
+    }
+
+    Ok(NoteFiles {
+        notes,
+        skipped: errors,
+    })
+}
+
+///
+/// Which notes to import, and which are already thoughts.
+///
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    /// Notes to make thoughts of
+    pub new: Vec<Note>,
+    /// Notes that are thoughts already, with the thought they match
+    pub existing: Vec<(Note, ThoughtId)>,
+    /// Notes that repeat an earlier note in the same import
+    pub repeated: usize,
+    /// Notes with neither title nor body
+    pub empty: usize,
+}
+
+///
+/// Sort `notes` into new ones and ones already among `existing`: the same
+/// id, or the same title and body. A note that matches a thought isn't
+/// imported even if it's been edited since, so importing again is safe.
+///
+pub fn plan_import(notes: Vec<Note>, existing: &[Thought]) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+
+    for note in notes {
+        if note.title.trim().is_empty() && note.body.trim().is_empty() {
+            plan.empty += 1;
+            continue;
+        }
+
+        let matched = existing
+            .iter()
+            .find(|thought| note.id == Some(thought.id) || note.same_as(thought));
+        if let Some(thought) = matched {
+            plan.existing.push((note, thought.id));
+            continue;
+        }
+
+        let repeat = plan.new.iter().any(|earlier| {
+            earlier.title.trim() == note.title.trim() && earlier.body.trim() == note.body.trim()
+        });
+        match repeat {
+            true => plan.repeated += 1,
+            false => plan.new.push(note),
+        }
+    }
+
+    plan
+}
+
+///
+/// What imported notes' links point at: note names, titles and the ids
+/// notes had before, each to the thought it is now.
+///
+#[derive(Debug, Default)]
+pub struct LinkTargets {
+    // See name_key
+    names: HashMap<String, ThoughtId>,
+    ids: HashMap<ThoughtId, ThoughtId>,
+}
+
+impl LinkTargets {
+    /// Targets for thoughts already on the server, by title and by the name
+    /// `export` gives their note.
+    pub fn new(existing: &[Thought]) -> LinkTargets {
+        let mut targets = LinkTargets::default();
+        for thought in existing {
+            if let Some(title) = thought
+                .title
+                .as_deref()
+                .filter(|title| !title.trim().is_empty())
+            {
+                targets.names.insert(name_key(title), thought.id);
+            }
+            targets.ids.insert(thought.id, thought.id);
+        }
+        for (id, name) in note_names(existing) {
+            targets.names.insert(name_key(&name), id);
+        }
+        targets
+    }
+
+    /// Links to `id` stay as they are: it's a thought on the server that
+    /// isn't one of the user's.
+    pub fn keep(&mut self, id: ThoughtId) {
+        self.ids.insert(id, id);
+    }
+
+    /// `note` is the thought `id` now. Its names win over existing titles,
+    /// since the notes link to each other.
+    pub fn add(&mut self, note: &Note, id: ThoughtId) {
+        for name in note.name.iter().chain(Some(&note.title)) {
+            if !name.trim().is_empty() {
+                self.names.insert(name_key(name), id);
+            }
+        }
+        if let Some(old) = note.id {
+            self.ids.insert(old, id);
+        }
+    }
+
+    ///
+    /// The thought `note` should link to: its `links_to`, moved to the new
+    /// id, or else the first of its wikilinks that names a thought.
+    ///
+    pub fn target(&self, note: &Note) -> Option<ThoughtId> {
+        let linked = note.links_to.and_then(|old| self.ids.get(&old));
+        let named = || {
+            note.wikilinks
+                .iter()
+                .find_map(|name| self.names.get(&name_key(name)))
+        };
+        linked.or_else(named).copied()
+    }
+
+    /// Wikilinks in `note` that don't name any thought.
+    pub fn unresolved<'a>(&self, note: &'a Note) -> Vec<&'a str> {
+        note.wikilinks
+            .iter()
+            .filter(|name| !self.names.contains_key(&name_key(name)))
+            .map(|name| name.as_str())
+            .collect()
+    }
+}
+
+// Names are matched without regard to case or spacing, as wikilinks aren't
+// fussy about either
+fn name_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Markdown and JSON files under `dir`, in a stable order
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+    entries.sort();
+
+    for path in entries {
+        let hidden = path
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            find_files(&path, files)?;
+            continue;
+        }
+        let wanted = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| ["md", "markdown", "json", "jsonl"].contains(&extension));
+        if wanted {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_thought;
+
+    // A folder of its own under the temp dir
+    fn scratch_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("comode-vault-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn untitled_notes_take_the_file_name() {
+        let dir = scratch_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let untitled = test_thought(7, "", "no title");
+        export(&dir, std::slice::from_ref(&untitled)).unwrap();
+        fs::write(dir.join("Plain note.md"), "Just text\n").unwrap();
+        fs::write(dir.join("Titled.md"), "---\ntitle: Its own\n---\nText\n").unwrap();
+
+        let files = read_notes(&dir, true).unwrap();
+        let titles = files
+            .notes
+            .iter()
+            .map(|note| note.title.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(titles, ["", "Plain note", "Its own"]);
+        assert_eq!(files.notes[0].id, Some(untitled.id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn names_shared_titles_apart() {
+        let a = test_thought(1, "Same", "a");
+        let b = test_thought(2, "same", "b");
+        let c = test_thought(3, "What: now?", "c");
+        let d = test_thought(4, "", "d");
+        let names = note_names(&[a.clone(), b.clone(), c.clone(), d.clone()]);
+
+        assert_eq!(names[&a.id], "Same (00000000)");
+        assert_eq!(names[&b.id], "same (00000000)");
+        assert_eq!(names[&c.id], "What now");
+        assert_eq!(names[&d.id], d.id.to_string());
+    }
+
+    #[test]
+    fn export_again_writes_only_changes() {
+        let dir = scratch_dir();
+        let mut a = test_thought(1, "Alpha", "a");
+        let b = test_thought(2, "Beta", "b");
+        a.to_id = Some(b.id);
+
+        let first = export(&dir, &[a.clone(), b.clone()]).unwrap();
+        assert_eq!(first.written, 2);
+        let note = fs::read_to_string(dir.join("Alpha.md")).unwrap();
+        assert!(note.ends_with("\n→ [[Beta]]\n"));
+
+        let second = export(&dir, &[a, b]).unwrap();
+        assert_eq!(second.written, 0);
+        assert_eq!(second.unchanged, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn export_keeps_files_when_titles_swap() {
+        let dir = scratch_dir();
+        let mut a = test_thought(1, "Alpha", "a");
+        let mut b = test_thought(2, "Beta", "b");
+        export(&dir, &[a.clone(), b.clone()]).unwrap();
+
+        a.title = Some("Beta".to_string());
+        b.title = Some("Alpha".to_string());
+        a.date_updated += chrono::Duration::seconds(1);
+        b.date_updated += chrono::Duration::seconds(1);
+        let report = export(&dir, &[a, b]).unwrap();
+
+        assert_eq!(report.renamed, 2);
+        let alpha = fs::read_to_string(dir.join("Alpha.md")).unwrap();
+        let beta = fs::read_to_string(dir.join("Beta.md")).unwrap();
+        assert!(alpha.ends_with("---\nb\n"));
+        assert!(beta.ends_with("---\na\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn notes_read_back_what_export_wrote() {
+        let mut thought = test_thought(1, "Quote \"this\": here", "Body\n\nwith [[Other|alias]]");
+        thought.public = false;
+        thought.to_id = Some(ThoughtId(uuid::Uuid::from_u128(2)));
+        let note = parse_note(&to_markdown(&thought, &["Other".to_string()]), true);
+
+        assert_eq!(note.id, Some(thought.id));
+        assert_eq!(note.title, thought.title.unwrap());
+        assert_eq!(note.body, thought.body);
+        assert!(!note.public);
+        assert_eq!(note.links_to, thought.to_id);
+        assert_eq!(note.wikilinks, ["Other", "Other"]);
+    }
+
+    #[test]
+    fn notes_from_other_tools_parse() {
+        let note = parse_note(
+            "---\ntitle: 'It''s'\ntags:\n  - a\nprivate: yes\n---\nText [[Somewhere#Heading]]\n",
+            true,
+        );
+        assert_eq!(note.title, "It's");
+        assert!(!note.public);
+        assert_eq!(note.body, "Text [[Somewhere#Heading]]");
+        assert_eq!(note.wikilinks, ["Somewhere"]);
+
+        // A rule with no end isn't front matter
+        let note = parse_note("---\nJust a rule\n", false);
+        assert_eq!(note.title, "");
+        assert_eq!(note.body, "---\nJust a rule");
+        assert!(!note.public);
+    }
+
+    #[test]
+    fn plan_skips_what_is_there_already() {
+        let there = test_thought(1, "There", "already");
+        let note = |title: &str, body: &str| Note {
+            title: title.to_string(),
+            body: body.to_string(),
+            ..Note::default()
+        };
+        let by_id = Note {
+            id: Some(there.id),
+            ..note("Edited", "since")
+        };
+
+        let plan = plan_import(
+            vec![
+                note(" There", "already\n"),
+                by_id,
+                note("New", "one"),
+                note("New", " one"),
+                note("", " "),
+            ],
+            std::slice::from_ref(&there),
+        );
+        assert_eq!(plan.existing.len(), 2);
+        assert!(plan.existing.iter().all(|(_, id)| *id == there.id));
+        assert_eq!(plan.new, [note("New", "one")]);
+        assert_eq!(plan.repeated, 1);
+        assert_eq!(plan.empty, 1);
+    }
+
+    #[test]
+    fn links_go_to_the_new_thoughts() {
+        let there = test_thought(1, "Existing Title", "");
+        let new_id = ThoughtId(uuid::Uuid::from_u128(10));
+        let old_id = ThoughtId(uuid::Uuid::from_u128(20));
+        let elsewhere = ThoughtId(uuid::Uuid::from_u128(30));
+        let mut targets = LinkTargets::new(std::slice::from_ref(&there));
+        targets.keep(elsewhere);
+        targets.add(
+            &Note {
+                name: Some("new-note".to_string()),
+                id: Some(old_id),
+                title: "New".to_string(),
+                ..Note::default()
+            },
+            new_id,
+        );
+
+        let linking = |links_to: Option<ThoughtId>, wikilinks: &[&str]| Note {
+            links_to,
+            wikilinks: wikilinks.iter().map(|name| name.to_string()).collect(),
+            ..Note::default()
+        };
+        assert_eq!(targets.target(&linking(Some(old_id), &[])), Some(new_id));
+        assert_eq!(
+            targets.target(&linking(Some(elsewhere), &[])),
+            Some(elsewhere)
+        );
+        assert_eq!(
+            targets.target(&linking(None, &["nowhere", "existing  title"])),
+            Some(there.id)
+        );
+        assert_eq!(targets.target(&linking(None, &["New-Note"])), Some(new_id));
+
+        let note = linking(None, &["nowhere", "new"]);
+        assert_eq!(targets.unresolved(&note), ["nowhere"]);
+    }
+}
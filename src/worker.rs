@@ -0,0 +1,175 @@
+// Background network worker
+//
+// Network calls are blocking, so each request runs on its own thread and the
+// result comes back to the UI loop as an `Action` over a channel. The UI loop
+// never waits on the network.
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use comode::client::blocking::Client;
+use comode::follow::{self, Change};
+use comode::outbox::OutboxItem;
+use comode::profile::Who;
+use comode::store::Store;
+use comode::sync::{self, SyncState};
+use comode::types::User;
+use comode::vault::{self, ExportReport};
+
+use crate::app::Action;
+
+/// Identifies a request, so results can be matched up or cancelled.
+pub type JobId = u64;
+
+///
+/// Network requests the app can make.
+///
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    FetchThoughts,
+    FetchPings,
+    // Someone else's profile
+    FetchProfile(Who),
+    // Thoughts of the people you follow, and follows to push
+    FetchFeed(Vec<String>),
+    PushFollows(Vec<Change>),
+    Send(OutboxItem),
+    // Fetch thoughts and push local edits
+    Sync(SyncState),
+    // Every page of thoughts, written to a folder as Markdown
+    Export(String),
+}
+
+pub struct Worker {
+    client: Client,
+    user: Arc<User>,
+    store: Option<Store>,
+    results: Sender<Action>,
+    cancelled: Arc<Mutex<HashSet<JobId>>>,
+}
+
+impl Worker {
+    ///
+    /// Start a worker that makes requests as `user` with `client`. Results
+    /// arrive on the returned receiver, and fetched thoughts, pings and feeds
+    /// are saved to `store`.
+    ///
+    pub fn new(client: Client, user: User, store: Option<Store>) -> (Worker, Receiver<Action>) {
+        let (results, receiver) = channel();
+        let worker = Worker {
+            client,
+            user: Arc::new(user),
+            store,
+            results,
+            cancelled: Arc::new(Mutex::new(HashSet::new())),
+        };
+
+        (worker, receiver)
+    }
+
+    ///
+    /// Run a request in the background.
+    ///
+    pub fn spawn(&self, job: JobId, request: Request) {
+        let client = self.client.clone();
+        let user = Arc::clone(&self.user);
+        let store = self.store.clone();
+        let results = self.results.clone();
+        let cancelled = Arc::clone(&self.cancelled);
+
+        thread::spawn(move || {
+            let action = match request {
+                Request::FetchThoughts => {
+                    let thoughts = client.get_user_thoughts(&user.username, None, None);
+                    if let (Some(store), Ok(thoughts)) = (&store, &thoughts) {
+                        let _ = store.save_thoughts(thoughts);
+                    }
+                    Action::ThoughtsLoaded { job, thoughts }
+                }
+                Request::FetchPings => {
+                    let pings = client.get_pings();
+                    if let (Some(store), Ok(pings)) = (&store, &pings) {
+                        let _ = store.save_pings(pings);
+                    }
+                    Action::PingsLoaded { job, pings }
+                }
+                Request::FetchProfile(who) => Action::ProfileLoaded {
+                    job,
+                    profile: client.get_profile(&who),
+                },
+                Request::FetchFeed(users) => {
+                    let feed = follow::feed(&client, &users);
+                    if let (Some(store), true) = (&store, feed.errors.is_empty()) {
+                        let _ = store.save_feed(&feed.thoughts);
+                    }
+                    Action::FeedLoaded { job, feed }
+                }
+                Request::PushFollows(changes) => Action::FollowsPushed {
+                    job,
+                    outcome: follow::push(&client, &changes),
+                },
+                Request::Sync(state) => {
+                    let outcome = sync::run(&client, &user.username, &state);
+                    if let (Some(store), Ok(outcome)) = (&store, &outcome) {
+                        let _ = store.save_thoughts(&outcome.thoughts);
+                    }
+                    Action::Synced { job, outcome }
+                }
+                Request::Send(item) => Action::OutboxSent {
+                    job,
+                    local_id: item.local_id,
+                    result: client.make_new_thought(
+                        &user.user_id,
+                        &item.title,
+                        &item.body,
+                        item.public,
+                    ),
+                },
+                Request::Export(dir) => {
+                    let result = export(&client, &user.username, &dir);
+                    Action::Exported { job, dir, result }
+                }
+            };
+
+            // Drop results nobody wants any more
+            let was_cancelled = match cancelled.lock() {
+                Ok(mut cancelled) => cancelled.remove(&job),
+                Err(_) => false,
+            };
+            if !was_cancelled {
+                // The UI may have exited already, which is fine
+                let _ = results.send(action);
+            }
+        });
+    }
+
+    ///
+    /// Cancel a request. The HTTP call itself can't be interrupted, but its
+    /// result is thrown away.
+    ///
+    pub fn cancel(&self, job: JobId) {
+        if let Ok(mut cancelled) = self.cancelled.lock() {
+            cancelled.insert(job);
+        }
+    }
+}
+
+// Fetch every page of thoughts and export them. A page that couldn't be
+// fetched stops the export, as its thoughts would look deleted to it.
+fn export(client: &Client, username: &str, dir: &str) -> Result<(usize, ExportReport), String> {
+    let thoughts = client
+        .get_all_user_thoughts(username)
+        .map_err(|e| format!("couldn't fetch your thoughts to export: {}", e))?;
+
+    // `~/` is the home folder, as the shell isn't there to expand it
+    let path = match (dir.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(dir),
+    };
+    match vault::export(&path, &thoughts) {
+        Ok(report) => Ok((thoughts.len(), report)),
+        Err(e) => Err(format!("couldn't export to {}: {}", dir, e)),
+    }
+}